@@ -1,8 +1,10 @@
+use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{
-    Addr, BlockInfo, Deps, MessageInfo, Order, StdError, StdResult, Storage, Uint128,
+    Addr, BlockInfo, Deps, MessageInfo, Order, StdError, StdResult, Storage, Timestamp, Uint128,
 };
 use cw20::Expiration;
-use cw_storage_plus::{Bound, Index, IndexList, IndexedMap, Item, MultiIndex};
+use cw_storage_plus::{Bound, Index, IndexList, IndexedMap, Item, Map, MultiIndex};
+use cw_utils::Duration;
 use cw_vault_standard::extensions::lockup::UnlockingPosition;
 
 // Settings for pagination
@@ -11,6 +13,65 @@ const DEFAULT_LIMIT: u32 = 10;
 /// An unlocking position for a user that can be claimed once it has matured.
 pub type Claim = UnlockingPosition;
 
+/// The block at which a claim was created.
+#[cw_serde]
+pub struct ClaimCreatedAt {
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+impl ClaimCreatedAt {
+    /// Returns when a claim created at this block is released if it is locked
+    /// for `lock_duration`.
+    pub fn release_at(&self, lock_duration: Duration) -> Expiration {
+        match lock_duration {
+            Duration::Height(blocks) => Expiration::AtHeight(self.height + blocks),
+            Duration::Time(seconds) => Expiration::AtTime(self.time.plus_seconds(seconds)),
+        }
+    }
+}
+
+impl From<&BlockInfo> for ClaimCreatedAt {
+    fn from(block: &BlockInfo) -> Self {
+        Self {
+            height: block.height,
+            time: block.time,
+        }
+    }
+}
+
+/// A conversion of all claims to a new base token, which is applied to existing
+/// claims in batches.
+#[cw_serde]
+pub struct ClaimsRescale {
+    /// The amount of new base tokens per `denominator` old base tokens.
+    pub numerator: Uint128,
+    /// The amount of old base tokens per `numerator` new base tokens.
+    pub denominator: Uint128,
+    /// Claims with lower ids were created before the conversion and are
+    /// denominated in the old base token until they are rescaled.
+    pub end: u64,
+    /// The id of the last claim that was rescaled, if any.
+    pub start_after: Option<u64>,
+    /// The total amount of old base tokens in the claims that have not been
+    /// rescaled yet.
+    pub unscaled_amount: Uint128,
+}
+
+impl ClaimsRescale {
+    /// Returns true if the claim with id `id` has not been rescaled yet.
+    fn is_pending(&self, id: u64) -> bool {
+        id < self.end && self.start_after.is_none_or(|start_after| id > start_after)
+    }
+
+    /// Converts `amount` old base tokens to new base tokens, rounding down.
+    fn scale(&self, amount: Uint128) -> StdResult<Uint128> {
+        amount
+            .checked_multiply_ratio(self.numerator, self.denominator)
+            .map_err(|e| StdError::generic_err(e.to_string()))
+    }
+}
+
 /// A struct for handling the addition and removal of claims, as well as
 /// querying and force unlocking of claims.
 pub struct Claims<'a> {
@@ -18,11 +79,22 @@ pub struct Claims<'a> {
     /// is claimed by its owner after it has matured, it is removed from this
     /// map.
     claims: IndexedMap<'a, u64, Claim, ClaimIndexes<'a>>,
+    /// The block at which each claim was created. Claims created before this
+    /// was stored have no entry.
+    created_at: Map<'a, u64, ClaimCreatedAt>,
+    /// The lock tier from which each claim was unlocked. Claims of the vault
+    /// token have no entry.
+    lock_tiers: Map<'a, u64, u32>,
     // Counter of the number of claims. Used as a default value for the ID of a new
     // claim. This is monotonically increasing and is not decremented when a claim is removed
     // It represents the number of claims that have been created since creation of the `Claims`
     // instance.
     next_claim_id: Item<'a, u64>,
+    /// The total amount of base tokens in all currently unclaimed claims.
+    total_amount: Item<'a, Uint128>,
+    /// The conversion to a new base token that is still being applied to
+    /// existing claims, if any.
+    rescale: Item<'a, ClaimsRescale>,
 }
 
 /// Helper struct for indexing claims. Needed by the [`IndexedMap`]
@@ -30,26 +102,55 @@ pub struct Claims<'a> {
 pub struct ClaimIndexes<'a> {
     /// Index mapping an address to all claims for that address.
     pub owner: MultiIndex<'a, Addr, Claim, u64>,
+    /// Index mapping the release time of claims, as returned by
+    /// [`release_at_key`], to all claims released at that time.
+    pub release_at: MultiIndex<'a, (u8, u64), Claim, u64>,
 }
 
 impl<'a> IndexList<Claim> for ClaimIndexes<'a> {
     fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Claim>> + '_> {
-        let v: Vec<&dyn Index<Claim>> = vec![&self.owner];
+        let v: Vec<&dyn Index<Claim>> = vec![&self.owner, &self.release_at];
         Box::new(v.into_iter())
     }
 }
 
+/// Returns the key of `expiration` in the `release_at` index. Expirations at a
+/// height and at a time are kept apart by the first element, so that claims
+/// are ordered by release height or time within each kind.
+pub fn release_at_key(expiration: &Expiration) -> (u8, u64) {
+    match expiration {
+        Expiration::AtHeight(height) => (0, *height),
+        Expiration::AtTime(time) => (1, time.nanos()),
+        Expiration::Never {} => (2, 0),
+    }
+}
+
 impl<'a> Claims<'a> {
     /// Create a new Claims instance
     ///
     /// ## Arguments
     /// * `claims_namespace` - The key to use for the the primary key (u64
     ///   lockup ID)
+    /// * `claims_index_namespace` - The key to use for the owner index
+    /// * `release_at_index_namespace` - The key to use for the release time
+    ///   index
+    /// * `created_at_namespace` - The key to use for the creation blocks of
+    ///   claims
+    /// * `lock_tier_namespace` - The key to use for the lock tiers of claims
     /// * `num_claims_key` - The key to use for the index value (owner addr)
+    /// * `total_amount_key` - The key to use for the total amount of base
+    ///   tokens in all claims
+    /// * `rescale_key` - The key to use for the pending conversion of claims to
+    ///   a new base token
     pub fn new(
         claims_namespace: &'a str,
         claims_index_namespace: &'a str,
+        release_at_index_namespace: &'a str,
+        created_at_namespace: &'a str,
+        lock_tier_namespace: &'a str,
         num_claims_key: &'a str,
+        total_amount_key: &'a str,
+        rescale_key: &'a str,
     ) -> Self {
         let indexes = ClaimIndexes {
             owner: MultiIndex::new(
@@ -57,21 +158,111 @@ impl<'a> Claims<'a> {
                 claims_namespace,
                 claims_index_namespace,
             ),
+            release_at: MultiIndex::new(
+                |_pk, d| release_at_key(&d.release_at),
+                claims_namespace,
+                release_at_index_namespace,
+            ),
         };
 
         Self {
             claims: IndexedMap::new(claims_namespace, indexes),
+            created_at: Map::new(created_at_namespace),
+            lock_tiers: Map::new(lock_tier_namespace),
             next_claim_id: Item::new(num_claims_key),
+            total_amount: Item::new(total_amount_key),
+            rescale: Item::new(rescale_key),
+        }
+    }
+
+    /// Returns an error if the claim with id `id` has not been converted to
+    /// the new base token yet, so that its amount can not be changed.
+    fn assert_rescaled(&self, storage: &dyn Storage, id: u64) -> StdResult<()> {
+        match self.rescale.may_load(storage)? {
+            Some(rescale) if rescale.is_pending(id) => Err(StdError::generic_err(format!(
+                "Claim {} has not been converted to the new base token yet",
+                id
+            ))),
+            _ => Ok(()),
         }
     }
 
+    /// Returns `claims` with the amounts of the claims that have not been
+    /// rescaled yet converted to the new base token.
+    fn rescaled(
+        &self,
+        storage: &dyn Storage,
+        mut claims: Vec<(u64, Claim)>,
+    ) -> StdResult<Vec<(u64, Claim)>> {
+        if let Some(rescale) = self.rescale.may_load(storage)? {
+            for (id, claim) in claims.iter_mut() {
+                if rescale.is_pending(*id) {
+                    claim.base_token_amount = rescale.scale(claim.base_token_amount)?;
+                }
+            }
+        }
+        Ok(claims)
+    }
+
+    /// Adds `amount` to the total amount of base tokens in all claims if
+    /// `increase` is true, else subtracts it.
+    fn update_total_amount(
+        &self,
+        storage: &mut dyn Storage,
+        amount: Uint128,
+        increase: bool,
+    ) -> StdResult<()> {
+        let total = self.query_total_amount(storage)?;
+        let total = if increase {
+            total.checked_add(amount)?
+        } else {
+            total.checked_sub(amount)?
+        };
+        self.total_amount.save(storage, &total)
+    }
+
+    /// Initializes the total amount of base tokens in all claims by summing
+    /// the existing claims, if it has not been stored yet. Used when migrating
+    /// from a version that did not track the total.
+    pub fn migrate_total_amount(&self, storage: &mut dyn Storage) -> StdResult<()> {
+        if self.total_amount.may_load(storage)?.is_some() {
+            return Ok(());
+        }
+        let total = self
+            .claims
+            .range(storage, None, None, Order::Ascending)
+            .map(|res| res.map(|(_, claim)| claim.base_token_amount))
+            .sum::<StdResult<Uint128>>()?;
+        self.total_amount.save(storage, &total)
+    }
+
+    /// Saves all claims again, so that they are added to indexes that did not
+    /// exist when they were created. Used when migrating from a version
+    /// without the `release_at` index.
+    pub fn migrate_indexes(&self, storage: &mut dyn Storage) -> StdResult<()> {
+        let claims = self
+            .claims
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for (id, claim) in claims {
+            self.claims.save(storage, id, &claim)?;
+        }
+        Ok(())
+    }
+
     /// Create a new claim and save it to the claims map.
+    ///
+    /// ## Arguments
+    /// * `release_at` - When the claim matures
+    /// * `created_at` - The current block, from which the release time is
+    ///   recomputed if the lock duration is shortened
     pub fn create_claim(
         &self,
         storage: &mut dyn Storage,
         addr: &Addr,
         amount: Uint128,
         release_at: Expiration,
+        created_at: &BlockInfo,
     ) -> StdResult<Claim> {
         let id = self.next_claim_id.may_load(storage)?.unwrap_or_default();
 
@@ -85,6 +276,8 @@ impl<'a> Claims<'a> {
         };
 
         self.claims.save(storage, id, &claim)?;
+        self.created_at.save(storage, id, &created_at.into())?;
+        self.update_total_amount(storage, amount, true)?;
 
         Ok(claim)
     }
@@ -112,6 +305,7 @@ impl<'a> Claims<'a> {
         if claim.owner != info.sender {
             return Err(StdError::generic_err("Claim not owned by sender"));
         }
+        self.assert_rescaled(storage, id)?;
 
         // Check if the claim is expired
         if !claim.release_at.is_expired(block) {
@@ -119,11 +313,59 @@ impl<'a> Claims<'a> {
         }
 
         // Remove the claim from the map
-        self.claims.remove(storage, id)?;
+        self.remove_claim(storage, id)?;
+        self.update_total_amount(storage, claim.base_token_amount, false)?;
 
         Ok(claim.base_token_amount)
     }
 
+    /// Redeem up to `limit` matured claims of `owner` for the underlying
+    /// tokens, in order of their ids. If `all_matured` is true, all claims are
+    /// treated as matured regardless of their release.
+    ///
+    /// ## Returns
+    /// Returns the ids of the removed claims and the total amount of tokens
+    /// redeemed. Returns an error if `owner` has no matured claims.
+    pub fn claim_all_matured(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        owner: &Addr,
+        limit: Option<u32>,
+        all_matured: bool,
+    ) -> StdResult<(Vec<u64>, Uint128)> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT) as usize;
+
+        let matured = self
+            .claims
+            .idx
+            .owner
+            .prefix(owner.clone())
+            .range(storage, None, None, Order::Ascending)
+            .filter(|res| {
+                res.as_ref().map_or(true, |(_, claim)| {
+                    all_matured || claim.release_at.is_expired(block)
+                })
+            })
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?;
+        if matured.is_empty() {
+            return Err(StdError::generic_err("No matured claims to withdraw."));
+        }
+
+        let mut ids = Vec::with_capacity(matured.len());
+        let mut total = Uint128::zero();
+        for (id, claim) in matured {
+            self.assert_rescaled(storage, id)?;
+            self.remove_claim(storage, id)?;
+            ids.push(id);
+            total = total.checked_add(claim.base_token_amount)?;
+        }
+        self.update_total_amount(storage, total, false)?;
+
+        Ok((ids, total))
+    }
+
     /// Bypass expiration and claim `claim_amount`. Should only be called if the
     /// caller is whitelisted. Will return an error if the claim does not exist
     /// or if the caller is not the owner of the claim.
@@ -133,6 +375,35 @@ impl<'a> Claims<'a> {
         info: &MessageInfo,
         lock_id: u64,
         claim_amount: Option<Uint128>,
+    ) -> StdResult<Uint128> {
+        self.reduce_claim(storage, info, lock_id, claim_amount)
+    }
+
+    /// Cancel `cancel_amount` of a claim, or the whole claim if
+    /// `cancel_amount` is `None`, regardless of whether it has matured. Will
+    /// return an error if the claim does not exist or if the caller is not the
+    /// owner of the claim.
+    ///
+    /// ## Returns
+    /// Returns the amount of tokens removed from the claim.
+    pub fn cancel_claim(
+        &self,
+        storage: &mut dyn Storage,
+        info: &MessageInfo,
+        lock_id: u64,
+        cancel_amount: Option<Uint128>,
+    ) -> StdResult<Uint128> {
+        self.reduce_claim(storage, info, lock_id, cancel_amount)
+    }
+
+    /// Removes `amount` from the claim with id `lock_id`, or the whole claim if
+    /// `amount` is `None`, and removes the claim if nothing is left.
+    fn reduce_claim(
+        &self,
+        storage: &mut dyn Storage,
+        info: &MessageInfo,
+        lock_id: u64,
+        amount: Option<Uint128>,
     ) -> StdResult<Uint128> {
         let mut lockup = self.claims.load(storage, lock_id)?;
 
@@ -140,10 +411,11 @@ impl<'a> Claims<'a> {
         if lockup.owner != info.sender {
             return Err(StdError::generic_err("Claim not owned by sender"));
         }
+        self.assert_rescaled(storage, lock_id)?;
 
         let claimable_amount = lockup.base_token_amount;
 
-        let claimed = claim_amount.unwrap_or(claimable_amount);
+        let claimed = amount.unwrap_or(claimable_amount);
 
         let left_after_claim = claimable_amount.checked_sub(claimed).map_err(|x| {
             StdError::generic_err(format!(
@@ -156,17 +428,324 @@ impl<'a> Claims<'a> {
             lockup.base_token_amount = left_after_claim;
             self.claims.save(storage, lock_id, &lockup)?;
         } else {
-            self.claims.remove(storage, lock_id)?;
+            self.remove_claim(storage, lock_id)?;
         }
+        self.update_total_amount(storage, claimed, false)?;
 
         Ok(claimed)
     }
 
+    /// Transfer the claim with id `lock_id` to `new_owner`. Will return an
+    /// error if the claim does not exist or if the caller is not the owner of
+    /// the claim.
+    ///
+    /// ## Returns
+    /// Returns the claim as it was before the transfer.
+    pub fn transfer_claim(
+        &self,
+        storage: &mut dyn Storage,
+        info: &MessageInfo,
+        lock_id: u64,
+        new_owner: &Addr,
+    ) -> StdResult<Claim> {
+        let claim = self.claims.load(storage, lock_id)?;
+
+        // Ensure the claim is owned by the sender
+        if claim.owner != info.sender {
+            return Err(StdError::generic_err("Claim not owned by sender"));
+        }
+
+        // Saving through the `IndexedMap` also updates the owner index
+        self.claims.save(
+            storage,
+            lock_id,
+            &Claim {
+                owner: new_owner.clone(),
+                ..claim.clone()
+            },
+        )?;
+
+        Ok(claim)
+    }
+
+    /// Sets the owner of the claim with id `lock_id` to `owner`, without
+    /// checking the current owner. Used when the owner is tracked elsewhere,
+    /// e.g. by an NFT contract.
+    pub fn set_owner(
+        &self,
+        storage: &mut dyn Storage,
+        lock_id: u64,
+        owner: &Addr,
+    ) -> StdResult<()> {
+        let claim = self.claims.load(storage, lock_id)?;
+        if claim.owner == owner {
+            return Ok(());
+        }
+
+        // Saving through the `IndexedMap` also updates the owner index
+        self.claims.save(
+            storage,
+            lock_id,
+            &Claim {
+                owner: owner.clone(),
+                ..claim
+            },
+        )
+    }
+
+    /// Split `amount` off the claim with id `lock_id` into a new claim for
+    /// `new_owner` with the same release time. The amounts of the two claims
+    /// sum to the amount of the original claim. Will return an error if the
+    /// claim does not exist, if the caller is not the owner of the claim or if
+    /// `amount` is not less than the amount of the claim.
+    ///
+    /// ## Returns
+    /// Returns the new claim.
+    pub fn split_claim(
+        &self,
+        storage: &mut dyn Storage,
+        info: &MessageInfo,
+        lock_id: u64,
+        amount: Uint128,
+        new_owner: &Addr,
+    ) -> StdResult<Claim> {
+        let mut claim = self.claims.load(storage, lock_id)?;
+
+        // Ensure the claim is owned by the sender
+        if claim.owner != info.sender {
+            return Err(StdError::generic_err("Claim not owned by sender"));
+        }
+        self.assert_rescaled(storage, lock_id)?;
+
+        if amount.is_zero() || amount >= claim.base_token_amount {
+            return Err(StdError::generic_err(
+                "Split amount must be greater than zero and less than the claim amount",
+            ));
+        }
+
+        claim.base_token_amount -= amount;
+        self.claims.save(storage, lock_id, &claim)?;
+
+        let id = self.next_claim_id.may_load(storage)?.unwrap_or_default();
+        self.next_claim_id.save(storage, &(id + 1))?;
+
+        let new_claim = Claim {
+            owner: new_owner.clone(),
+            id,
+            release_at: claim.release_at,
+            base_token_amount: amount,
+        };
+        self.claims.save(storage, id, &new_claim)?;
+        if let Some(created_at) = self.created_at.may_load(storage, lock_id)? {
+            self.created_at.save(storage, id, &created_at)?;
+        }
+        if let Some(lock_tier) = self.lock_tiers.may_load(storage, lock_id)? {
+            self.lock_tiers.save(storage, id, &lock_tier)?;
+        }
+
+        Ok(new_claim)
+    }
+
+    /// Removes the claim with id `id`, its creation block and its lock tier.
+    fn remove_claim(&self, storage: &mut dyn Storage, id: u64) -> StdResult<()> {
+        self.claims.remove(storage, id)?;
+        self.created_at.remove(storage, id);
+        self.lock_tiers.remove(storage, id);
+        Ok(())
+    }
+
+    /// Records that the claim with id `id` was unlocked from `lock_tier`.
+    pub fn set_lock_tier(
+        &self,
+        storage: &mut dyn Storage,
+        id: u64,
+        lock_tier: u32,
+    ) -> StdResult<()> {
+        self.lock_tiers.save(storage, id, &lock_tier)
+    }
+
+    /// Returns the lock tier from which the claim with id `id` was unlocked, or
+    /// `None` if it is a claim of the vault token.
+    pub fn query_lock_tier(&self, storage: &dyn Storage, id: u64) -> StdResult<Option<u32>> {
+        self.lock_tiers.may_load(storage, id)
+    }
+
+    /// Moves the release time of up to `limit` claims with ids greater than
+    /// `start_after` and less than `end` forward to their creation block plus
+    /// `lock_duration`, if that is earlier. Claims without a stored creation
+    /// block, claims whose release time is in a different unit than
+    /// `lock_duration`, and claims unlocked from a lock tier, which have their
+    /// own lock duration, are not changed.
+    ///
+    /// ## Returns
+    /// Returns the ids of the changed claims, and the id of the last claim that
+    /// was processed or `None` if no claims are left to process.
+    pub fn shorten_claims(
+        &self,
+        storage: &mut dyn Storage,
+        lock_duration: Duration,
+        end: u64,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<(Vec<u64>, Option<u64>)> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT) as usize;
+        let start: Option<Bound<u64>> = start_after.map(Bound::exclusive);
+
+        let claims = self
+            .claims
+            .range(
+                storage,
+                start,
+                Some(Bound::exclusive(end)),
+                Order::Ascending,
+            )
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?;
+        let last = if claims.len() < limit {
+            None
+        } else {
+            claims.last().map(|(id, _)| *id)
+        };
+
+        let mut shortened = vec![];
+        for (id, mut claim) in claims {
+            if self.lock_tiers.has(storage, id) {
+                continue;
+            }
+            let Some(created_at) = self.created_at.may_load(storage, id)? else {
+                continue;
+            };
+            let release_at = created_at.release_at(lock_duration);
+            if release_at < claim.release_at {
+                // Saving through the `IndexedMap` also updates the release index
+                claim.release_at = release_at;
+                self.claims.save(storage, id, &claim)?;
+                shortened.push(id);
+            }
+        }
+
+        Ok((shortened, last))
+    }
+
+    /// Starts converting all claims to a new base token, of which there are
+    /// `numerator` per `denominator` old base tokens. The total amount of base
+    /// tokens in all claims is converted immediately, rounding down, while the
+    /// existing claims are converted in batches by [`Self::apply_rescale`].
+    /// Until then, their amounts can not be changed. Returns an error if a
+    /// previous conversion has not been completed.
+    ///
+    /// ## Returns
+    /// Returns the new total amount of base tokens in all claims.
+    pub fn start_rescale(
+        &self,
+        storage: &mut dyn Storage,
+        numerator: Uint128,
+        denominator: Uint128,
+    ) -> StdResult<Uint128> {
+        if self.rescale.may_load(storage)?.is_some() {
+            return Err(StdError::generic_err(
+                "Claims are still being converted to the current base token",
+            ));
+        }
+
+        let rescale = ClaimsRescale {
+            numerator,
+            denominator,
+            end: self.next_claim_id.may_load(storage)?.unwrap_or_default(),
+            start_after: None,
+            unscaled_amount: self.query_total_amount(storage)?,
+        };
+        let total = rescale.scale(rescale.unscaled_amount)?;
+        self.total_amount.save(storage, &total)?;
+        if !rescale.unscaled_amount.is_zero() {
+            self.rescale.save(storage, &rescale)?;
+        }
+
+        Ok(total)
+    }
+
+    /// Converts up to `limit` claims that have not been converted to the new
+    /// base token yet, rounding down. Rounding dust is removed from the total
+    /// amount of base tokens in all claims.
+    ///
+    /// ## Returns
+    /// Returns the ids of the converted claims, the amount of rounding dust
+    /// and whether all claims have been converted. Returns an error if no
+    /// conversion is pending.
+    pub fn apply_rescale(
+        &self,
+        storage: &mut dyn Storage,
+        limit: Option<u32>,
+    ) -> StdResult<(Vec<u64>, Uint128, bool)> {
+        let mut rescale = self.rescale.load(storage)?;
+        let limit = limit.unwrap_or(DEFAULT_LIMIT) as usize;
+        let start: Option<Bound<u64>> = rescale.start_after.map(Bound::exclusive);
+
+        let claims = self
+            .claims
+            .range(
+                storage,
+                start,
+                Some(Bound::exclusive(rescale.end)),
+                Order::Ascending,
+            )
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?;
+        let done = claims.len() < limit;
+
+        // The total amount holds the converted amount of all unconverted claims
+        // together, which is at least the sum of their individually converted
+        // amounts. The difference is dust.
+        let mut ids = Vec::with_capacity(claims.len());
+        let mut dust = Uint128::zero();
+        for (id, mut claim) in claims {
+            let reserved = rescale.scale(rescale.unscaled_amount)?;
+            rescale.unscaled_amount = rescale
+                .unscaled_amount
+                .checked_sub(claim.base_token_amount)?;
+            let released = reserved.checked_sub(rescale.scale(rescale.unscaled_amount)?)?;
+
+            claim.base_token_amount = rescale.scale(claim.base_token_amount)?;
+            dust = dust.checked_add(released.checked_sub(claim.base_token_amount)?)?;
+            self.claims.save(storage, id, &claim)?;
+            rescale.start_after = Some(id);
+            ids.push(id);
+        }
+        if done {
+            dust = dust.checked_add(rescale.scale(rescale.unscaled_amount)?)?;
+            self.rescale.remove(storage);
+        } else {
+            self.rescale.save(storage, &rescale)?;
+        }
+        self.update_total_amount(storage, dust, false)?;
+
+        Ok((ids, dust, done))
+    }
+
     // ========== Query functions ==========
 
+    /// Returns the total amount of base tokens in all currently unclaimed
+    /// claims.
+    pub fn query_total_amount(&self, storage: &dyn Storage) -> StdResult<Uint128> {
+        Ok(self.total_amount.may_load(storage)?.unwrap_or_default())
+    }
+
+    /// Returns the id that the next created claim will have.
+    pub fn query_next_claim_id(&self, storage: &dyn Storage) -> StdResult<u64> {
+        Ok(self.next_claim_id.may_load(storage)?.unwrap_or_default())
+    }
+
+    /// Returns the conversion to a new base token that is still being applied
+    /// to existing claims, if any.
+    pub fn query_rescale(&self, storage: &dyn Storage) -> StdResult<Option<ClaimsRescale>> {
+        self.rescale.may_load(storage)
+    }
+
     /// Query lockup by id
     pub fn query_claim_by_id(&self, deps: Deps, lockup_id: u64) -> StdResult<UnlockingPosition> {
-        self.claims.load(deps.storage, lockup_id)
+        let claim = self.claims.load(deps.storage, lockup_id)?;
+        let mut claims = self.rescaled(deps.storage, vec![(lockup_id, claim)])?;
+        Ok(claims.remove(0).1)
     }
 
     /// Reads all claims for an owner. The optional arguments `start_after` and
@@ -194,6 +773,78 @@ impl<'a> Claims<'a> {
             .range(deps.storage, start, None, Order::Ascending)
             .take(limit)
             .collect::<StdResult<Vec<_>>>()
+            .and_then(|claims| self.rescaled(deps.storage, claims))
+    }
+
+    /// Reads all claims in order of their ids. The optional arguments
+    /// `start_after` and `limit` can be used for pagination.
+    pub fn query_all_claims(
+        &self,
+        deps: Deps,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<Vec<(u64, Claim)>> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT) as usize;
+        let start: Option<Bound<u64>> = start_after.map(Bound::exclusive);
+
+        self.claims
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()
+            .and_then(|claims| self.rescaled(deps.storage, claims))
+    }
+
+    /// Reads all claims that are released at or after `from` and before `to`,
+    /// in order of their release and then their ids. `from` and `to` must both
+    /// be heights or both be times.
+    ///
+    /// # Arguments
+    /// - `from` - The inclusive start of the window
+    /// - `to` - The exclusive end of the window
+    /// - `start_after` - Optional release time and id of the claim to start the
+    ///   query after. The claim does not need to exist anymore.
+    /// - `limit` - Optional maximum number of claims to return
+    pub fn query_claims_by_release(
+        &self,
+        deps: Deps,
+        from: Expiration,
+        to: Expiration,
+        start_after: Option<(Expiration, u64)>,
+        limit: Option<u32>,
+    ) -> StdResult<Vec<(u64, Claim)>> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT) as usize;
+        let from_key = release_at_key(&from);
+        let to_key = release_at_key(&to);
+        if from_key.0 != to_key.0 {
+            return Err(StdError::generic_err(
+                "from and to must both be heights or both be times",
+            ));
+        }
+
+        // A cursor before `from` starts the query at `from`
+        let start_after = start_after.map(|(release_at, id)| (release_at_key(&release_at), id));
+        let min = match start_after {
+            Some((start_key, _)) if start_key.0 != from_key.0 => {
+                return Err(StdError::generic_err(
+                    "start_after must be of the same kind as from and to",
+                ));
+            }
+            Some((start_key, id)) if start_key >= from_key => Bound::exclusive((start_key, id)),
+            _ => Bound::inclusive((from_key, 0)),
+        };
+        // All claims released at `to` are excluded, whatever their ids
+        let max = match to_key.1.checked_sub(1) {
+            Some(before_to) => Bound::inclusive(((to_key.0, before_to), u64::MAX)),
+            None => return Ok(vec![]),
+        };
+
+        self.claims
+            .idx
+            .release_at
+            .range(deps.storage, Some(min), Some(max), Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()
+            .and_then(|claims| self.rescaled(deps.storage, claims))
     }
 }
 
@@ -202,7 +853,7 @@ mod tests {
     use cosmwasm_std::testing::{
         mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
     };
-    use cosmwasm_std::{Addr, OwnedDeps, Uint128};
+    use cosmwasm_std::{Addr, OwnedDeps, Timestamp, Uint128};
     use cw_utils::Expiration;
 
     use test_case::test_case;
@@ -214,7 +865,12 @@ mod tests {
 
     const CLAIMS: &str = "claims";
     const CLAIMS_INDEX: &str = "claims_index";
+    const CLAIMS_RELEASE_AT_INDEX: &str = "claims_release_at_index";
+    const CLAIMS_CREATED_AT: &str = "claims_created_at";
+    const CLAIMS_LOCK_TIER: &str = "claims_lock_tier";
     const NUM_CLAIMS: &str = "num_claims";
+    const CLAIMS_TOTAL: &str = "claims_total";
+    const CLAIMS_RESCALE: &str = "claims_rescale";
     const BASE_TOKEN_AMOUNT: Uint128 = Uint128::new(100);
     const EXPIRATION: Expiration = Expiration::AtHeight(100);
 
@@ -225,7 +881,16 @@ mod tests {
     ) {
         let mut deps = mock_dependencies();
 
-        let claims = Claims::new(CLAIMS, CLAIMS_INDEX, NUM_CLAIMS);
+        let claims = Claims::new(
+            CLAIMS,
+            CLAIMS_INDEX,
+            CLAIMS_RELEASE_AT_INDEX,
+            CLAIMS_CREATED_AT,
+            CLAIMS_LOCK_TIER,
+            NUM_CLAIMS,
+            CLAIMS_TOTAL,
+            CLAIMS_RESCALE,
+        );
 
         let claim = claims
             .create_claim(
@@ -233,6 +898,7 @@ mod tests {
                 &Addr::unchecked(OWNER),
                 BASE_TOKEN_AMOUNT,
                 EXPIRATION,
+                &mock_env().block,
             )
             .unwrap();
 
@@ -259,6 +925,12 @@ mod tests {
                 release_at: EXPIRATION,
             }
         );
+
+        // Assert that the total amount was increased
+        assert_eq!(
+            claims.query_total_amount(&deps.storage).unwrap(),
+            BASE_TOKEN_AMOUNT
+        );
     }
 
     #[test_case(100, NOT_OWNER => Err(StdError::generic_err("Claim not owned by sender")); "claim not owned by sender")]
@@ -275,6 +947,10 @@ mod tests {
             Ok(amount) => {
                 // Assert that the claim was deleted
                 assert!(claims.claims.load(&deps.storage, 0).is_err());
+                assert_eq!(
+                    claims.query_total_amount(&deps.storage).unwrap(),
+                    Uint128::zero()
+                );
                 Ok(amount)
             }
             Err(err) => {
@@ -285,6 +961,67 @@ mod tests {
         }
     }
 
+    #[test_case(100, None, false => Ok((vec![0, 2], BASE_TOKEN_AMOUNT * Uint128::new(2))); "all matured claims")]
+    #[test_case(100, Some(1), false => Ok((vec![0], BASE_TOKEN_AMOUNT)); "limited to one claim")]
+    #[test_case(99, None, false => Err(StdError::generic_err("No matured claims to withdraw.")); "no matured claims")]
+    #[test_case(99, None, true => Ok((vec![0, 1, 2], BASE_TOKEN_AMOUNT * Uint128::new(3))); "all claims matured")]
+    fn test_claim_all_matured(
+        block_height: u64,
+        limit: Option<u32>,
+        all_matured: bool,
+    ) -> StdResult<(Vec<u64>, Uint128)> {
+        let mut env = mock_env();
+        env.block.height = block_height;
+        let owner = Addr::unchecked(OWNER);
+
+        // Create a second matured claim, with an unlocking claim and a claim of
+        // another owner in between
+        let (mut deps, claims, _claim) = create_claim();
+        claims
+            .create_claim(
+                &mut deps.storage,
+                &owner,
+                BASE_TOKEN_AMOUNT,
+                Expiration::AtHeight(1000),
+                &mock_env().block,
+            )
+            .unwrap();
+        claims
+            .create_claim(
+                &mut deps.storage,
+                &owner,
+                BASE_TOKEN_AMOUNT,
+                EXPIRATION,
+                &mock_env().block,
+            )
+            .unwrap();
+        claims
+            .create_claim(
+                &mut deps.storage,
+                &Addr::unchecked(NOT_OWNER),
+                BASE_TOKEN_AMOUNT,
+                EXPIRATION,
+                &mock_env().block,
+            )
+            .unwrap();
+
+        let (ids, amount) =
+            claims.claim_all_matured(&mut deps.storage, &env.block, &owner, limit, all_matured)?;
+
+        // Assert that only the returned claims were deleted
+        for id in 0..4 {
+            assert_eq!(
+                claims.claims.load(&deps.storage, id).is_err(),
+                ids.contains(&id)
+            );
+        }
+        assert_eq!(
+            claims.query_total_amount(&deps.storage).unwrap(),
+            BASE_TOKEN_AMOUNT * Uint128::new(4) - amount
+        );
+        Ok((ids, amount))
+    }
+
     #[test_case(None, OWNER => Ok(BASE_TOKEN_AMOUNT); "sender is owner")]
     #[test_case(None, NOT_OWNER => Err(StdError::generic_err("Claim not owned by sender")); "sender is not owner")]
     #[test_case(Some(Uint128::new(99u128)), OWNER => Ok(Uint128::new(99u128)); "sender is owner and amount is less than base token amount")]
@@ -295,6 +1032,11 @@ mod tests {
 
         match claims.force_claim(&mut deps.storage, &info, 0, claim_amount) {
             Ok(amount) => {
+                // Assert that the total amount was decreased
+                assert_eq!(
+                    claims.query_total_amount(&deps.storage).unwrap(),
+                    BASE_TOKEN_AMOUNT - amount
+                );
                 // Assert that the claim was deleted if entire amount was unlocked
                 if amount == BASE_TOKEN_AMOUNT {
                     assert!(claims.claims.load(&deps.storage, 0).is_err());
@@ -318,6 +1060,155 @@ mod tests {
         }
     }
 
+    #[test_case(None => Ok(BASE_TOKEN_AMOUNT); "cancel whole claim")]
+    #[test_case(Some(Uint128::new(40)) => Ok(Uint128::new(40)); "cancel part of claim")]
+    #[test_case(Some(Uint128::new(101)) => matches Err(_); "cancel more than claim")]
+    fn test_cancel_claim(cancel_amount: Option<Uint128>) -> StdResult<Uint128> {
+        let info = mock_info(OWNER, &[]);
+
+        let (mut deps, claims, _claim) = create_claim();
+
+        let cancelled = claims.cancel_claim(&mut deps.storage, &info, 0, cancel_amount)?;
+        assert_eq!(
+            claims.query_total_amount(&deps.storage).unwrap(),
+            BASE_TOKEN_AMOUNT - cancelled
+        );
+        Ok(cancelled)
+    }
+
+    #[test_case(OWNER => Ok(()); "sender is owner")]
+    #[test_case(NOT_OWNER => Err(StdError::generic_err("Claim not owned by sender")); "sender is not owner")]
+    fn test_transfer_claim(sender: &str) -> StdResult<()> {
+        let info = mock_info(sender, &[]);
+        let new_owner = Addr::unchecked("new_owner");
+
+        let (mut deps, claims, claim) = create_claim();
+
+        let old_claim = claims.transfer_claim(&mut deps.storage, &info, 0, &new_owner)?;
+        assert_eq!(old_claim, claim);
+
+        // Assert that the claim was moved to the new owner in the owner index
+        let new_owner_claims = claims
+            .query_claims_for_owner(deps.as_ref(), &new_owner, None, None)
+            .unwrap();
+        assert_eq!(
+            new_owner_claims,
+            vec![(
+                0,
+                Claim {
+                    owner: new_owner,
+                    ..claim
+                }
+            )]
+        );
+        assert!(claims
+            .query_claims_for_owner(deps.as_ref(), &Addr::unchecked(OWNER), None, None)
+            .unwrap()
+            .is_empty());
+        Ok(())
+    }
+
+    #[test_case(Uint128::new(40) => Ok(Uint128::new(40)); "split part of claim")]
+    #[test_case(BASE_TOKEN_AMOUNT => matches Err(_); "split whole claim")]
+    #[test_case(Uint128::zero() => matches Err(_); "split zero")]
+    fn test_split_claim(amount: Uint128) -> StdResult<Uint128> {
+        let info = mock_info(OWNER, &[]);
+        let new_owner = Addr::unchecked("new_owner");
+
+        let (mut deps, claims, _claim) = create_claim();
+
+        let new_claim = claims.split_claim(&mut deps.storage, &info, 0, amount, &new_owner)?;
+        assert_eq!(
+            new_claim,
+            Claim {
+                id: 1,
+                owner: new_owner,
+                base_token_amount: amount,
+                release_at: EXPIRATION,
+            }
+        );
+
+        // Assert that the amounts sum to the original amount and the total is
+        // unchanged
+        let old_claim = claims.claims.load(&deps.storage, 0).unwrap();
+        assert_eq!(old_claim.base_token_amount + amount, BASE_TOKEN_AMOUNT);
+        assert_eq!(
+            claims.query_total_amount(&deps.storage).unwrap(),
+            BASE_TOKEN_AMOUNT
+        );
+        Ok(new_claim.base_token_amount)
+    }
+
+    #[test]
+    fn test_rescale_claims() {
+        let (mut deps, claims, _claim) = create_claim();
+        for amount in [33u128, 33] {
+            claims
+                .create_claim(
+                    &mut deps.storage,
+                    &Addr::unchecked(OWNER),
+                    Uint128::new(amount),
+                    EXPIRATION,
+                    &mock_env().block,
+                )
+                .unwrap();
+        }
+
+        // The total is converted immediately and claims are converted on read
+        let total = claims
+            .start_rescale(&mut deps.storage, Uint128::new(3), Uint128::new(2))
+            .unwrap();
+        assert_eq!(total, Uint128::new(249));
+        assert_eq!(
+            claims
+                .query_claim_by_id(deps.as_ref(), 1)
+                .unwrap()
+                .base_token_amount,
+            Uint128::new(49)
+        );
+
+        // Unconverted claims can not be changed
+        let info = mock_info(OWNER, &[]);
+        claims
+            .cancel_claim(&mut deps.storage, &info, 1, None)
+            .unwrap_err();
+        claims
+            .start_rescale(&mut deps.storage, Uint128::new(3), Uint128::new(2))
+            .unwrap_err();
+
+        // Claims are converted in batches. Amounts are rounded down and the
+        // rounding dust is removed from the total.
+        let (ids, dust, done) = claims.apply_rescale(&mut deps.storage, Some(2)).unwrap();
+        assert_eq!(ids, vec![0, 1]);
+        assert_eq!(dust, Uint128::one());
+        assert!(!done);
+        assert_eq!(
+            claims.query_total_amount(&deps.storage).unwrap(),
+            Uint128::new(248)
+        );
+        claims
+            .cancel_claim(&mut deps.storage, &info, 1, None)
+            .unwrap();
+
+        let (ids, dust, done) = claims.apply_rescale(&mut deps.storage, Some(2)).unwrap();
+        assert_eq!(ids, vec![2]);
+        assert_eq!(dust, Uint128::zero());
+        assert!(done);
+        assert_eq!(
+            claims.query_total_amount(&deps.storage).unwrap(),
+            Uint128::new(199)
+        );
+        assert_eq!(
+            claims
+                .claims
+                .load(&deps.storage, 2)
+                .unwrap()
+                .base_token_amount,
+            Uint128::new(49)
+        );
+        assert_eq!(claims.query_rescale(&deps.storage).unwrap(), None);
+    }
+
     #[test_case(0 => Ok(Claim {id: 0, owner: Addr::unchecked(OWNER), base_token_amount: BASE_TOKEN_AMOUNT, release_at: EXPIRATION}); "claim exists")]
     #[test_case(1 => matches Err(_); "claim does not exist")]
     fn test_query_claim_by_id(id: u64) -> StdResult<Claim> {
@@ -352,11 +1243,26 @@ mod tests {
         let mut deps = mock_dependencies();
 
         // Create 100 claims for owner
-        let claims = Claims::new(CLAIMS, CLAIMS_INDEX, NUM_CLAIMS);
+        let claims = Claims::new(
+            CLAIMS,
+            CLAIMS_INDEX,
+            CLAIMS_RELEASE_AT_INDEX,
+            CLAIMS_CREATED_AT,
+            CLAIMS_LOCK_TIER,
+            NUM_CLAIMS,
+            CLAIMS_TOTAL,
+            CLAIMS_RESCALE,
+        );
         let owner = Addr::unchecked(owner);
         for _ in 0..100 {
             claims
-                .create_claim(&mut deps.storage, &owner, BASE_TOKEN_AMOUNT, EXPIRATION)
+                .create_claim(
+                    &mut deps.storage,
+                    &owner,
+                    BASE_TOKEN_AMOUNT,
+                    EXPIRATION,
+                    &mock_env().block,
+                )
                 .unwrap();
         }
 
@@ -365,4 +1271,219 @@ mod tests {
             .query_claims_for_owner(deps.as_ref(), &owner, start_after, limit)
             .map(|claims| claims.iter().map(|c| c.1.clone()).collect())
     }
+
+    #[test_case(None, None => vec![0, 1, 2, 3]; "all claims")]
+    #[test_case(Some(1), Some(2) => vec![2, 3]; "pagination")]
+    fn test_query_all_claims(start_after: Option<u64>, limit: Option<u32>) -> Vec<u64> {
+        let (mut deps, claims, _claim) = create_claim();
+        for _ in 0..3 {
+            claims
+                .create_claim(
+                    &mut deps.storage,
+                    &Addr::unchecked(NOT_OWNER),
+                    BASE_TOKEN_AMOUNT,
+                    EXPIRATION,
+                    &mock_env().block,
+                )
+                .unwrap();
+        }
+
+        claims
+            .query_all_claims(deps.as_ref(), start_after, limit)
+            .unwrap()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    #[test_case(Expiration::AtHeight(0), Expiration::AtHeight(1000), None, None => Ok(vec![2, 0, 3, 1]); "all claims")]
+    #[test_case(Expiration::AtHeight(100), Expiration::AtHeight(300), None, None => Ok(vec![0, 3]); "window")]
+    #[test_case(Expiration::AtHeight(0), Expiration::AtHeight(200), None, None => Ok(vec![2, 0]); "claims at to are excluded")]
+    #[test_case(Expiration::AtHeight(0), Expiration::AtHeight(1000), Some((Expiration::AtHeight(100), 0)), Some(2) => Ok(vec![3, 1]); "pagination")]
+    #[test_case(Expiration::AtHeight(0), Expiration::AtHeight(1000), Some((Expiration::AtHeight(150), 99)), None => Ok(vec![3, 1]); "pagination after removed claim")]
+    #[test_case(Expiration::AtHeight(0), Expiration::AtHeight(0), None, None => Ok(vec![]); "empty window")]
+    #[test_case(Expiration::AtHeight(0), Expiration::AtTime(Timestamp::from_seconds(1000)), None, None => matches Err(_); "mixed kinds")]
+    fn test_query_claims_by_release(
+        from: Expiration,
+        to: Expiration,
+        start_after: Option<(Expiration, u64)>,
+        limit: Option<u32>,
+    ) -> StdResult<Vec<u64>> {
+        // Create claims released at heights 100, 300, 50 and 200, and one
+        // released at a time
+        let (mut deps, claims, _claim) = create_claim();
+        let owner = Addr::unchecked(OWNER);
+        for height in [300, 50, 200] {
+            claims
+                .create_claim(
+                    &mut deps.storage,
+                    &owner,
+                    BASE_TOKEN_AMOUNT,
+                    Expiration::AtHeight(height),
+                    &mock_env().block,
+                )
+                .unwrap();
+        }
+        claims
+            .create_claim(
+                &mut deps.storage,
+                &owner,
+                BASE_TOKEN_AMOUNT,
+                Expiration::AtTime(Timestamp::from_seconds(100)),
+                &mock_env().block,
+            )
+            .unwrap();
+
+        Ok(claims
+            .query_claims_by_release(deps.as_ref(), from, to, start_after, limit)?
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect())
+    }
+
+    #[test]
+    fn test_migrate_indexes() {
+        let (mut deps, claims, _claim) = create_claim();
+
+        // Remove the claim from the release index to simulate a claim created
+        // before the index existed
+        let claim = claims.claims.load(&deps.storage, 0).unwrap();
+        claims
+            .claims
+            .idx
+            .release_at
+            .remove(&mut deps.storage, &0u64.to_be_bytes(), &claim)
+            .unwrap();
+        let query = |deps: Deps| {
+            claims
+                .query_claims_by_release(
+                    deps,
+                    Expiration::AtHeight(0),
+                    Expiration::AtHeight(1000),
+                    None,
+                    None,
+                )
+                .unwrap()
+                .len()
+        };
+        assert_eq!(query(deps.as_ref()), 0);
+
+        claims.migrate_indexes(&mut deps.storage).unwrap();
+        assert_eq!(query(deps.as_ref()), 1);
+    }
+
+    #[test]
+    fn test_shorten_claims() {
+        let (mut deps, claims, _claim) = create_claim();
+        let block = mock_env().block;
+
+        // Claims 1, 2 and 4 are released 100 blocks after their creation and
+        // claim 3 is released at a time. Claim 2 was created before creation
+        // blocks were stored, and claim 4 was unlocked from a lock tier.
+        for release_at in [
+            Expiration::AtHeight(block.height + 100),
+            Expiration::AtHeight(block.height + 100),
+            Expiration::AtTime(block.time.plus_seconds(100)),
+            Expiration::AtHeight(block.height + 100),
+        ] {
+            claims
+                .create_claim(
+                    &mut deps.storage,
+                    &Addr::unchecked(OWNER),
+                    BASE_TOKEN_AMOUNT,
+                    release_at,
+                    &block,
+                )
+                .unwrap();
+        }
+        claims.created_at.remove(&mut deps.storage, 2);
+        claims.set_lock_tier(&mut deps.storage, 4, 0).unwrap();
+
+        // Claim 5 is created after the reduction started and is not shortened
+        let end = claims.query_next_claim_id(&deps.storage).unwrap();
+        claims
+            .create_claim(
+                &mut deps.storage,
+                &Addr::unchecked(OWNER),
+                BASE_TOKEN_AMOUNT,
+                Expiration::AtHeight(block.height + 100),
+                &block,
+            )
+            .unwrap();
+
+        // Shorten the claims in two pages
+        let (mut shortened, last) = claims
+            .shorten_claims(&mut deps.storage, Duration::Height(50), end, None, Some(3))
+            .unwrap();
+        assert_eq!(last, Some(2));
+        let (more, last) = claims
+            .shorten_claims(&mut deps.storage, Duration::Height(50), end, last, Some(3))
+            .unwrap();
+        assert_eq!(last, None);
+        shortened.extend(more);
+
+        // Only claim 1 is shortened. Claim 0 is already released earlier.
+        assert_eq!(shortened, vec![1]);
+        let release_at = |id| claims.claims.load(&deps.storage, id).unwrap().release_at;
+        assert_eq!(release_at(0), EXPIRATION);
+        assert_eq!(release_at(1), Expiration::AtHeight(block.height + 50));
+        assert_eq!(release_at(2), Expiration::AtHeight(block.height + 100));
+        assert_eq!(
+            release_at(3),
+            Expiration::AtTime(block.time.plus_seconds(100))
+        );
+        assert_eq!(release_at(4), Expiration::AtHeight(block.height + 100));
+        assert_eq!(release_at(5), Expiration::AtHeight(block.height + 100));
+    }
+
+    #[test]
+    fn test_split_claim_copies_creation_block_and_lock_tier() {
+        let (mut deps, claims, _claim) = create_claim();
+        claims.set_lock_tier(&mut deps.storage, 0, 2).unwrap();
+        claims
+            .split_claim(
+                &mut deps.storage,
+                &mock_info(OWNER, &[]),
+                0,
+                Uint128::one(),
+                &Addr::unchecked(NOT_OWNER),
+            )
+            .unwrap();
+
+        assert_eq!(
+            claims.created_at.load(&deps.storage, 1).unwrap(),
+            ClaimCreatedAt::from(&mock_env().block)
+        );
+        assert_eq!(claims.query_lock_tier(&deps.storage, 1).unwrap(), Some(2));
+
+        // The lock tier is removed together with the claim
+        claims
+            .cancel_claim(&mut deps.storage, &mock_info(OWNER, &[]), 0, None)
+            .unwrap();
+        assert_eq!(claims.query_lock_tier(&deps.storage, 0).unwrap(), None);
+    }
+
+    #[test]
+    fn test_migrate_total_amount() {
+        let (mut deps, claims, _claim) = create_claim();
+        claims
+            .create_claim(
+                &mut deps.storage,
+                &Addr::unchecked(OWNER),
+                BASE_TOKEN_AMOUNT,
+                EXPIRATION,
+                &mock_env().block,
+            )
+            .unwrap();
+
+        // Remove the stored total to simulate a claims instance from before the
+        // total was tracked
+        claims.total_amount.remove(&mut deps.storage);
+        claims.migrate_total_amount(&mut deps.storage).unwrap();
+
+        assert_eq!(
+            claims.query_total_amount(&deps.storage).unwrap(),
+            BASE_TOKEN_AMOUNT * Uint128::new(2)
+        );
+    }
 }
@@ -4,31 +4,36 @@ use apollo_utils::assets::receive_asset;
 use cosmwasm_std::entry_point;
 
 use cosmwasm_std::{
-    to_json_binary, Binary, CosmosMsg, Deps, DepsMut, Env, Event, MessageInfo, QueryRequest, Reply,
-    Response, StdResult, SubMsg, Uint128, WasmQuery,
+    to_json_binary, Binary, Deps, DepsMut, Env, Event, MessageInfo, QueryRequest, Reply, Response,
+    StdError, StdResult, SubMsg, Uint128, WasmMsg, WasmQuery,
 };
 use cw2::ensure_from_older_version;
-use cw_dex_astroport::{astroport, AstroportPool, AstroportStaking};
-use cw_utils::Duration;
+use cw20::MinterResponse;
+use cw_dex_astroport::{astroport, AstroportPool};
+use cw_utils::parse_reply_instantiate_data;
 use cw_vault_standard::extensions::force_unlock::ForceUnlockExecuteMsg;
 use cw_vault_standard::extensions::lockup::LockupExecuteMsg;
-use osmosis_std::types::osmosis::tokenfactory::v1beta1::MsgCreateDenom;
 
 use crate::error::{ContractError, ContractResponse};
-use crate::helpers::{self, IntoInternalCall};
 use crate::msg::{
-    ApolloExtensionExecuteMsg, ApolloExtensionQueryMsg, ExecuteMsg, ExtensionExecuteMsg,
-    ExtensionQueryMsg, InstantiateMsg, InternalMsg, MigrateMsg, QueryMsg,
+    ApolloExtensionExecuteMsg, ApolloExtensionQueryMsg, Cw20InstantiateMsg, Cw721InstantiateMsg,
+    ExecuteMsg, ExtensionExecuteMsg, ExtensionQueryMsg, InstantiateMsg, InternalMsg, MigrateMsg,
+    QueryMsg, VaultTokenBackend,
 };
 use crate::query::{
-    query_force_withdraw_whitelist, query_state, query_unlocking_position,
-    query_unlocking_positions, query_vault_info, query_vault_standard_info,
+    query_all_unlocking_positions, query_config_update_proposals, query_donations,
+    query_force_withdraw_whitelist, query_idle_balances, query_lock_tier_positions,
+    query_lock_tiers, query_pending_rewards, query_simulate_compound, query_state,
+    query_unlocking_position, query_unlocking_positions,
+    query_unlocking_positions_released_between, query_user_position, query_validate_config_update,
+    query_vault_info, query_vault_standard_info,
 };
 use crate::state::{
-    ConfigUnchecked, VaultState, BASE_TOKEN, CONFIG, FORCE_WITHDRAW_WHITELIST, POOL, STAKING,
-    STATE, VAULT_TOKEN_DENOM,
+    self, ConfigUnchecked, EmergencyUnlockPenalty, StakingModeUnchecked, VaultState, VaultToken,
+    BASE_TOKEN, CONFIG, FORCE_WITHDRAW_WHITELIST, POOL, STAKING, STATE, UNLOCKING_POSITION_NFT,
+    VAULT_TOKEN,
 };
-use crate::{execute, query};
+use crate::{execute, helpers, migrations, query};
 
 pub const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
 pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -36,6 +41,14 @@ pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 /// The ID used in the reply entrypoint for SubMsgs that compound the vault
 pub const COMPOUND_REPLY_ID: u64 = 4018u64;
 
+/// The ID used in the reply entrypoint for the SubMsg that instantiates the
+/// cw20 vault token
+pub const INSTANTIATE_VAULT_TOKEN_REPLY_ID: u64 = 4019u64;
+
+/// The ID used in the reply entrypoint for the SubMsg that instantiates the
+/// cw721 contract for unlocking position NFTs
+pub const INSTANTIATE_UNLOCKING_POSITION_NFT_REPLY_ID: u64 = 4020u64;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
@@ -68,7 +81,7 @@ pub fn instantiate(
 
     // Create, validate and store config
     let config = ConfigUnchecked {
-        lock_duration: Duration::Time(msg.lock_duration),
+        lock_duration: msg.lock_duration,
         reward_tokens: msg.reward_tokens,
         deposits_enabled: msg.deposits_enabled,
         router: msg.router,
@@ -77,19 +90,19 @@ pub fn instantiate(
         performance_fee: msg.performance_fee.unwrap_or_default(),
         deposit_fee: msg.deposit_fee.unwrap_or_default(),
         withdrawal_fee: msg.withdrawal_fee.unwrap_or_default(),
+        config_update_delay: msg.config_update_delay,
+        reject_implicit_donations: false,
+        emergency_unlock_penalty: EmergencyUnlockPenalty::Disabled,
+        lock_tiers: vec![],
+        dust_threshold: Uint128::zero(),
     }
     .check(deps.as_ref())?;
     CONFIG.save(deps.storage, &config)?;
 
-    // Store base token and vault token denom
-    let vault_token_denom = format!(
-        "factory/{}/{}",
-        env.contract.address, msg.vault_token_subdenom
-    );
+    // Store base token
     let base_token = AssetInfo::from_str(deps.api, &pair_info.liquidity_token);
 
     BASE_TOKEN.save(deps.storage, &base_token)?;
-    VAULT_TOKEN_DENOM.save(deps.storage, &vault_token_denom)?;
     STATE.save(
         deps.storage,
         &VaultState {
@@ -99,20 +112,101 @@ pub fn instantiate(
     )?;
 
     // Store staking info
-    let staking = AstroportStaking {
-        lp_token: base_token.clone(),
-        incentives: deps.api.addr_validate(&msg.astroport_incentives_addr)?,
-    };
+    let staking = match msg.astroport_incentives_addr {
+        Some(incentives) => StakingModeUnchecked::Astroport { incentives },
+        None => StakingModeUnchecked::Hold {},
+    }
+    .check(deps.api, &base_token)?;
     STAKING.save(deps.storage, &staking)?;
 
     // Create vault token
-    let create_denom_msg: CosmosMsg = MsgCreateDenom {
-        sender: env.contract.address.to_string(),
-        subdenom: msg.vault_token_subdenom,
-    }
-    .into();
+    let metadata = msg.vault_token_metadata.clone().unwrap_or_default();
+    let res = match msg
+        .vault_token_backend
+        .unwrap_or(VaultTokenBackend::TokenFactory { flavor: None })
+    {
+        VaultTokenBackend::TokenFactory { flavor } => {
+            let flavor = flavor.unwrap_or_default();
+            let denom = format!(
+                "factory/{}/{}",
+                env.contract.address, msg.vault_token_subdenom
+            );
+            VAULT_TOKEN.save(
+                deps.storage,
+                &VaultToken::TokenFactory {
+                    denom,
+                    flavor: flavor.clone(),
+                },
+            )?;
 
-    Ok(Response::new().add_message(create_denom_msg))
+            let create_denom_msg = flavor.create_denom_msg(&env, msg.vault_token_subdenom);
+
+            // Set the vault token metadata once the denom exists
+            let metadata_msgs = msg
+                .vault_token_metadata
+                .map(|metadata| {
+                    helpers::set_vault_token_metadata_msg(deps.as_ref(), &env, &metadata)
+                })
+                .transpose()?;
+
+            Response::new()
+                .add_message(create_denom_msg)
+                .add_messages(metadata_msgs)
+        }
+        VaultTokenBackend::Cw20 { code_id } => {
+            // The vault token is stored in the reply, once the address of the
+            // cw20 contract is known
+            let decimals = u8::try_from(metadata.decimals())
+                .map_err(|_| StdError::generic_err("Too many vault token decimals"))?;
+            let cw20_msg = Cw20InstantiateMsg {
+                name: metadata.name(&pool.pool_assets)?,
+                symbol: metadata.symbol(&pool.pool_assets)?,
+                decimals,
+                initial_balances: vec![],
+                mint: Some(MinterResponse {
+                    minter: env.contract.address.to_string(),
+                    cap: None,
+                }),
+            };
+            let instantiate_msg = SubMsg::reply_on_success(
+                WasmMsg::Instantiate {
+                    admin: Some(msg.owner.clone()),
+                    code_id,
+                    label: cw20_msg.name.clone(),
+                    msg: to_json_binary(&cw20_msg)?,
+                    funds: vec![],
+                },
+                INSTANTIATE_VAULT_TOKEN_REPLY_ID,
+            );
+
+            Response::new().add_submessage(instantiate_msg)
+        }
+    };
+
+    // Create the unlocking position NFT contract. Its address is stored in the
+    // reply.
+    let res = match msg.unlocking_position_nft_code_id {
+        Some(code_id) => {
+            let cw721_msg = Cw721InstantiateMsg {
+                name: format!("{} Unlocking Positions", metadata.name(&pool.pool_assets)?),
+                symbol: format!("{}-UNLOCKING", metadata.symbol(&pool.pool_assets)?),
+                minter: env.contract.address.to_string(),
+            };
+            res.add_submessage(SubMsg::reply_on_success(
+                WasmMsg::Instantiate {
+                    admin: Some(msg.owner),
+                    code_id,
+                    label: cw721_msg.name.clone(),
+                    msg: to_json_binary(&cw721_msg)?,
+                    funds: vec![],
+                },
+                INSTANTIATE_UNLOCKING_POSITION_NFT_REPLY_ID,
+            ))
+        }
+        None => res,
+    };
+
+    Ok(res)
 }
 
 #[allow(deprecated)]
@@ -123,62 +217,57 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    // Only messages that let depositors exit are allowed after a shutdown
+    if state::is_shut_down(deps.storage)? && !msg.is_allowed_after_shutdown() {
+        return Err(ContractError::VaultShutDown {});
+    }
+
     match msg {
         ExecuteMsg::Deposit { amount, recipient } => {
             let base_token = BASE_TOKEN.load(deps.storage)?;
-            let deposited = Asset::new(base_token.clone(), amount);
+            let deposited = Asset::new(base_token, amount);
             let transfer_res = receive_asset(&info, &env, &deposited)?;
 
-            // Call contract itself first to compound, but as a SubMsg so that we can still
-            // deposit if the compound fails
-            let compound_msg = SubMsg::reply_on_error(
-                InternalMsg::Compound {
-                    discount_deposit: amount,
-                }
-                .into_internal_call(&env, vec![])?,
-                COMPOUND_REPLY_ID,
-            );
-
             let recipient = helpers::unwrap_recipient(recipient, &info, deps.api)?;
-            let deposit_msg =
-                InternalMsg::Deposit { amount, recipient }.into_internal_call(&env, vec![])?;
+            let deposit_res =
+                execute::basic_vault::execute_compound_and_deposit(&env, amount, recipient, None)?;
 
-            Ok(transfer_res
-                .add_submessage(compound_msg)
-                .add_message(deposit_msg))
+            Ok(transfer_res.add_submessages(deposit_res.messages))
         }
         ExecuteMsg::Redeem { recipient, amount } => {
-            // Call contract itself first to compound, but as a SubMsg so that we can still
-            // redeem if the compound fails
-            let compound_msg = SubMsg::reply_on_error(
-                ApolloExtensionExecuteMsg::Compound {}.into_internal_call(&env, vec![])?,
-                COMPOUND_REPLY_ID,
-            );
+            let vault_token = VAULT_TOKEN.load(deps.storage)?;
+            helpers::assert_vault_tokens_sent(&info, &vault_token, amount)?;
 
             let recipient = helpers::unwrap_recipient(recipient, &info, deps.api)?;
-            let redeem_msg =
-                InternalMsg::Redeem { amount, recipient }.into_internal_call(&env, info.funds)?;
-
-            Ok(Response::new()
-                .add_submessage(compound_msg)
-                .add_message(redeem_msg))
+            execute::basic_vault::execute_compound_and_redeem(&env, amount, recipient, info.funds)
+        }
+        ExecuteMsg::Receive(cw20_msg) => {
+            execute::receive::execute_receive(deps, env, info, cw20_msg)
+        }
+        ExecuteMsg::ReceiveNft(nft_msg) => {
+            execute::receive::execute_receive_nft(deps, env, info, nft_msg)
         }
         ExecuteMsg::VaultExtension(msg) => match msg {
             ExtensionExecuteMsg::Lockup(msg) => match msg {
                 LockupExecuteMsg::Unlock { amount } => {
-                    let recipient = info.sender.clone();
-                    execute::basic_vault::execute_redeem(deps, env, info, amount, recipient, false)
+                    let vault_token = VAULT_TOKEN.load(deps.storage)?;
+                    helpers::assert_vault_tokens_sent(&info, &vault_token, amount)?;
+                    execute::basic_vault::execute_redeem(deps, env, amount, info.sender, false)
                 }
                 LockupExecuteMsg::EmergencyUnlock { amount } => {
-                    let recipient = info.sender.clone();
-                    execute::basic_vault::execute_redeem(deps, env, info, amount, recipient, false)
+                    let vault_token = VAULT_TOKEN.load(deps.storage)?;
+                    helpers::assert_vault_tokens_sent(&info, &vault_token, amount)?;
+                    execute::lockup::execute_emergency_unlock(deps, env, amount, info.sender)
                 }
                 LockupExecuteMsg::WithdrawUnlocked {
                     recipient,
                     lockup_id,
-                } => execute::lockup::execute_withdraw_unlocked(
-                    deps, env, info, recipient, lockup_id,
-                ),
+                } => {
+                    helpers::assert_unlocking_positions_not_nfts(deps.as_ref())?;
+                    execute::lockup::execute_withdraw_unlocked(
+                        deps, env, info, recipient, lockup_id,
+                    )
+                }
             },
             ExtensionExecuteMsg::ForceUnlock(msg) => match msg {
                 ForceUnlockExecuteMsg::ForceRedeem { recipient, amount } => {
@@ -187,17 +276,22 @@ pub fn execute(
                     if !FORCE_WITHDRAW_WHITELIST.contains(deps.storage, &info.sender) {
                         return Err(ContractError::Unauthorized {});
                     }
+                    let vault_token = VAULT_TOKEN.load(deps.storage)?;
+                    helpers::assert_vault_tokens_sent(&info, &vault_token, amount)?;
                     let recipient = helpers::unwrap_recipient(recipient, &info, deps.api)?;
 
-                    execute::basic_vault::execute_redeem(deps, env, info, amount, recipient, true)
+                    execute::basic_vault::execute_redeem(deps, env, amount, recipient, true)
                 }
                 ForceUnlockExecuteMsg::ForceWithdrawUnlocking {
                     lockup_id,
                     amount,
                     recipient,
-                } => execute::lockup::execute_force_withdraw_unlocking(
-                    deps, env, info, amount, recipient, lockup_id,
-                ),
+                } => {
+                    helpers::assert_unlocking_positions_not_nfts(deps.as_ref())?;
+                    execute::lockup::execute_force_withdraw_unlocking(
+                        deps, env, info, amount, recipient, lockup_id,
+                    )
+                }
                 ForceUnlockExecuteMsg::UpdateForceWithdrawWhitelist {
                     add_addresses,
                     remove_addresses,
@@ -223,17 +317,37 @@ pub fn execute(
                     InternalMsg::ProvideLiquidity {} => {
                         execute::compound::execute_provide_liquidity(deps, env)
                     }
-                    InternalMsg::StakeLps { discount_tokens } => {
-                        execute::compound::execute_stake_lps(deps, env, discount_tokens)
-                    }
+                    InternalMsg::StakeLps {
+                        discount_tokens,
+                        untracked_tokens,
+                    } => execute::compound::execute_stake_lps(
+                        deps,
+                        env,
+                        discount_tokens,
+                        untracked_tokens,
+                    ),
                     InternalMsg::Deposit { amount, recipient } => {
                         execute::basic_vault::execute_deposit(deps, env, amount, recipient)
                     }
+                    InternalMsg::DepositToLockTier {
+                        lock_tier,
+                        amount,
+                        recipient,
+                    } => execute::basic_vault::execute_deposit_to_lock_tier(
+                        deps, env, lock_tier, amount, recipient,
+                    ),
                     InternalMsg::Redeem { recipient, amount } => {
-                        execute::basic_vault::execute_redeem(
-                            deps, env, info, amount, recipient, false,
-                        )
+                        execute::basic_vault::execute_redeem(deps, env, amount, recipient, false)
                     }
+                    InternalMsg::StakeMigratedLps {
+                        old_base_token_amount,
+                        min_base_token_amount,
+                    } => execute::migrate_pool::execute_stake_migrated_lps(
+                        deps,
+                        env,
+                        old_base_token_amount,
+                        min_base_token_amount,
+                    ),
                 }
             }
             ExtensionExecuteMsg::UpdateOwnership(action) => {
@@ -242,19 +356,134 @@ pub fn execute(
                 Ok(Response::new().add_attributes(ownership.into_attributes()))
             }
             ExtensionExecuteMsg::Apollo(msg) => match msg {
-                ApolloExtensionExecuteMsg::UpdateConfig { updates } => {
-                    execute::basic_vault::execute_update_config(deps, info, updates)
+                ApolloExtensionExecuteMsg::UpdateConfig {
+                    updates,
+                    shorten_existing_claims,
+                } => execute::basic_vault::execute_update_config(
+                    deps,
+                    info,
+                    updates,
+                    shorten_existing_claims.unwrap_or(false),
+                ),
+                ApolloExtensionExecuteMsg::ProposeConfigUpdate {
+                    updates,
+                    staking_mode,
+                    pool,
+                } => execute::basic_vault::execute_propose_config_update(
+                    deps,
+                    env,
+                    info,
+                    updates,
+                    staking_mode,
+                    pool,
+                ),
+                ApolloExtensionExecuteMsg::ExecuteConfigUpdate {
+                    proposal_id,
+                    pool_migration_min_out,
+                } => execute::basic_vault::execute_execute_config_update(
+                    deps,
+                    env,
+                    info,
+                    proposal_id,
+                    pool_migration_min_out,
+                ),
+                ApolloExtensionExecuteMsg::CancelConfigUpdate { proposal_id } => {
+                    execute::basic_vault::execute_cancel_config_update(deps, info, proposal_id)
+                }
+                ApolloExtensionExecuteMsg::UpdateStakingMode { staking_mode } => {
+                    execute::basic_vault::execute_update_staking_mode(deps, env, info, staking_mode)
+                }
+                ApolloExtensionExecuteMsg::RescueTokens { assets, recipient } => {
+                    execute::basic_vault::execute_rescue_tokens(deps, info, assets, recipient)
+                }
+                ApolloExtensionExecuteMsg::Donate {} => {
+                    execute::basic_vault::execute_donate(deps, env, info)
+                }
+                ApolloExtensionExecuteMsg::SetVaultTokenMetadata { metadata } => {
+                    execute::basic_vault::execute_set_vault_token_metadata(
+                        deps, env, info, metadata,
+                    )
+                }
+                ApolloExtensionExecuteMsg::DepositToLockTier {
+                    lock_tier,
+                    amount,
+                    recipient,
+                } => {
+                    let base_token = BASE_TOKEN.load(deps.storage)?;
+                    let deposited = Asset::new(base_token, amount);
+                    let transfer_res = receive_asset(&info, &env, &deposited)?;
+
+                    let recipient = helpers::unwrap_recipient(recipient, &info, deps.api)?;
+                    let deposit_res = execute::basic_vault::execute_compound_and_deposit(
+                        &env,
+                        amount,
+                        recipient,
+                        Some(lock_tier),
+                    )?;
+
+                    Ok(transfer_res.add_submessages(deposit_res.messages))
+                }
+                ApolloExtensionExecuteMsg::UnlockFromLockTier { lock_tier, amount } => {
+                    execute::lockup::execute_unlock_from_lock_tier(
+                        deps, env, info, lock_tier, amount,
+                    )
+                }
+                ApolloExtensionExecuteMsg::Shutdown {} => {
+                    execute::basic_vault::execute_shutdown(deps, env, info)
                 }
                 ApolloExtensionExecuteMsg::Compound {} => {
                     execute::compound::execute_compound(deps, env, Uint128::zero())
                 }
+                ApolloExtensionExecuteMsg::EmergencyWithdrawUnlocking {
+                    lockup_id,
+                    recipient,
+                } => {
+                    helpers::assert_unlocking_positions_not_nfts(deps.as_ref())?;
+                    execute::lockup::execute_emergency_withdraw_unlocking(
+                        deps, env, info, lockup_id, recipient,
+                    )
+                }
+                ApolloExtensionExecuteMsg::ApplyLockDurationReduction { limit } => {
+                    execute::lockup::execute_apply_lock_duration_reduction(deps, limit)
+                }
+                ApolloExtensionExecuteMsg::ApplyClaimsRescale { limit } => {
+                    execute::migrate_pool::execute_apply_claims_rescale(deps, limit)
+                }
+                ApolloExtensionExecuteMsg::WithdrawAllUnlocked { recipient, limit } => {
+                    execute::lockup::execute_withdraw_all_unlocked(
+                        deps, env, info, recipient, limit,
+                    )
+                }
+                ApolloExtensionExecuteMsg::CancelUnlock { lockup_id, amount } => {
+                    helpers::assert_unlocking_positions_not_nfts(deps.as_ref())?;
+                    execute::lockup::execute_cancel_unlock(deps, env, info, lockup_id, amount)
+                }
+                ApolloExtensionExecuteMsg::TransferUnlockingPosition {
+                    lockup_id,
+                    new_owner,
+                } => execute::lockup::execute_transfer_unlocking_position(
+                    deps, info, lockup_id, new_owner,
+                ),
+                ApolloExtensionExecuteMsg::SplitUnlockingPosition {
+                    lockup_id,
+                    amount,
+                    new_owner,
+                } => execute::lockup::execute_split_unlocking_position(
+                    deps, info, lockup_id, amount, new_owner,
+                ),
+                ApolloExtensionExecuteMsg::SweepDust {} => {
+                    execute::compound::execute_sweep_dust(deps, env, info)
+                }
+                ApolloExtensionExecuteMsg::DiscoverRewardTokens {} => {
+                    execute::compound::execute_discover_reward_tokens(deps, env, info)
+                }
             },
         },
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::VaultStandardInfo {} => to_json_binary(&query_vault_standard_info(deps)?),
         QueryMsg::Info {} => to_json_binary(&query_vault_info(deps)?),
@@ -271,10 +500,10 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
             to_json_binary(&state.vault_token_supply)
         }
         QueryMsg::ConvertToShares { amount } => {
-            to_json_binary(&helpers::convert_to_shares(deps, amount))
+            to_json_binary(&helpers::convert_to_shares(deps, None, amount))
         }
         QueryMsg::ConvertToAssets { amount } => {
-            to_json_binary(&helpers::convert_to_assets(deps, amount))
+            to_json_binary(&helpers::convert_to_assets(deps, None, amount))
         }
         QueryMsg::VaultTokenExchangeRate { quote_denom } => {
             to_json_binary(&query::vault_token_exchange_rate(deps, quote_denom)?)
@@ -315,13 +544,55 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
                     let state_res = query_state(deps)?;
                     to_json_binary(&state_res)
                 }
+                ApolloExtensionQueryMsg::ValidateConfigUpdate { updates } => {
+                    to_json_binary(&query_validate_config_update(deps, updates)?)
+                }
+                ApolloExtensionQueryMsg::PendingRewards {} => {
+                    to_json_binary(&query_pending_rewards(deps, env)?)
+                }
+                ApolloExtensionQueryMsg::SimulateCompound {} => {
+                    to_json_binary(&query_simulate_compound(deps, env)?)
+                }
+                ApolloExtensionQueryMsg::IdleBalances {} => {
+                    to_json_binary(&query_idle_balances(deps, env)?)
+                }
+                ApolloExtensionQueryMsg::Donations {} => to_json_binary(&query_donations(deps)?),
+                ApolloExtensionQueryMsg::AllUnlockingPositions { start_after, limit } => {
+                    to_json_binary(&query_all_unlocking_positions(deps, start_after, limit)?)
+                }
+                ApolloExtensionQueryMsg::UnlockingPositionsReleasedBetween {
+                    from,
+                    to,
+                    start_after,
+                    limit,
+                } => to_json_binary(&query_unlocking_positions_released_between(
+                    deps,
+                    from,
+                    to,
+                    start_after,
+                    limit,
+                )?),
+                ApolloExtensionQueryMsg::TotalUnlocking {} => {
+                    to_json_binary(&state::claims().query_total_amount(deps.storage)?)
+                }
+                ApolloExtensionQueryMsg::UserPosition { address } => {
+                    to_json_binary(&query_user_position(deps, env, address)?)
+                }
+                ApolloExtensionQueryMsg::LockTiers {} => to_json_binary(&query_lock_tiers(deps)?),
+                ApolloExtensionQueryMsg::LockTierPositions { address } => {
+                    to_json_binary(&query_lock_tier_positions(deps, address)?)
+                }
+                ApolloExtensionQueryMsg::ConfigUpdateProposals { start_after, limit } => {
+                    let proposals = query_config_update_proposals(deps, start_after, limit)?;
+                    to_json_binary(&proposals)
+                }
             },
         },
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn reply(_deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
     // If this reply is triggered by a compound SubMsg::ReplyOnError, we add an
     // event so it can be seen in the transaction logs. Unfortunately we can't
     // add the error, because error messages are still redacted
@@ -331,14 +602,45 @@ pub fn reply(_deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, Contract
             .add_attribute("action", "reply on compound failed");
         return Ok(Response::new().add_event(event));
     }
+    // Store the address of the cw20 vault token once it is instantiated
+    if msg.id == INSTANTIATE_VAULT_TOKEN_REPLY_ID {
+        let res = parse_reply_instantiate_data(msg)?;
+        let addr = deps.api.addr_validate(&res.contract_address)?;
+        VAULT_TOKEN.save(deps.storage, &VaultToken::Cw20 { addr: addr.clone() })?;
+
+        let event = Event::new("apollo/vaults/instantiate_vault_token")
+            .add_attribute("vault_token", addr.to_string());
+        return Ok(Response::new().add_event(event));
+    }
+    // Store the address of the unlocking position NFT contract once it is
+    // instantiated
+    if msg.id == INSTANTIATE_UNLOCKING_POSITION_NFT_REPLY_ID {
+        let res = parse_reply_instantiate_data(msg)?;
+        let addr = deps.api.addr_validate(&res.contract_address)?;
+        UNLOCKING_POSITION_NFT.save(deps.storage, &addr)?;
+
+        let event = Event::new("apollo/vaults/instantiate_unlocking_position_nft")
+            .add_attribute("unlocking_position_nft", addr.to_string());
+        return Ok(Response::new().add_event(event));
+    }
     Ok(Response::new())
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
-    ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+pub fn migrate(mut deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let from_version = ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    let applied = migrations::run_migrations(deps.branch(), &env, &from_version, &msg)?;
 
     cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
-    Ok(Response::new())
+    // Attribute values must not be empty
+    let mut event = Event::new("apollo/vaults/migrate")
+        .add_attribute("from_version", from_version.to_string())
+        .add_attribute("to_version", CONTRACT_VERSION);
+    if !applied.is_empty() {
+        event = event.add_attribute("applied_migrations", applied.join(","));
+    }
+
+    Ok(Response::new().add_event(event))
 }
@@ -1,6 +1,7 @@
-use cosmwasm_std::{Coin, OverflowError, StdError};
+use cosmwasm_std::{Coin, OverflowError, StdError, Timestamp};
 use cw_dex_astroport::cw_dex::CwDexError;
 use cw_ownable::OwnershipError;
+use cw_utils::ParseReplyError;
 
 #[derive(Debug, thiserror::Error)]
 pub enum ContractError {
@@ -19,9 +20,15 @@ pub enum ContractError {
     #[error(transparent)]
     Semver(#[from] semver::Error),
 
+    #[error(transparent)]
+    ParseReply(#[from] ParseReplyError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
+    #[error("Invalid cw20 receive message for token {token}")]
+    InvalidReceiveMsg { token: String },
+
     #[error("Deposits are disabled")]
     DepositsDisabled {},
 
@@ -31,6 +38,54 @@ pub enum ContractError {
     #[error("Lockup is disabled for this vault")]
     LockupDisabled {},
 
+    #[error("Config update must be proposed and executed after the config update delay")]
+    ConfigUpdateRequiresTimelock {},
+
+    #[error("Config update proposal {id} can not be executed until {executable_at}")]
+    ConfigUpdateTimelockNotExpired { id: u64, executable_at: Timestamp },
+
+    #[error("The new pool must have the same assets as the current pool")]
+    PoolAssetsMismatch {},
+
+    #[error("Can not rescue {asset}, since it may belong to depositors")]
+    CannotRescueVaultAsset { asset: String },
+
+    #[error("Cw20 vault tokens must be sent to the vault with Cw20ExecuteMsg::Send")]
+    Cw20VaultTokenMustBeSent {},
+
+    #[error("The metadata of cw20 vault tokens can not be changed")]
+    Cw20VaultTokenMetadataImmutable {},
+
+    #[error("Unlocking positions are NFTs and must be managed through the NFT contract")]
+    UnlockingPositionsAreNfts {},
+
+    #[error("Existing unlocking positions can only be shortened when lock_duration is updated")]
+    LockDurationNotUpdated {},
+
+    #[error("No lock duration reduction is being applied to unlocking positions")]
+    NoLockDurationReduction {},
+
+    #[error("A lock duration reduction is still being applied to unlocking positions")]
+    LockDurationReductionPending {},
+
+    #[error("No unlocking positions are being converted to a new base token")]
+    NoClaimsRescale {},
+
+    #[error("config_update_delay must be set when migrating from versions before 0.6.0")]
+    ConfigUpdateDelayRequired {},
+
+    #[error("Emergency unlocks are disabled for this vault")]
+    EmergencyUnlockDisabled {},
+
+    #[error("Lock tier {lock_tier} does not exist")]
+    LockTierNotFound { lock_tier: u32 },
+
+    #[error("Lock tiers can not be removed")]
+    LockTiersRemoved {},
+
+    #[error("The vault has been shut down")]
+    VaultShutDown {},
+
     #[error("Unexpected funds sent. Expected: {expected:?}, Actual: {actual:?}")]
     UnexpectedFunds {
         expected: Vec<Coin>,
@@ -1,20 +1,26 @@
-use apollo_cw_asset::Asset;
+use apollo_cw_asset::{Asset, AssetInfo, AssetUnchecked};
 use cosmwasm_std::{
-    coins, Addr, BankMsg, CosmosMsg, DepsMut, Env, Event, MessageInfo, Response, Uint128,
+    Addr, Coin, Deps, DepsMut, Env, Event, MessageInfo, Response, StdError, StdResult, Storage,
+    SubMsg, Uint128,
 };
+use cw_utils::Duration;
 use cw_vault_standard::extensions::lockup::{
     UNLOCKING_POSITION_ATTR_KEY, UNLOCKING_POSITION_CREATED_EVENT_TYPE,
 };
 use optional_struct::Applyable;
 
-use crate::error::{ContractError, ContractResponse};
-use crate::helpers::{self, burn_vault_tokens, mint_vault_tokens, IsZero};
+use crate::contract::COMPOUND_REPLY_ID;
+use crate::error::{ContractError, ContractResponse, ContractResult};
+use crate::execute::migrate_pool;
+use crate::helpers::{self, burn_vault_tokens, mint_vault_tokens, IntoInternalCall, IsZero};
+use crate::msg::{ApolloExtensionExecuteMsg, InternalMsg, PoolMigrationMinOut};
 use crate::state::{
-    self, ConfigUnchecked, ConfigUpdates, BASE_TOKEN, CONFIG, STAKING, STATE, VAULT_TOKEN_DENOM,
+    self, Config, ConfigUnchecked, ConfigUpdateProposal, ConfigUpdates, LockDurationReduction,
+    Shutdown, StakingMode, StakingModeUnchecked, VaultTokenMetadata, BASE_TOKEN, CONFIG,
+    CONFIG_UPDATE_PROPOSALS, DONATIONS, LOCK_DURATION_REDUCTION, LOCK_TIER_STATES,
+    NEXT_CONFIG_UPDATE_PROPOSAL_ID, POOL, SHUTDOWN, STAKING, STATE, VAULT_TOKEN,
 };
 
-use cw_dex_astroport::cw_dex::traits::{Stake, Unstake};
-
 pub fn execute_deposit(
     mut deps: DepsMut,
     env: Env,
@@ -23,7 +29,7 @@ pub fn execute_deposit(
 ) -> ContractResponse {
     let cfg = CONFIG.load(deps.storage)?;
     let base_token = BASE_TOKEN.load(deps.storage)?;
-    let vault_token_denom = VAULT_TOKEN_DENOM.load(deps.storage)?;
+    let vault_token = VAULT_TOKEN.load(deps.storage)?;
 
     // Check that deposits are enabled
     if !cfg.deposits_enabled {
@@ -38,21 +44,15 @@ pub fn execute_deposit(
     let staking = STAKING.load(deps.storage)?;
     let staking_res = staking.stake(deps.as_ref(), &env, asset_after_fee.amount)?;
 
-    // Mint vault tokens
-    let (mint_msg, mint_amount) = mint_vault_tokens(
+    // Mint vault tokens to recipient
+    let (mint_msgs, mint_amount) = mint_vault_tokens(
         deps.branch(),
-        env,
+        &env,
         asset_after_fee.amount,
-        &vault_token_denom,
+        &vault_token,
+        &recipient,
     )?;
 
-    // Send minted vault tokens to recipient
-    let send_msg: CosmosMsg = BankMsg::Send {
-        to_address: recipient.to_string(),
-        amount: coins(mint_amount.u128(), vault_token_denom),
-    }
-    .into();
-
     let state = STATE.load(deps.storage)?;
     let event = Event::new("apollo/vaults/execute_deposit")
         .add_attribute("deposit_amount", amount)
@@ -63,45 +63,194 @@ pub fn execute_deposit(
 
     Ok(staking_res
         .add_messages(fee_msgs)
-        .add_message(mint_msg)
-        .add_message(send_msg)
+        .add_messages(mint_msgs)
         .add_event(event))
 }
 
-pub fn execute_redeem(
+/// Deposits `amount` base tokens into the lock tier `lock_tier` and mints
+/// shares of the tier to `recipient`.
+pub fn execute_deposit_to_lock_tier(
     mut deps: DepsMut,
     env: Env,
-    info: MessageInfo,
+    lock_tier: u32,
     amount: Uint128,
     recipient: Addr,
-    force_redeem: bool,
 ) -> ContractResponse {
     let cfg = CONFIG.load(deps.storage)?;
     let base_token = BASE_TOKEN.load(deps.storage)?;
-    let vt_denom = VAULT_TOKEN_DENOM.load(deps.storage)?;
+
+    // Check that deposits are enabled and that the lock tier exists
+    if !cfg.deposits_enabled {
+        return Err(ContractError::DepositsDisabled {});
+    }
+    if cfg.lock_tiers.get(lock_tier as usize).is_none() {
+        return Err(ContractError::LockTierNotFound { lock_tier });
+    }
+
+    // Take deposit fee if set
+    let deposit_asset = Asset::new(base_token, amount);
+    let (fee_msgs, asset_after_fee) = cfg.deposit_fee.fee_msgs_from_asset(deposit_asset, &env)?;
+
+    // Stake deposited LP tokens
     let staking = STAKING.load(deps.storage)?;
+    let staking_res = staking.stake(deps.as_ref(), &env, asset_after_fee.amount)?;
 
-    // Check that only vault tokens were sent and that the amount is correct
-    helpers::assert_correct_funds(&info, &vt_denom, amount)?;
+    // Mint shares of the lock tier to recipient
+    let mint_amount = helpers::mint_lock_tier_shares(
+        deps.branch(),
+        lock_tier,
+        asset_after_fee.amount,
+        &recipient,
+    )?;
+
+    let lock_tier_state = LOCK_TIER_STATES.load(deps.storage, lock_tier)?;
+    let event = Event::new("apollo/vaults/execute_deposit_to_lock_tier")
+        .add_attribute("lock_tier", lock_tier.to_string())
+        .add_attribute("recipient", recipient.to_string())
+        .add_attribute("deposit_amount", amount)
+        .add_attribute("deposit_fee_amount", amount - asset_after_fee.amount)
+        .add_attribute("lock_tier_shares_minted", mint_amount)
+        .add_attribute(
+            "lock_tier_staked_base_tokens_after_action",
+            lock_tier_state.staked_base_tokens,
+        )
+        .add_attribute(
+            "lock_tier_shares_after_action",
+            lock_tier_state.vault_token_supply,
+        );
+
+    Ok(staking_res.add_messages(fee_msgs).add_event(event))
+}
+
+/// Compounds the vault and then deposits `amount` base tokens, which the
+/// caller must have checked were received, for `recipient`. If `lock_tier` is
+/// set, the base tokens are deposited into that lock tier instead of minting
+/// vault tokens. The compounding is done in a SubMsg so that the deposit still
+/// succeeds if compounding fails.
+pub fn execute_compound_and_deposit(
+    env: &Env,
+    amount: Uint128,
+    recipient: Addr,
+    lock_tier: Option<u32>,
+) -> ContractResponse {
+    let compound_msg = SubMsg::reply_on_error(
+        InternalMsg::Compound {
+            discount_deposit: amount,
+        }
+        .into_internal_call(env, vec![])?,
+        COMPOUND_REPLY_ID,
+    );
+    let deposit_msg = match lock_tier {
+        Some(lock_tier) => InternalMsg::DepositToLockTier {
+            lock_tier,
+            amount,
+            recipient,
+        },
+        None => InternalMsg::Deposit { amount, recipient },
+    }
+    .into_internal_call(env, vec![])?;
+
+    Ok(Response::new()
+        .add_submessage(compound_msg)
+        .add_message(deposit_msg))
+}
+
+/// Compounds the vault and then redeems `amount` vault tokens for
+/// `recipient`. The compounding is done in a SubMsg so that the vault tokens
+/// can still be redeemed if compounding fails. `funds` are forwarded to the
+/// internal redeem call.
+pub fn execute_compound_and_redeem(
+    env: &Env,
+    amount: Uint128,
+    recipient: Addr,
+    funds: Vec<Coin>,
+) -> ContractResponse {
+    let compound_msg = SubMsg::reply_on_error(
+        ApolloExtensionExecuteMsg::Compound {}.into_internal_call(env, vec![])?,
+        COMPOUND_REPLY_ID,
+    );
+    let redeem_msg = InternalMsg::Redeem { amount, recipient }.into_internal_call(env, funds)?;
+
+    Ok(Response::new()
+        .add_submessage(compound_msg)
+        .add_message(redeem_msg))
+}
+
+/// Burns `amount` vault tokens, which the caller must have checked were sent
+/// to the vault, and either sends the released base tokens to `recipient` or
+/// creates a claim for them. Nothing is locked for force redeems or once the
+/// vault has been shut down.
+pub fn execute_redeem(
+    mut deps: DepsMut,
+    env: Env,
+    amount: Uint128,
+    recipient: Addr,
+    force_redeem: bool,
+) -> ContractResponse {
+    let cfg = CONFIG.load(deps.storage)?;
+    let vault_token = VAULT_TOKEN.load(deps.storage)?;
 
     // Calculate claim amount and create msg to burn vault tokens
-    let (burn_msg, claim_amount) = burn_vault_tokens(deps.branch(), &env, amount, &vt_denom)?;
+    let (burn_msg, claim_amount) = burn_vault_tokens(deps.branch(), &env, amount, &vault_token)?;
+
+    // Release the base tokens, or lock them if this is not a force redeem and
+    // the vault has not been shut down
+    let lock_duration = if force_redeem || state::is_shut_down(deps.storage)? {
+        Duration::Time(0)
+    } else {
+        cfg.lock_duration
+    };
+    let (res, fee_amount) = release_base_tokens(
+        deps.branch(),
+        &env,
+        claim_amount,
+        &recipient,
+        lock_duration,
+        None,
+    )?;
+
+    let state = STATE.load(deps.storage)?;
+    let event = Event::new("apollo/vaults/execute_redeem")
+        .add_attribute("is_force_redeem", format!("{}", force_redeem))
+        .add_attribute("vault_tokens_redeemed", amount)
+        .add_attribute("lp_tokens_claimed", claim_amount)
+        .add_attribute("withdrawal_fee_amount", fee_amount)
+        .add_attribute("staked_base_tokens_after_action", state.staked_base_tokens)
+        .add_attribute("vault_token_supply_after_action", state.vault_token_supply);
+
+    Ok(res.add_message(burn_msg).add_event(event))
+}
+
+/// Takes the withdrawal fee from `claim_amount` base tokens that were released
+/// from the vault's accounting. If `lock_duration` is zero, the rest is
+/// unstaked and sent to `recipient`, else a claim is created for `recipient`
+/// so they can call `WithdrawUnlocked` later. The claim records `lock_tier` if
+/// the base tokens were unlocked from a lock tier. Returns the response
+/// together with the fee amount.
+pub(crate) fn release_base_tokens(
+    deps: DepsMut,
+    env: &Env,
+    claim_amount: Uint128,
+    recipient: &Addr,
+    lock_duration: Duration,
+    lock_tier: Option<u32>,
+) -> ContractResult<(Response, Uint128)> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let base_token = BASE_TOKEN.load(deps.storage)?;
+    let staking = STAKING.load(deps.storage)?;
 
     // Deduct withdrawal fee if set
     let claim_asset = Asset::new(base_token.clone(), claim_amount);
-    let (fee_msgs, asset_after_fee) = cfg.withdrawal_fee.fee_msgs_from_asset(claim_asset, &env)?;
+    let (fee_msgs, asset_after_fee) = cfg.withdrawal_fee.fee_msgs_from_asset(claim_asset, env)?;
     let fee_amount = claim_amount - asset_after_fee.amount;
     let claim_amount_after_fee = asset_after_fee.amount;
 
-    // If lock duration is zero or this is a force redeem, unstake LP tokens and
-    // send them to recipient, else create a claim for recipient so they can
-    // call `WithdrawUnlocked` later.
-    let res = if cfg.lock_duration.is_zero() || force_redeem {
+    let res = if lock_duration.is_zero() {
         // Unstake LP tokens for claim plus fee
-        let res = staking.unstake(deps.as_ref(), &env, claim_amount)?;
+        let res = staking.unstake(deps.as_ref(), env, claim_amount)?;
 
         // Send LP tokens to recipient
-        let send_msg = Asset::new(base_token, claim_amount_after_fee).transfer_msg(&recipient)?;
+        let send_msg = Asset::new(base_token, claim_amount_after_fee).transfer_msg(recipient)?;
 
         res.add_message(send_msg)
     } else {
@@ -109,53 +258,433 @@ pub fn execute_redeem(
         let res = if fee_amount.is_zero() {
             Response::new()
         } else {
-            staking.unstake(deps.as_ref(), &env, fee_amount)?
+            staking.unstake(deps.as_ref(), env, fee_amount)?
         };
 
         // Create claim for recipient
         let claim = state::claims().create_claim(
             deps.storage,
-            &recipient,
+            recipient,
             claim_amount_after_fee,
-            cfg.lock_duration.after(&env.block),
+            lock_duration.after(&env.block),
+            &env.block,
         )?;
+        if let Some(lock_tier) = lock_tier {
+            state::claims().set_lock_tier(deps.storage, claim.id, lock_tier)?;
+        }
+        let nft_msg = helpers::mint_unlocking_position_nft_msg(deps.as_ref(), &claim)?;
         let event = Event::new(UNLOCKING_POSITION_CREATED_EVENT_TYPE)
             .add_attribute(UNLOCKING_POSITION_ATTR_KEY, format!("{}", claim.id));
-        res.add_event(event)
+        res.add_messages(nft_msg).add_event(event)
     };
 
-    let state = STATE.load(deps.storage)?;
-    let event = Event::new("apollo/vaults/execute_redeem")
-        .add_attribute("is_force_redeem", format!("{}", force_redeem))
-        .add_attribute("vault_tokens_redeemed", amount)
-        .add_attribute("lp_tokens_claimed", claim_amount)
-        .add_attribute("withdrawal_fee_amount", fee_amount)
-        .add_attribute("staked_base_tokens_after_action", state.staked_base_tokens)
-        .add_attribute("vault_token_supply_after_action", state.vault_token_supply);
-
-    Ok(res
-        .add_message(burn_msg)
-        .add_messages(fee_msgs)
-        .add_event(event))
+    Ok((res.add_messages(fee_msgs), fee_amount))
 }
 
 pub fn execute_update_config(
     deps: DepsMut,
     info: MessageInfo,
     updates: ConfigUpdates<String>,
+    shorten_existing_claims: bool,
 ) -> ContractResponse {
     cw_ownable::assert_owner(deps.storage, &info.sender)?;
 
     let event = Event::new("apollo/vaults/execute_update_config")
-        .add_attribute("updates", format!("{:?}", updates));
+        .add_attribute("updates", format!("{:?}", updates))
+        .add_attribute(
+            "shorten_existing_claims",
+            shorten_existing_claims.to_string(),
+        );
 
-    let mut config: ConfigUnchecked = CONFIG.load(deps.storage)?.into();
+    let config = CONFIG.load(deps.storage)?;
 
-    updates.apply_to(&mut config);
+    // Updates that could be used to redirect funds must go through the timelock
+    if !updates.can_bypass_timelock(&config) {
+        return Err(ContractError::ConfigUpdateRequiresTimelock {});
+    }
+
+    // Existing claims are shortened in batches by `ApplyLockDurationReduction`,
+    // which must finish before another reduction can be started
+    if shorten_existing_claims {
+        let lock_duration = updates
+            .lock_duration
+            .ok_or(ContractError::LockDurationNotUpdated {})?;
+        if LOCK_DURATION_REDUCTION.may_load(deps.storage)?.is_some() {
+            return Err(ContractError::LockDurationReductionPending {});
+        }
+        let end = state::claims().query_next_claim_id(deps.storage)?;
+        LOCK_DURATION_REDUCTION.save(
+            deps.storage,
+            &LockDurationReduction {
+                lock_duration,
+                end,
+                start_after: None,
+            },
+        )?;
+    } else {
+        clear_lock_duration_reduction(deps.storage, &config, &updates);
+    }
 
-    let config = config.check(deps.as_ref())?;
+    let config = apply_config_updates(deps.as_ref(), config, updates)?;
 
     CONFIG.save(deps.storage, &config)?;
 
     Ok(Response::new().add_event(event))
 }
+
+pub fn execute_propose_config_update(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    updates: ConfigUpdates<String>,
+    staking_mode: Option<StakingModeUnchecked>,
+    pool: Option<String>,
+) -> ContractResponse {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    let config = CONFIG.load(deps.storage)?;
+
+    // Validate the updates against the current config so that invalid proposals
+    // are rejected early. They are validated again when executed.
+    apply_config_updates(deps.as_ref(), config.clone(), updates.clone())?;
+    if let Some(staking_mode) = &staking_mode {
+        staking_mode.check(deps.api, &BASE_TOKEN.load(deps.storage)?)?;
+    }
+    if let Some(pool) = &pool {
+        // The staking mode is migrated to the new LP token along with the pool
+        if staking_mode.is_some() {
+            return Err(StdError::generic_err(
+                "A pool migration can not be proposed together with a staking mode update",
+            )
+            .into());
+        }
+        migrate_pool::validate_new_pool(deps.as_ref(), pool)?;
+    }
+
+    let proposal =
+        save_config_update_proposal(deps.storage, &env, &config, updates, staking_mode, pool)?;
+
+    let event = Event::new("apollo/vaults/execute_propose_config_update")
+        .add_attribute("proposal_id", proposal.id.to_string())
+        .add_attribute("updates", format!("{:?}", proposal.updates))
+        .add_attribute("staking_mode", format!("{:?}", proposal.staking_mode))
+        .add_attribute("pool", format!("{:?}", proposal.pool))
+        .add_attribute("executable_at", proposal.executable_at.to_string());
+
+    Ok(Response::new().add_event(event))
+}
+
+/// Stores a new config update proposal that can be executed after the config
+/// update delay has passed. The updates must already be validated.
+pub(crate) fn save_config_update_proposal(
+    storage: &mut dyn Storage,
+    env: &Env,
+    config: &Config,
+    updates: ConfigUpdates<String>,
+    staking_mode: Option<StakingModeUnchecked>,
+    pool: Option<String>,
+) -> StdResult<ConfigUpdateProposal> {
+    let id = NEXT_CONFIG_UPDATE_PROPOSAL_ID
+        .may_load(storage)?
+        .unwrap_or_default();
+    NEXT_CONFIG_UPDATE_PROPOSAL_ID.save(storage, &(id + 1))?;
+
+    let proposal = ConfigUpdateProposal {
+        id,
+        updates,
+        staking_mode,
+        pool,
+        proposed_at: env.block.time,
+        executable_at: env.block.time.plus_seconds(config.config_update_delay),
+    };
+    CONFIG_UPDATE_PROPOSALS.save(storage, id, &proposal)?;
+
+    Ok(proposal)
+}
+
+pub fn execute_execute_config_update(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+    pool_migration_min_out: Option<PoolMigrationMinOut>,
+) -> ContractResponse {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    let proposal = CONFIG_UPDATE_PROPOSALS.load(deps.storage, proposal_id)?;
+    if env.block.time < proposal.executable_at {
+        return Err(ContractError::ConfigUpdateTimelockNotExpired {
+            id: proposal_id,
+            executable_at: proposal.executable_at,
+        });
+    }
+    CONFIG_UPDATE_PROPOSALS.remove(deps.storage, proposal_id);
+
+    let event = Event::new("apollo/vaults/execute_execute_config_update")
+        .add_attribute("proposal_id", proposal_id.to_string())
+        .add_attribute("updates", format!("{:?}", proposal.updates))
+        .add_attribute("staking_mode", format!("{:?}", proposal.staking_mode))
+        .add_attribute("pool", format!("{:?}", proposal.pool));
+
+    let config = CONFIG.load(deps.storage)?;
+    clear_lock_duration_reduction(deps.storage, &config, &proposal.updates);
+    let config = apply_config_updates(deps.as_ref(), config, proposal.updates)?;
+
+    CONFIG.save(deps.storage, &config)?;
+
+    let res = match (proposal.staking_mode, proposal.pool) {
+        (Some(staking_mode), _) => {
+            let staking_mode = staking_mode.check(deps.api, &BASE_TOKEN.load(deps.storage)?)?;
+            migrate_staking_mode(deps, &env, staking_mode)?
+        }
+        (None, Some(pool)) => {
+            let min_out = pool_migration_min_out.ok_or_else(|| {
+                StdError::generic_err("pool_migration_min_out is required to migrate the pool")
+            })?;
+            let new_pool = migrate_pool::validate_new_pool(deps.as_ref(), &pool)?;
+            migrate_pool::migrate_pool(deps, &env, new_pool, min_out)?
+        }
+        (None, None) => Response::new(),
+    };
+
+    Ok(res.add_event(event))
+}
+
+/// Removes the lock duration reduction that is being applied to existing
+/// claims, if `updates` change the lock duration, so that claims are never
+/// shortened to a lock duration that is no longer in effect.
+fn clear_lock_duration_reduction(
+    storage: &mut dyn Storage,
+    config: &Config,
+    updates: &ConfigUpdates<String>,
+) {
+    if updates
+        .lock_duration
+        .is_some_and(|lock_duration| lock_duration != config.lock_duration)
+    {
+        LOCK_DURATION_REDUCTION.remove(storage);
+    }
+}
+
+/// Shuts the vault down for good. All LP tokens are unstaked and held by the
+/// vault, deposits and compounding are disabled and every unlocking position is
+/// matured, so that depositors can withdraw their share of the LP tokens
+/// without waiting for the lock duration. Only callable by the owner.
+pub fn execute_shutdown(deps: DepsMut, env: Env, info: MessageInfo) -> ContractResponse {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    SHUTDOWN.save(deps.storage, &Shutdown::from(&env.block))?;
+    CONFIG.update(deps.storage, |mut config| -> StdResult<_> {
+        config.deposits_enabled = false;
+        Ok(config)
+    })?;
+
+    let event = Event::new("apollo/vaults/execute_shutdown")
+        .add_attribute("height", env.block.height.to_string())
+        .add_attribute("time", env.block.time.to_string());
+
+    // Unstake all LP tokens and hold them in the vault
+    let res = migrate_staking_mode(deps, &env, StakingMode::Hold)?;
+
+    Ok(res.add_event(event))
+}
+
+pub fn execute_update_staking_mode(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    staking_mode: StakingModeUnchecked,
+) -> ContractResponse {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    // Only unstaking can be done without the timelock, since staking in another
+    // contract could be used to redirect funds
+    let staking_mode = staking_mode.check(deps.api, &BASE_TOKEN.load(deps.storage)?)?;
+    if staking_mode != StakingMode::Hold {
+        return Err(ContractError::ConfigUpdateRequiresTimelock {});
+    }
+
+    migrate_staking_mode(deps, &env, staking_mode)
+}
+
+/// Unstakes all LP tokens from the current staking mode, stakes them in
+/// `staking_mode` and stores it as the new staking mode. `STATE` is not
+/// changed since the vault holds the same amount of LP tokens after the
+/// migration.
+fn migrate_staking_mode(deps: DepsMut, env: &Env, staking_mode: StakingMode) -> ContractResponse {
+    let old_staking_mode = STAKING.load(deps.storage)?;
+    let amount = old_staking_mode.query_staked_amount(deps.as_ref(), env)?;
+
+    let mut res = Response::new();
+    if !amount.is_zero() {
+        res = old_staking_mode.unstake(deps.as_ref(), env, amount)?;
+    }
+
+    STAKING.save(deps.storage, &staking_mode)?;
+
+    if !amount.is_zero() {
+        let stake_res = staking_mode.stake(deps.as_ref(), env, amount)?;
+        res = res
+            .add_submessages(stake_res.messages)
+            .add_attributes(stake_res.attributes)
+            .add_events(stake_res.events);
+    }
+
+    let event = Event::new("apollo/vaults/execute_update_staking_mode")
+        .add_attribute("old_staking_mode", format!("{:?}", old_staking_mode))
+        .add_attribute("new_staking_mode", format!("{:?}", staking_mode))
+        .add_attribute("migrated_amount", amount);
+
+    Ok(res.add_event(event))
+}
+
+pub fn execute_cancel_config_update(
+    deps: DepsMut,
+    info: MessageInfo,
+    proposal_id: u64,
+) -> ContractResponse {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    // Load the proposal to return an error if it does not exist
+    CONFIG_UPDATE_PROPOSALS.load(deps.storage, proposal_id)?;
+    CONFIG_UPDATE_PROPOSALS.remove(deps.storage, proposal_id);
+
+    let event = Event::new("apollo/vaults/execute_cancel_config_update")
+        .add_attribute("proposal_id", proposal_id.to_string());
+
+    Ok(Response::new().add_event(event))
+}
+
+/// Stakes the base tokens sent with the message and adds them to the vault's
+/// assets without minting vault tokens. Only native base tokens can be
+/// donated this way.
+pub fn execute_donate(deps: DepsMut, env: Env, info: MessageInfo) -> ContractResponse {
+    let base_token = BASE_TOKEN.load(deps.storage)?;
+    let amount = match &base_token {
+        AssetInfo::Native(denom) => {
+            let amount = info
+                .funds
+                .iter()
+                .find(|coin| &coin.denom == denom)
+                .map(|coin| coin.amount)
+                .unwrap_or_default();
+            helpers::assert_correct_funds(&info, denom, amount)?;
+            amount
+        }
+        AssetInfo::Cw20(_) => {
+            return Err(ContractError::Std(StdError::generic_err(
+                "Cw20 base tokens must be donated with ReceiveMsg::Donate",
+            )))
+        }
+    };
+
+    donate(deps, env, info.sender, amount)
+}
+
+/// Stakes `amount` base tokens, which the caller must have checked were sent
+/// to the vault, and adds them to the vault's assets without minting vault
+/// tokens.
+pub fn donate(deps: DepsMut, env: Env, donor: Addr, amount: Uint128) -> ContractResponse {
+    if amount.is_zero() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Donation amount must be greater than zero",
+        )));
+    }
+
+    // Stake the donated base tokens and distribute them to the vault token and
+    // the lock tiers like compounded base tokens
+    let cfg = CONFIG.load(deps.storage)?;
+    let staking = STAKING.load(deps.storage)?;
+    let res = staking.stake(deps.as_ref(), &env, amount)?;
+    helpers::distribute_compounded_base_tokens(deps.storage, &cfg.lock_tiers, amount)?;
+    let state = STATE.load(deps.storage)?;
+
+    let mut donations = DONATIONS.may_load(deps.storage)?.unwrap_or_default();
+    donations.explicit = donations.explicit.checked_add(amount)?;
+    DONATIONS.save(deps.storage, &donations)?;
+
+    let event = Event::new("apollo/vaults/execute_donate")
+        .add_attribute("donor", donor.to_string())
+        .add_attribute("amount", amount)
+        .add_attribute("staked_base_tokens_after_action", state.staked_base_tokens)
+        .add_attribute("vault_token_supply_after_action", state.vault_token_supply);
+
+    Ok(res.add_event(event))
+}
+
+/// Sends `assets` that were accidentally sent to the vault to `recipient`.
+/// Returns an error if any of the assets could be depositor funds, i.e. the
+/// base token, a reward token, a pool asset or the vault token.
+pub fn execute_rescue_tokens(
+    deps: DepsMut,
+    info: MessageInfo,
+    assets: Vec<AssetUnchecked>,
+    recipient: String,
+) -> ContractResponse {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    let cfg = CONFIG.load(deps.storage)?;
+    let pool = POOL.load(deps.storage)?;
+    let base_token = BASE_TOKEN.load(deps.storage)?;
+    let vault_token = VAULT_TOKEN.load(deps.storage)?.to_asset_info();
+    let recipient = deps.api.addr_validate(&recipient)?;
+
+    let assets = assets
+        .iter()
+        .map(|asset| asset.check(deps.api))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut event = Event::new("apollo/vaults/execute_rescue_tokens")
+        .add_attribute("recipient", recipient.to_string());
+    let mut msgs = vec![];
+    for asset in assets {
+        if asset.info == base_token
+            || asset.info == vault_token
+            || cfg.reward_tokens.contains(&asset.info)
+            || pool.pool_assets.contains(&asset.info)
+        {
+            return Err(ContractError::CannotRescueVaultAsset {
+                asset: asset.info.to_string(),
+            });
+        }
+        event = event.add_attribute("rescued_asset", asset.to_string());
+        msgs.push(asset.transfer_msg(&recipient)?);
+    }
+
+    Ok(Response::new().add_messages(msgs).add_event(event))
+}
+
+/// Sets the bank metadata of the vault token. Only callable by the owner.
+pub fn execute_set_vault_token_metadata(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    metadata: VaultTokenMetadata,
+) -> ContractResponse {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    let msg = helpers::set_vault_token_metadata_msg(deps.as_ref(), &env, &metadata)?;
+
+    let event = Event::new("apollo/vaults/execute_set_vault_token_metadata");
+    Ok(Response::new().add_message(msg).add_event(event))
+}
+
+/// Applies `updates` to `config` and validates the result. Lock tiers can not
+/// be removed, since depositors may own shares of them.
+pub(crate) fn apply_config_updates(
+    deps: Deps,
+    config: Config,
+    updates: ConfigUpdates<String>,
+) -> ContractResult<Config> {
+    if let Some(lock_tiers) = &updates.lock_tiers {
+        if lock_tiers.len() < config.lock_tiers.len() {
+            return Err(ContractError::LockTiersRemoved {});
+        }
+    }
+
+    let mut config: ConfigUnchecked = config.into();
+
+    updates.apply_to(&mut config);
+
+    Ok(config.check(deps)?)
+}
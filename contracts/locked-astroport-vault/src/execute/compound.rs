@@ -1,12 +1,13 @@
 use apollo_cw_asset::{Asset, AssetList};
-use cosmwasm_std::{attr, to_json_binary, DepsMut, Env, Event, Response, StdError, Uint128};
+use cosmwasm_std::{attr, to_json_binary, DepsMut, Env, Event, MessageInfo, Response, Uint128};
 
 use crate::error::ContractResponse;
-use crate::helpers::IntoInternalCall;
+use crate::execute::basic_vault::{apply_config_updates, save_config_update_proposal};
+use crate::helpers::{self, IntoInternalCall};
 use crate::msg::InternalMsg;
-use crate::state::{BASE_TOKEN, CONFIG, POOL, STAKING, STATE};
-
-use cw_dex_astroport::cw_dex::traits::{Rewards, Stake};
+use crate::state::{
+    check_route, ConfigUpdates, BASE_TOKEN, CONFIG, DONATIONS, POOL, STAKING, STATE, VAULT_TOKEN,
+};
 
 pub fn execute_compound(deps: DepsMut, env: Env, discount_deposit: Uint128) -> ContractResponse {
     let staking = STAKING.load(deps.storage)?;
@@ -23,9 +24,15 @@ pub fn execute_compound(deps: DepsMut, env: Env, discount_deposit: Uint128) -> C
     // Provide Liquidity
     let provide_msg = InternalMsg::ProvideLiquidity {}.into_internal_call(&env, vec![])?;
 
-    // Stake LP tokens
+    // Stake LP tokens. Base tokens that are in the vault before compounding
+    // were sent directly to the vault.
     let stake_msg = InternalMsg::StakeLps {
         discount_tokens: discount_deposit,
+        untracked_tokens: helpers::query_untracked_base_tokens(
+            deps.as_ref(),
+            &env,
+            discount_deposit,
+        )?,
     }
     .into_internal_call(&env, vec![])?;
 
@@ -112,14 +119,40 @@ pub fn execute_provide_liquidity(deps: DepsMut, env: Env) -> ContractResponse {
         .add_event(event))
 }
 
-pub fn execute_stake_lps(deps: DepsMut, env: Env, discount_tokens: Uint128) -> ContractResponse {
+pub fn execute_stake_lps(
+    deps: DepsMut,
+    env: Env,
+    discount_tokens: Uint128,
+    untracked_tokens: Uint128,
+) -> ContractResponse {
+    let cfg = CONFIG.load(deps.storage)?;
     let base_token = BASE_TOKEN.load(deps.storage)?;
     let staking = STAKING.load(deps.storage)?;
 
     // Query LP token balance
     let lp_token_balance = base_token.query_balance(&deps.querier, &env.contract.address)?;
 
-    let stake_amount = lp_token_balance.checked_sub(discount_tokens)?;
+    // LP tokens that are held but not staked on behalf of depositors must not
+    // be treated as newly compounded LP tokens
+    let held_amount = staking.held_base_tokens(deps.storage)?;
+
+    let stake_amount = lp_token_balance
+        .checked_sub(discount_tokens)?
+        .checked_sub(held_amount)?;
+
+    // Untracked LP tokens were sent directly to the vault. Either record them as
+    // an implicit donation or leave them unstaked.
+    let implicit_donation = untracked_tokens.min(stake_amount);
+    let stake_amount = if cfg.reject_implicit_donations {
+        stake_amount - implicit_donation
+    } else {
+        if !implicit_donation.is_zero() {
+            let mut donations = DONATIONS.may_load(deps.storage)?.unwrap_or_default();
+            donations.implicit = donations.implicit.checked_add(implicit_donation)?;
+            DONATIONS.save(deps.storage, &donations)?;
+        }
+        stake_amount
+    };
 
     // Return with no messages if there are no LP tokens to stake
     if stake_amount.is_zero() {
@@ -129,18 +162,148 @@ pub fn execute_stake_lps(deps: DepsMut, env: Env, discount_tokens: Uint128) -> C
     // Stake LP tokens
     let staking_res = staking.stake(deps.as_ref(), &env, stake_amount)?;
 
-    // Update total staked amount
-    STATE.update(deps.storage, |mut state| {
-        state.staked_base_tokens += stake_amount;
-        Ok::<_, StdError>(state)
-    })?;
+    // Update total staked amounts of the vault token and the lock tiers
+    helpers::distribute_compounded_base_tokens(deps.storage, &cfg.lock_tiers, stake_amount)?;
 
     let state = STATE.load(deps.storage)?;
     let event = Event::new("apollo/vaults/execute_compound")
         .add_attribute("action", "stake_lps")
         .add_attribute("amount_to_stake", stake_amount.to_string())
+        .add_attribute("implicit_donation", implicit_donation)
+        .add_attribute(
+            "implicit_donation_rejected",
+            cfg.reject_implicit_donations.to_string(),
+        )
         .add_attribute("staked_base_tokens_after_action", state.staked_base_tokens)
         .add_attribute("vault_token_supply_after_action", state.vault_token_supply);
 
     Ok(staking_res.add_event(event))
 }
+
+/// Queries the Astroport incentives contract for reward tokens that are not
+/// yet in `Config::reward_tokens` and proposes a config update that appends
+/// the ones that the router can route to the reward liquidation target. The
+/// new reward tokens are subject to the config update delay like any other
+/// proposal. Can only be called by the owner.
+pub fn execute_discover_reward_tokens(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> ContractResponse {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    let cfg = CONFIG.load(deps.storage)?;
+    let staking = STAKING.load(deps.storage)?;
+
+    let mut reward_tokens = cfg.reward_tokens.clone();
+    let mut added = vec![];
+    let mut unroutable = vec![];
+    for asset_info in helpers::query_incentives_reward_tokens(deps.as_ref(), &staking)? {
+        if reward_tokens.contains(&asset_info) {
+            continue;
+        }
+        match check_route(
+            deps.as_ref(),
+            &cfg.router,
+            &asset_info,
+            &cfg.reward_liquidation_target,
+        ) {
+            Ok(()) => {
+                added.push(asset_info.to_string());
+                reward_tokens.push(asset_info);
+            }
+            Err(_) => unroutable.push(asset_info.to_string()),
+        }
+    }
+
+    // Attribute values must not be empty
+    let mut event = Event::new("apollo/vaults/execute_discover_reward_tokens");
+    if !unroutable.is_empty() {
+        event = event.add_attribute("unroutable_reward_tokens", unroutable.join(","));
+    }
+    if !added.is_empty() {
+        event = event.add_attribute("added_reward_tokens", added.join(","));
+        let updates = ConfigUpdates {
+            reward_tokens: Some(reward_tokens.into_iter().map(Into::into).collect()),
+            ..Default::default()
+        };
+        apply_config_updates(deps.as_ref(), cfg.clone(), updates.clone())?;
+        let proposal = save_config_update_proposal(deps.storage, &env, &cfg, updates, None, None)?;
+        event = event
+            .add_attribute("proposal_id", proposal.id.to_string())
+            .add_attribute("executable_at", proposal.executable_at.to_string());
+    }
+
+    Ok(Response::new().add_event(event))
+}
+
+/// Sells all idle balances of at most `Config::dust_threshold` that are not
+/// the base token, a reward token, a pool asset or the vault token into the
+/// reward liquidation target, and then provides liquidity and stakes the
+/// received LP tokens. Used to recover funds stranded by partially filled
+/// swaps or router leftovers in intermediate tokens. Larger balances are left
+/// to be rescued with `RescueTokens`, and balances that the router can not
+/// route to the reward liquidation target are skipped. Can only be called by
+/// the owner.
+pub fn execute_sweep_dust(deps: DepsMut, env: Env, info: MessageInfo) -> ContractResponse {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    let cfg = CONFIG.load(deps.storage)?;
+    let pool = POOL.load(deps.storage)?;
+    let base_token = BASE_TOKEN.load(deps.storage)?;
+    let vault_token = VAULT_TOKEN.load(deps.storage)?.to_asset_info();
+
+    let mut event = Event::new("apollo/vaults/execute_sweep_dust");
+
+    let mut tokens_to_sell = AssetList::new();
+    for asset in helpers::query_idle_balances(deps.as_ref(), &env)? {
+        if asset.amount > cfg.dust_threshold
+            || asset.info == base_token
+            || asset.info == vault_token
+            || cfg.reward_tokens.contains(&asset.info)
+            || pool.pool_assets.contains(&asset.info)
+        {
+            continue;
+        }
+        match check_route(
+            deps.as_ref(),
+            &cfg.router,
+            &asset.info,
+            &cfg.reward_liquidation_target,
+        ) {
+            Ok(()) => {
+                event = event.add_attribute("sold_token", asset.to_string());
+                tokens_to_sell.add(&asset)?;
+            }
+            Err(_) => event = event.add_attribute("unroutable_token", asset.to_string()),
+        }
+    }
+
+    let mut res = Response::new();
+    if tokens_to_sell.len() > 0 {
+        res = res.add_messages(cfg.router.basket_liquidate_msgs(
+            tokens_to_sell,
+            &cfg.reward_liquidation_target,
+            None,
+            None,
+        )?);
+    }
+
+    // Provide liquidity with the liquidation target and any leftover pool
+    // assets, and stake the LP tokens
+    let provide_msg = InternalMsg::ProvideLiquidity {}.into_internal_call(&env, vec![])?;
+    let stake_msg = InternalMsg::StakeLps {
+        discount_tokens: Uint128::zero(),
+        untracked_tokens: helpers::query_untracked_base_tokens(
+            deps.as_ref(),
+            &env,
+            Uint128::zero(),
+        )?,
+    }
+    .into_internal_call(&env, vec![])?;
+
+    Ok(res
+        .add_message(provide_msg)
+        .add_message(stake_msg)
+        .add_event(event))
+}
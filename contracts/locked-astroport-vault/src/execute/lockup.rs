@@ -1,11 +1,21 @@
 use apollo_cw_asset::Asset;
-use cosmwasm_std::{DepsMut, Env, Event, MessageInfo, Response, StdError, Uint128};
+use cosmwasm_std::{Addr, DepsMut, Env, Event, MessageInfo, Response, StdError, Uint128};
+use cw_utils::Duration;
+use cw_vault_standard::extensions::lockup::{
+    UNLOCKING_POSITION_ATTR_KEY, UNLOCKING_POSITION_CREATED_EVENT_TYPE,
+};
 
-use crate::error::{ContractError, ContractResponse};
-use crate::helpers::unwrap_recipient;
-use crate::state::{self, BASE_TOKEN, FORCE_WITHDRAW_WHITELIST, STAKING};
-
-use cw_dex_astroport::cw_dex::traits::Unstake;
+use crate::error::{ContractError, ContractResponse, ContractResult};
+use crate::execute::basic_vault::release_base_tokens;
+use crate::helpers::{
+    assert_unlocking_positions_not_nfts, burn_lock_tier_shares, burn_vault_tokens,
+    distribute_compounded_base_tokens, mint_lock_tier_shares, mint_vault_tokens,
+    release_unlocking_position_nft_msg, unwrap_recipient,
+};
+use crate::state::{
+    self, EmergencyUnlockPenalty, BASE_TOKEN, CONFIG, FORCE_WITHDRAW_WHITELIST,
+    LOCK_DURATION_REDUCTION, LOCK_TIER_STATES, STAKING, STATE, VAULT_TOKEN,
+};
 
 pub fn execute_withdraw_unlocked(
     deps: DepsMut,
@@ -17,8 +27,14 @@ pub fn execute_withdraw_unlocked(
     let base_token = BASE_TOKEN.load(deps.storage)?;
     let recipient = unwrap_recipient(recipient, &info, deps.api)?;
 
-    // Calculate amount of LP tokens available to claim
-    let claim_amount = state::claims().claim_tokens(deps.storage, &env.block, &info, lockup_id)?;
+    // Calculate amount of LP tokens available to claim. All claims are matured
+    // once the vault has been shut down.
+    let claim_amount = if state::is_shut_down(deps.storage)? {
+        state::claims().force_claim(deps.storage, &info, lockup_id, None)?
+    } else {
+        state::claims().claim_tokens(deps.storage, &env.block, &info, lockup_id)?
+    };
+    let nft_msg = release_unlocking_position_nft_msg(deps.as_ref(), lockup_id, &info.sender)?;
 
     // Unstake LP tokens
     let staking = STAKING.load(deps.storage)?;
@@ -31,9 +47,214 @@ pub fn execute_withdraw_unlocked(
         .add_attribute("lockup_id", format!("{}", lockup_id))
         .add_attribute("claim_amount", claim_amount);
 
+    Ok(res
+        .add_messages(nft_msg)
+        .add_message(send_msg)
+        .add_event(event))
+}
+
+pub fn execute_withdraw_all_unlocked(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: Option<String>,
+    limit: Option<u32>,
+) -> ContractResponse {
+    assert_unlocking_positions_not_nfts(deps.as_ref())?;
+    let base_token = BASE_TOKEN.load(deps.storage)?;
+    let recipient = unwrap_recipient(recipient, &info, deps.api)?;
+
+    // Remove all matured claims and sum up the amount of LP tokens to send
+    let shut_down = state::is_shut_down(deps.storage)?;
+    let (lockup_ids, claim_amount) = state::claims().claim_all_matured(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        limit,
+        shut_down,
+    )?;
+
+    // Unstake LP tokens
+    let staking = STAKING.load(deps.storage)?;
+    let res = staking.unstake(deps.as_ref(), &env, claim_amount)?;
+
+    // Send LP tokens to recipient
+    let send_msg = Asset::new(base_token, claim_amount).transfer_msg(recipient)?;
+
+    let event = Event::new("apollo/vaults/execute_withdraw_all_unlocked")
+        .add_attribute("lockup_ids", format!("{:?}", lockup_ids))
+        .add_attribute("claim_amount", claim_amount);
+
     Ok(res.add_message(send_msg).add_event(event))
 }
 
+/// Removes `amount` base tokens from the claim with id `lockup_id`, or the
+/// whole claim if `amount` is `None`, and mints vault tokens for them to the
+/// caller at the current exchange rate. Claims that were unlocked from a lock
+/// tier are cancelled into shares of that tier instead. The base tokens of
+/// claims are still staked, so nothing needs to be staked. The withdrawal fee
+/// that was taken when the claim was created is not refunded.
+pub fn execute_cancel_unlock(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    lockup_id: u64,
+    amount: Option<Uint128>,
+) -> ContractResponse {
+    let cfg = CONFIG.load(deps.storage)?;
+    let vault_token = VAULT_TOKEN.load(deps.storage)?;
+
+    // Re-entering the vault is a deposit, so deposits must be enabled
+    if !cfg.deposits_enabled {
+        return Err(ContractError::DepositsDisabled {});
+    }
+
+    // The lock tier must be loaded before the claim is removed
+    let lock_tier = state::claims().query_lock_tier(deps.storage, lockup_id)?;
+    let cancel_amount = state::claims().cancel_claim(deps.storage, &info, lockup_id, amount)?;
+    let nft_msg = release_unlocking_position_nft_msg(deps.as_ref(), lockup_id, &info.sender)?;
+
+    let mut event = Event::new("apollo/vaults/execute_cancel_unlock")
+        .add_attribute("lockup_id", format!("{}", lockup_id))
+        .add_attribute("cancel_amount", cancel_amount);
+    let mint_msgs = match lock_tier {
+        Some(lock_tier) => {
+            let mint_amount =
+                mint_lock_tier_shares(deps.branch(), lock_tier, cancel_amount, &info.sender)?;
+
+            let lock_tier_state = LOCK_TIER_STATES.load(deps.storage, lock_tier)?;
+            event = event
+                .add_attribute("lock_tier", lock_tier.to_string())
+                .add_attribute("lock_tier_shares_minted", mint_amount)
+                .add_attribute(
+                    "lock_tier_staked_base_tokens_after_action",
+                    lock_tier_state.staked_base_tokens,
+                )
+                .add_attribute(
+                    "lock_tier_shares_after_action",
+                    lock_tier_state.vault_token_supply,
+                );
+            vec![]
+        }
+        None => {
+            let (mint_msgs, mint_amount) = mint_vault_tokens(
+                deps.branch(),
+                &env,
+                cancel_amount,
+                &vault_token,
+                &info.sender,
+            )?;
+
+            let state = STATE.load(deps.storage)?;
+            event = event
+                .add_attribute("vault_tokens_minted", mint_amount)
+                .add_attribute("staked_base_tokens_after_action", state.staked_base_tokens)
+                .add_attribute("vault_token_supply_after_action", state.vault_token_supply);
+            mint_msgs
+        }
+    };
+
+    Ok(Response::new()
+        .add_messages(nft_msg)
+        .add_messages(mint_msgs)
+        .add_event(event))
+}
+
+/// Burns `amount` shares of the lock tier `lock_tier` owned by the caller and
+/// creates a claim for the released base tokens that matures after the lock
+/// duration of the tier. If the tier has no lock duration or the vault has
+/// been shut down, the base tokens are sent to the caller immediately instead.
+pub fn execute_unlock_from_lock_tier(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    lock_tier: u32,
+    amount: Uint128,
+) -> ContractResponse {
+    let cfg = CONFIG.load(deps.storage)?;
+    let lock_duration = cfg
+        .lock_tiers
+        .get(lock_tier as usize)
+        .ok_or(ContractError::LockTierNotFound { lock_tier })?
+        .lock_duration;
+    let lock_duration = if state::is_shut_down(deps.storage)? {
+        Duration::Time(0)
+    } else {
+        lock_duration
+    };
+
+    let claim_amount = burn_lock_tier_shares(deps.branch(), lock_tier, amount, &info.sender)?;
+    let (res, fee_amount) = release_base_tokens(
+        deps.branch(),
+        &env,
+        claim_amount,
+        &info.sender,
+        lock_duration,
+        Some(lock_tier),
+    )?;
+
+    let lock_tier_state = LOCK_TIER_STATES.load(deps.storage, lock_tier)?;
+    let event = Event::new("apollo/vaults/execute_unlock_from_lock_tier")
+        .add_attribute("lock_tier", lock_tier.to_string())
+        .add_attribute("lock_tier_shares_burned", amount)
+        .add_attribute("lp_tokens_claimed", claim_amount)
+        .add_attribute("withdrawal_fee_amount", fee_amount)
+        .add_attribute(
+            "lock_tier_staked_base_tokens_after_action",
+            lock_tier_state.staked_base_tokens,
+        )
+        .add_attribute(
+            "lock_tier_shares_after_action",
+            lock_tier_state.vault_token_supply,
+        );
+
+    Ok(res.add_event(event))
+}
+
+pub fn execute_transfer_unlocking_position(
+    deps: DepsMut,
+    info: MessageInfo,
+    lockup_id: u64,
+    new_owner: String,
+) -> ContractResponse {
+    assert_unlocking_positions_not_nfts(deps.as_ref())?;
+    let new_owner = deps.api.addr_validate(&new_owner)?;
+
+    let claim = state::claims().transfer_claim(deps.storage, &info, lockup_id, &new_owner)?;
+
+    let event = Event::new("apollo/vaults/execute_transfer_unlocking_position")
+        .add_attribute("lockup_id", format!("{}", lockup_id))
+        .add_attribute("old_owner", claim.owner)
+        .add_attribute("new_owner", new_owner);
+
+    Ok(Response::new().add_event(event))
+}
+
+pub fn execute_split_unlocking_position(
+    deps: DepsMut,
+    info: MessageInfo,
+    lockup_id: u64,
+    amount: Uint128,
+    new_owner: Option<String>,
+) -> ContractResponse {
+    assert_unlocking_positions_not_nfts(deps.as_ref())?;
+    let new_owner = unwrap_recipient(new_owner, &info, deps.api)?;
+
+    let new_claim =
+        state::claims().split_claim(deps.storage, &info, lockup_id, amount, &new_owner)?;
+
+    let created_event = Event::new(UNLOCKING_POSITION_CREATED_EVENT_TYPE)
+        .add_attribute(UNLOCKING_POSITION_ATTR_KEY, format!("{}", new_claim.id));
+    let event = Event::new("apollo/vaults/execute_split_unlocking_position")
+        .add_attribute("lockup_id", format!("{}", lockup_id))
+        .add_attribute("new_lockup_id", format!("{}", new_claim.id))
+        .add_attribute("amount", amount)
+        .add_attribute("old_owner", info.sender)
+        .add_attribute("new_owner", new_owner);
+
+    Ok(Response::new().add_event(created_event).add_event(event))
+}
+
 pub fn execute_update_force_withdraw_whitelist(
     deps: DepsMut,
     _env: Env,
@@ -89,6 +310,7 @@ pub fn execute_force_withdraw_unlocking(
     // Get the claimed amount and update the claim in storage, deleting it if
     // all of the tokens are claimed, or updating it with the remaining amount.
     let claim_amount = state::claims().force_claim(deps.storage, &info, lockup_id, amount)?;
+    let nft_msg = release_unlocking_position_nft_msg(deps.as_ref(), lockup_id, &info.sender)?;
 
     // Unstake LP tokens
     let staking = STAKING.load(deps.storage)?;
@@ -101,5 +323,132 @@ pub fn execute_force_withdraw_unlocking(
         .add_attribute("lockup_id", format!("{}", lockup_id))
         .add_attribute("claim_amount", claim_amount);
 
-    Ok(res.add_message(send_msg).add_event(event))
+    Ok(res
+        .add_messages(nft_msg)
+        .add_message(send_msg)
+        .add_event(event))
+}
+
+pub fn execute_apply_lock_duration_reduction(
+    deps: DepsMut,
+    limit: Option<u32>,
+) -> ContractResponse {
+    let mut reduction = LOCK_DURATION_REDUCTION
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoLockDurationReduction {})?;
+
+    let (shortened, last) = state::claims().shorten_claims(
+        deps.storage,
+        reduction.lock_duration,
+        reduction.end,
+        reduction.start_after,
+        limit,
+    )?;
+
+    // Remove the reduction once all claims have been processed
+    match last {
+        Some(_) => {
+            reduction.start_after = last;
+            LOCK_DURATION_REDUCTION.save(deps.storage, &reduction)?;
+        }
+        None => LOCK_DURATION_REDUCTION.remove(deps.storage),
+    }
+
+    let event = Event::new("apollo/vaults/execute_apply_lock_duration_reduction")
+        .add_attribute("lock_duration", format!("{:?}", reduction.lock_duration))
+        .add_attribute("lockup_ids", format!("{:?}", shortened))
+        .add_attribute("done", last.is_none().to_string());
+
+    Ok(Response::new().add_event(event))
+}
+
+/// Burns `amount` vault tokens, which the caller must have checked were sent
+/// to the vault, and immediately sends the released base tokens minus the
+/// emergency unlock penalty to `recipient`.
+pub fn execute_emergency_unlock(
+    mut deps: DepsMut,
+    env: Env,
+    amount: Uint128,
+    recipient: Addr,
+) -> ContractResponse {
+    let vault_token = VAULT_TOKEN.load(deps.storage)?;
+
+    let (burn_msg, release_amount) = burn_vault_tokens(deps.branch(), &env, amount, &vault_token)?;
+    let (res, penalty_amount) = emergency_exit(deps.branch(), &env, release_amount, &recipient)?;
+
+    let state = STATE.load(deps.storage)?;
+    let event = Event::new("apollo/vaults/execute_emergency_unlock")
+        .add_attribute("vault_tokens_redeemed", amount)
+        .add_attribute("lp_tokens_claimed", release_amount)
+        .add_attribute("penalty_amount", penalty_amount)
+        .add_attribute("staked_base_tokens_after_action", state.staked_base_tokens)
+        .add_attribute("vault_token_supply_after_action", state.vault_token_supply);
+
+    Ok(res.add_message(burn_msg).add_event(event))
+}
+
+pub fn execute_emergency_withdraw_unlocking(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    lockup_id: u64,
+    recipient: Option<String>,
+) -> ContractResponse {
+    let recipient = unwrap_recipient(recipient, &info, deps.api)?;
+
+    // Remove the whole claim regardless of whether it has matured
+    let claim_amount = state::claims().cancel_claim(deps.storage, &info, lockup_id, None)?;
+    let nft_msg = release_unlocking_position_nft_msg(deps.as_ref(), lockup_id, &info.sender)?;
+
+    let (res, penalty_amount) = emergency_exit(deps.branch(), &env, claim_amount, &recipient)?;
+
+    let state = STATE.load(deps.storage)?;
+    let event = Event::new("apollo/vaults/execute_emergency_withdraw_unlocking")
+        .add_attribute("lockup_id", format!("{}", lockup_id))
+        .add_attribute("claim_amount", claim_amount)
+        .add_attribute("penalty_amount", penalty_amount)
+        .add_attribute("staked_base_tokens_after_action", state.staked_base_tokens);
+
+    Ok(res.add_messages(nft_msg).add_event(event))
+}
+
+/// Unstakes `amount` base tokens that leave the vault before the end of the
+/// lock duration and sends them to `recipient`, minus the emergency unlock
+/// penalty. Returns the response and the penalty amount.
+fn emergency_exit(
+    deps: DepsMut,
+    env: &Env,
+    amount: Uint128,
+    recipient: &Addr,
+) -> ContractResult<(Response, Uint128)> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let base_token = BASE_TOKEN.load(deps.storage)?;
+    let staking = STAKING.load(deps.storage)?;
+
+    let (unstake_amount, penalty_msgs, send_amount) = match cfg.emergency_unlock_penalty {
+        EmergencyUnlockPenalty::Disabled => return Err(ContractError::EmergencyUnlockDisabled {}),
+        EmergencyUnlockPenalty::ToHolders { penalty_rate } => {
+            // The penalty stays staked and is distributed to the vault token
+            // and the lock tiers like compounded base tokens
+            let penalty_amount = amount * penalty_rate;
+            distribute_compounded_base_tokens(deps.storage, &cfg.lock_tiers, penalty_amount)?;
+
+            let send_amount = amount - penalty_amount;
+            (send_amount, vec![], send_amount)
+        }
+        EmergencyUnlockPenalty::ToFeeRecipients(penalty) => {
+            let (penalty_msgs, asset_after_penalty) =
+                penalty.fee_msgs_from_asset(Asset::new(base_token.clone(), amount), env)?;
+            (amount, penalty_msgs, asset_after_penalty.amount)
+        }
+    };
+
+    // Unstake LP tokens and send them to recipient
+    let res = staking.unstake(deps.as_ref(), env, unstake_amount)?;
+    let send_msg = Asset::new(base_token, send_amount).transfer_msg(recipient)?;
+
+    Ok((
+        res.add_message(send_msg).add_messages(penalty_msgs),
+        amount - send_amount,
+    ))
 }
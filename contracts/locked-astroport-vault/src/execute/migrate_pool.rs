@@ -0,0 +1,222 @@
+use apollo_cw_asset::{Asset, AssetList};
+use cosmwasm_std::{
+    Deps, DepsMut, Env, Event, Order, Response, StdError, StdResult, Storage, Uint128,
+};
+use cw_dex_astroport::cw_dex::traits::Pool;
+use cw_dex_astroport::{AstroportPool, AstroportStaking};
+
+use crate::error::{ContractError, ContractResponse, ContractResult};
+use crate::helpers::IntoInternalCall;
+use crate::msg::{InternalMsg, PoolMigrationMinOut};
+use crate::state::{self, StakingMode, BASE_TOKEN, LOCK_TIER_STATES, POOL, STAKING, STATE};
+
+/// Checks that `new_pool_addr` is an Astroport pair with the same pool assets
+/// as the current pool and returns it.
+pub(crate) fn validate_new_pool(deps: Deps, new_pool_addr: &str) -> ContractResult<AstroportPool> {
+    let old_pool = POOL.load(deps.storage)?;
+    let new_pool = AstroportPool::new(
+        deps,
+        deps.api.addr_validate(new_pool_addr)?,
+        old_pool.liquidity_manager.clone(),
+    )?;
+    if new_pool.pair_addr == old_pool.pair_addr {
+        return Err(ContractError::Std(StdError::generic_err(
+            "New pool is the same as the current pool",
+        )));
+    }
+    if new_pool.pool_assets.len() != old_pool.pool_assets.len()
+        || new_pool
+            .pool_assets
+            .iter()
+            .any(|asset| !old_pool.pool_assets.contains(asset))
+    {
+        return Err(ContractError::PoolAssetsMismatch {});
+    }
+    Ok(new_pool)
+}
+
+/// Migrates all liquidity of the vault from the current pool to `new_pool`,
+/// which must have been validated with [`validate_new_pool`]. All LP tokens
+/// are unstaked and withdrawn from the current pool for at least
+/// `min_out.withdrawn_assets`, after which the vault calls itself to provide
+/// liquidity to the new pair and stake the new LP tokens, of which at least
+/// `min_out.base_tokens` must be received. Called when a config update
+/// proposal that migrates the pool is executed.
+pub(crate) fn migrate_pool(
+    deps: DepsMut,
+    env: &Env,
+    new_pool: AstroportPool,
+    min_out: PoolMigrationMinOut,
+) -> ContractResponse {
+    let old_pool = POOL.load(deps.storage)?;
+    let old_base_token = BASE_TOKEN.load(deps.storage)?;
+    let old_staking = STAKING.load(deps.storage)?;
+    let min_withdrawn_assets: AssetList = min_out
+        .withdrawn_assets
+        .iter()
+        .map(|asset| asset.check(deps.api))
+        .collect::<StdResult<Vec<_>>>()?
+        .into();
+
+    // The amount of old LP tokens that the vault holds on behalf of depositors,
+    // including LP tokens in lock tiers and unlocking claims
+    let state = STATE.load(deps.storage)?;
+    let old_base_token_amount = state
+        .staked_base_tokens
+        .checked_add(state::lock_tier_base_tokens(deps.storage)?)?
+        .checked_add(state::claims().query_total_amount(deps.storage)?)?;
+
+    // Unstake and withdraw all liquidity from the old pool
+    let mut res = Response::new();
+    if !old_base_token_amount.is_zero() {
+        res = old_staking.unstake(deps.as_ref(), env, old_base_token_amount)?;
+        let withdraw_res = old_pool.withdraw_liquidity(
+            deps.as_ref(),
+            env,
+            Asset::new(old_base_token.clone(), old_base_token_amount),
+            min_withdrawn_assets,
+        )?;
+        res = res
+            .add_submessages(withdraw_res.messages)
+            .add_events(withdraw_res.events);
+    }
+
+    // Store the new pool, base token and staking mode
+    let new_staking = match &old_staking {
+        StakingMode::Astroport(staking) => StakingMode::Astroport(AstroportStaking {
+            lp_token: new_pool.lp_token.clone(),
+            incentives: staking.incentives.clone(),
+        }),
+        StakingMode::Hold => StakingMode::Hold,
+    };
+    POOL.save(deps.storage, &new_pool)?;
+    BASE_TOKEN.save(deps.storage, &new_pool.lp_token)?;
+    STAKING.save(deps.storage, &new_staking)?;
+
+    // Provide liquidity to the new pair and stake the new LP tokens
+    let provide_msg = InternalMsg::ProvideLiquidity {}.into_internal_call(env, vec![])?;
+    let stake_msg = InternalMsg::StakeMigratedLps {
+        old_base_token_amount,
+        min_base_token_amount: min_out.base_tokens,
+    }
+    .into_internal_call(env, vec![])?;
+
+    let event = Event::new("apollo/vaults/execute_migrate_pool")
+        .add_attribute("old_pool", old_pool.pair_addr.to_string())
+        .add_attribute("new_pool", new_pool.pair_addr.to_string())
+        .add_attribute("old_base_token", old_base_token.to_string())
+        .add_attribute("new_base_token", new_pool.lp_token.to_string())
+        .add_attribute("old_base_token_amount", old_base_token_amount);
+
+    Ok(res
+        .add_message(provide_msg)
+        .add_message(stake_msg)
+        .add_event(event))
+}
+
+/// Stakes all LP tokens of the new pool after a pool migration and converts
+/// the vault's accounting to the new base token. Fails if fewer than
+/// `min_base_token_amount` LP tokens were received from the new pool. The
+/// staked base tokens, the lock tiers and every unlocking claim are scaled by
+/// the amount of new LP tokens received per old LP token, so that the value of
+/// vault tokens, lock tier shares and claims is unchanged.
+pub fn execute_stake_migrated_lps(
+    deps: DepsMut,
+    env: Env,
+    old_base_token_amount: Uint128,
+    min_base_token_amount: Uint128,
+) -> ContractResponse {
+    let base_token = BASE_TOKEN.load(deps.storage)?;
+    let staking = STAKING.load(deps.storage)?;
+
+    let new_base_token_amount = base_token.query_balance(&deps.querier, &env.contract.address)?;
+    if new_base_token_amount < min_base_token_amount {
+        return Err(ContractError::Std(StdError::generic_err(format!(
+            "Received {} new base tokens, but at least {} were expected",
+            new_base_token_amount, min_base_token_amount
+        ))));
+    }
+
+    // Convert claims and lock tiers to the new base token. Any rounding dust is
+    // attributed to the staked base tokens.
+    let (claims_amount, lock_tiers_amount) = if old_base_token_amount.is_zero() {
+        (Uint128::zero(), Uint128::zero())
+    } else {
+        (
+            state::claims().start_rescale(
+                deps.storage,
+                new_base_token_amount,
+                old_base_token_amount,
+            )?,
+            rescale_lock_tiers(deps.storage, new_base_token_amount, old_base_token_amount)?,
+        )
+    };
+    let mut state = STATE.load(deps.storage)?;
+    state.staked_base_tokens = new_base_token_amount
+        .checked_sub(claims_amount)?
+        .checked_sub(lock_tiers_amount)?;
+    STATE.save(deps.storage, &state)?;
+
+    let res = if new_base_token_amount.is_zero() {
+        Response::new()
+    } else {
+        staking.stake(deps.as_ref(), &env, new_base_token_amount)?
+    };
+
+    let event = Event::new("apollo/vaults/execute_migrate_pool")
+        .add_attribute("action", "stake_migrated_lps")
+        .add_attribute("new_base_token_amount", new_base_token_amount)
+        .add_attribute("claims_amount_after_action", claims_amount)
+        .add_attribute("lock_tiers_amount_after_action", lock_tiers_amount)
+        .add_attribute("staked_base_tokens_after_action", state.staked_base_tokens)
+        .add_attribute("vault_token_supply_after_action", state.vault_token_supply);
+
+    Ok(res.add_event(event))
+}
+
+/// Multiplies the staked base tokens of every lock tier by `numerator /
+/// denominator`, rounding down, and returns the new total amount of base
+/// tokens staked in the lock tiers.
+fn rescale_lock_tiers(
+    storage: &mut dyn Storage,
+    numerator: Uint128,
+    denominator: Uint128,
+) -> StdResult<Uint128> {
+    let lock_tiers = LOCK_TIER_STATES
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut total = Uint128::zero();
+    for (lock_tier, mut lock_tier_state) in lock_tiers {
+        lock_tier_state.staked_base_tokens = lock_tier_state
+            .staked_base_tokens
+            .checked_multiply_ratio(numerator, denominator)
+            .map_err(|e| StdError::generic_err(e.to_string()))?;
+        LOCK_TIER_STATES.save(storage, lock_tier, &lock_tier_state)?;
+        total = total.checked_add(lock_tier_state.staked_base_tokens)?;
+    }
+    Ok(total)
+}
+
+/// Converts up to `limit` more unlocking claims to the base token of the pool
+/// that the vault migrated to. The rounding dust is added to the staked base
+/// tokens. Can be called by anyone.
+pub fn execute_apply_claims_rescale(deps: DepsMut, limit: Option<u32>) -> ContractResponse {
+    let claims = state::claims();
+    if claims.query_rescale(deps.storage)?.is_none() {
+        return Err(ContractError::NoClaimsRescale {});
+    }
+
+    let (rescaled, dust, done) = claims.apply_rescale(deps.storage, limit)?;
+
+    let mut state = STATE.load(deps.storage)?;
+    state.staked_base_tokens = state.staked_base_tokens.checked_add(dust)?;
+    STATE.save(deps.storage, &state)?;
+
+    let event = Event::new("apollo/vaults/execute_apply_claims_rescale")
+        .add_attribute("lockup_ids", format!("{:?}", rescaled))
+        .add_attribute("dust", dust)
+        .add_attribute("done", done.to_string());
+
+    Ok(Response::new().add_event(event))
+}
@@ -8,3 +8,10 @@ pub mod compound;
 /// Contains ExecuteMsg handler functions for the lockup and force-unlock
 /// extensions.
 pub mod lockup;
+
+/// Contains ExecuteMsg handler functions for migrating the vault's liquidity
+/// to a new Astroport pair.
+pub mod migrate_pool;
+
+/// Contains ExecuteMsg handler functions for cw20 tokens sent to the vault.
+pub mod receive;
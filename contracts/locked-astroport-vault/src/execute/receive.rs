@@ -0,0 +1,140 @@
+use apollo_cw_asset::AssetInfo;
+use cosmwasm_std::{from_json, DepsMut, Env, MessageInfo, StdError};
+use cw20::Cw20ReceiveMsg;
+use cw721::Cw721ReceiveMsg;
+
+use crate::error::{ContractError, ContractResponse};
+use crate::execute::basic_vault::{
+    donate, execute_compound_and_deposit, execute_compound_and_redeem, execute_redeem,
+};
+use crate::execute::lockup::{
+    execute_cancel_unlock, execute_emergency_unlock, execute_emergency_withdraw_unlocking,
+    execute_force_withdraw_unlocking, execute_withdraw_unlocked,
+};
+use crate::msg::{ReceiveMsg, ReceiveNftMsg};
+use crate::state::{
+    self, VaultToken, BASE_TOKEN, FORCE_WITHDRAW_WHITELIST, UNLOCKING_POSITION_NFT, VAULT_TOKEN,
+};
+
+/// Handles cw20 tokens sent to the vault with `Cw20ExecuteMsg::Send`. Only the
+/// cw20 base token and the cw20 vault token are accepted, and the sender of
+/// the tokens is treated as the caller of the message in `cw20_msg`.
+pub fn execute_receive(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    cw20_msg: Cw20ReceiveMsg,
+) -> ContractResponse {
+    // Only the base token and vault token contracts can call this, since
+    // otherwise anyone could claim to have sent tokens
+    let is_base_token = BASE_TOKEN.load(deps.storage)? == AssetInfo::Cw20(info.sender.clone());
+    let is_vault_token = match VAULT_TOKEN.load(deps.storage)? {
+        VaultToken::Cw20 { addr } => addr == info.sender,
+        VaultToken::TokenFactory { .. } => false,
+    };
+    if !is_base_token && !is_vault_token {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let sender = deps.api.addr_validate(&cw20_msg.sender)?;
+    let amount = cw20_msg.amount;
+    let msg: ReceiveMsg = from_json(&cw20_msg.msg)?;
+
+    // Deposits and donations must be made with the base token and everything
+    // else with the vault token
+    let is_deposit = matches!(
+        msg,
+        ReceiveMsg::Deposit { .. } | ReceiveMsg::DepositToLockTier { .. } | ReceiveMsg::Donate {}
+    );
+    if is_base_token != is_deposit {
+        return Err(ContractError::InvalidReceiveMsg {
+            token: info.sender.to_string(),
+        });
+    }
+
+    match msg {
+        ReceiveMsg::Deposit { recipient } => {
+            let recipient = match recipient {
+                Some(recipient) => deps.api.addr_validate(&recipient)?,
+                None => sender,
+            };
+            execute_compound_and_deposit(&env, amount, recipient, None)
+        }
+        ReceiveMsg::DepositToLockTier {
+            lock_tier,
+            recipient,
+        } => {
+            let recipient = match recipient {
+                Some(recipient) => deps.api.addr_validate(&recipient)?,
+                None => sender,
+            };
+            execute_compound_and_deposit(&env, amount, recipient, Some(lock_tier))
+        }
+        ReceiveMsg::Redeem { recipient } => {
+            let recipient = match recipient {
+                Some(recipient) => deps.api.addr_validate(&recipient)?,
+                None => sender,
+            };
+            execute_compound_and_redeem(&env, amount, recipient, vec![])
+        }
+        ReceiveMsg::Donate {} => donate(deps, env, sender, amount),
+        ReceiveMsg::Unlock {} => execute_redeem(deps, env, amount, sender, false),
+        ReceiveMsg::EmergencyUnlock {} => execute_emergency_unlock(deps, env, amount, sender),
+        ReceiveMsg::ForceRedeem { recipient } => {
+            if !FORCE_WITHDRAW_WHITELIST.contains(deps.storage, &sender) {
+                return Err(ContractError::Unauthorized {});
+            }
+            let recipient = match recipient {
+                Some(recipient) => deps.api.addr_validate(&recipient)?,
+                None => sender,
+            };
+            execute_redeem(deps, env, amount, recipient, true)
+        }
+    }
+}
+
+/// Handles unlocking position NFTs sent to the vault with `SendNft`. The
+/// sender of the NFT becomes the owner of the position in `Claims` and is
+/// treated as the caller of the message in `nft_msg`. The vault holds the NFT
+/// while the message is executed, so it can burn it without being approved.
+pub fn execute_receive_nft(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    nft_msg: Cw721ReceiveMsg,
+) -> ContractResponse {
+    // Only the unlocking position NFT contract can call this, since otherwise
+    // anyone could claim to have sent an NFT
+    if UNLOCKING_POSITION_NFT.may_load(deps.storage)?.as_ref() != Some(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let sender = deps.api.addr_validate(&nft_msg.sender)?;
+    let lockup_id: u64 = nft_msg
+        .token_id
+        .parse()
+        .map_err(|_| StdError::generic_err(format!("Invalid token id {}", nft_msg.token_id)))?;
+    let msg: ReceiveNftMsg = from_json(&nft_msg.msg)?;
+
+    // The holder of the NFT owns the position
+    state::claims().set_owner(deps.storage, lockup_id, &sender)?;
+    let info = MessageInfo {
+        sender,
+        funds: vec![],
+    };
+
+    match msg {
+        ReceiveNftMsg::WithdrawUnlocked { recipient } => {
+            execute_withdraw_unlocked(deps, env, info, recipient, lockup_id)
+        }
+        ReceiveNftMsg::CancelUnlock { amount } => {
+            execute_cancel_unlock(deps, env, info, lockup_id, amount)
+        }
+        ReceiveNftMsg::EmergencyWithdrawUnlocking { recipient } => {
+            execute_emergency_withdraw_unlocking(deps, env, info, lockup_id, recipient)
+        }
+        ReceiveNftMsg::ForceWithdrawUnlocking { amount, recipient } => {
+            execute_force_withdraw_unlocking(deps, env, info, amount, recipient, lockup_id)
+        }
+    }
+}
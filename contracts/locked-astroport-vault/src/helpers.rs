@@ -1,9 +1,17 @@
-use cosmwasm_std::{coin, coins, Addr, Api, CosmosMsg, Deps, DepsMut, Env, Uint128};
+use apollo_cw_asset::{Asset, AssetInfo};
+use cosmwasm_std::{
+    coins, to_json_binary, Addr, Api, CosmosMsg, Decimal, Deps, DepsMut, Env, Order, Storage,
+    Uint128, WasmMsg,
+};
 use cw_utils::Duration;
-use osmosis_std::types::osmosis::tokenfactory::v1beta1::{MsgBurn, MsgMint};
 
+use crate::claims::Claim;
 use crate::error::{ContractError, ContractResult};
-use crate::state::STATE;
+use crate::msg::Cw721ExecuteMsg;
+use crate::state::{
+    self, LockTier, StakingMode, VaultState, VaultToken, VaultTokenMetadata, BASE_TOKEN, CONFIG,
+    LOCK_TIER_SHARES, LOCK_TIER_STATES, POOL, STAKING, STATE, UNLOCKING_POSITION_NFT, VAULT_TOKEN,
+};
 
 use cosmwasm_std::{Coin, MessageInfo, StdResult};
 
@@ -36,50 +44,55 @@ pub fn unwrap_recipient(
     recipient.map_or(Ok(info.sender.clone()), |x| api.addr_validate(&x))
 }
 
-/// Returns the number of vault tokens that will be minted for
-/// `base_token_amount` base tokens.
-pub(crate) fn convert_to_shares(deps: Deps, base_token_amount: Uint128) -> Uint128 {
-    let state = STATE.load(deps.storage).unwrap();
-    // vault_token_supply can be zero when staked_base_tokens is not zero, if there
-    // are rewards in the vault before the first deposit since in this case they
-    // would get compounded and the staked_base_tokens would increase without
-    // minting any vault tokens. Therefore, we base the initial amount of vault
-    // tokens on the total amount of base tokens staked in the vault,
-    // rather than just the deposited amount so that the vault token price cannot be
-    // manipulated.
-    if state.staked_base_tokens.is_zero() || state.vault_token_supply.is_zero() {
-        return (state.staked_base_tokens + base_token_amount)
-            * INITIAL_VAULT_TOKENS_PER_BASE_TOKEN;
+/// Returns the share class of `lock_tier`, or of the vault token if
+/// `lock_tier` is `None`.
+pub(crate) fn load_share_class(
+    storage: &dyn Storage,
+    lock_tier: Option<u32>,
+) -> StdResult<VaultState> {
+    match lock_tier {
+        Some(lock_tier) => Ok(LOCK_TIER_STATES
+            .may_load(storage, lock_tier)?
+            .unwrap_or_default()),
+        None => STATE.load(storage),
     }
-    state
-        .vault_token_supply
-        .multiply_ratio(base_token_amount, state.staked_base_tokens)
 }
 
-/// Returns the number of base tokens that will be released for
-/// `vault_token_amount` vault tokens.
-pub(crate) fn convert_to_assets(deps: Deps, vault_token_amount: Uint128) -> Uint128 {
-    let state = STATE.load(deps.storage).unwrap();
-    if state.vault_token_supply.is_zero() {
-        return vault_token_amount / INITIAL_VAULT_TOKENS_PER_BASE_TOKEN;
-    }
-    state
-        .staked_base_tokens
-        .multiply_ratio(vault_token_amount, state.vault_token_supply)
+/// Returns the number of shares of `lock_tier`, or vault tokens if
+/// `lock_tier` is `None`, that will be minted for `base_token_amount` base
+/// tokens.
+pub(crate) fn convert_to_shares(
+    deps: Deps,
+    lock_tier: Option<u32>,
+    base_token_amount: Uint128,
+) -> Uint128 {
+    load_share_class(deps.storage, lock_tier)
+        .unwrap()
+        .convert_to_shares(base_token_amount)
+}
+
+/// Returns the number of base tokens that will be released for `shares`
+/// shares of `lock_tier`, or vault tokens if `lock_tier` is `None`.
+pub(crate) fn convert_to_assets(deps: Deps, lock_tier: Option<u32>, shares: Uint128) -> Uint128 {
+    load_share_class(deps.storage, lock_tier)
+        .unwrap()
+        .convert_to_assets(shares)
 }
 
-/// Return a token factory mint message to mint `amount` of vault tokens to
-/// `env.contract.address`. Also updates the contract's internal storage of the
-/// total amount of staked base tokens and the total supply of vault tokens.
+/// Return the messages to mint vault tokens for `deposit_amount` base tokens
+/// to `recipient`, in a tuple together with the amount of vault tokens minted.
+/// Also updates the contract's internal storage of the total amount of staked
+/// base tokens and the total supply of vault tokens.
 pub(crate) fn mint_vault_tokens(
     deps: DepsMut,
-    env: Env,
+    env: &Env,
     deposit_amount: Uint128,
-    vault_token_denom: &str,
-) -> ContractResult<(CosmosMsg, Uint128)> {
+    vault_token: &VaultToken,
+    recipient: &Addr,
+) -> ContractResult<(Vec<CosmosMsg>, Uint128)> {
     let mut state = STATE.load(deps.storage)?;
 
-    let mint_amount = convert_to_shares(deps.as_ref(), deposit_amount);
+    let mint_amount = convert_to_shares(deps.as_ref(), None, deposit_amount);
 
     state.staked_base_tokens = state.staked_base_tokens.checked_add(deposit_amount)?;
     state.vault_token_supply = state.vault_token_supply.checked_add(mint_amount)?;
@@ -87,41 +100,181 @@ pub(crate) fn mint_vault_tokens(
     STATE.save(deps.storage, &state)?;
 
     Ok((
-        MsgMint {
-            sender: env.contract.address.to_string(),
-            amount: Some(coin(mint_amount.u128(), vault_token_denom).into()),
-        }
-        .into(),
+        vault_token.mint_msgs(env, mint_amount, recipient)?,
         mint_amount,
     ))
 }
 
-/// Return a token factory burn message to burn `amount` of vault tokens from\
+/// Return a message to burn `amount` of vault tokens held by
 /// `env.contract.address` in a tuple together with the amount of base tokens
 /// that should be released.
 pub(crate) fn burn_vault_tokens(
     deps: DepsMut,
     env: &Env,
     burn_amount: Uint128,
-    vault_token_denom: &str,
+    vault_token: &VaultToken,
 ) -> ContractResult<(CosmosMsg, Uint128)> {
     let mut state = STATE.load(deps.storage)?;
 
-    let release_amount = convert_to_assets(deps.as_ref(), burn_amount);
+    let release_amount = convert_to_assets(deps.as_ref(), None, burn_amount);
 
     state.staked_base_tokens = state.staked_base_tokens.checked_sub(release_amount)?;
     state.vault_token_supply = state.vault_token_supply.checked_sub(burn_amount)?;
 
     STATE.save(deps.storage, &state)?;
 
-    Ok((
-        MsgBurn {
-            sender: env.contract.address.to_string(),
-            amount: Some(coin(burn_amount.u128(), vault_token_denom).into()),
+    Ok((vault_token.burn_msg(env, burn_amount)?, release_amount))
+}
+
+/// Mints shares of `lock_tier` for `deposit_amount` base tokens to
+/// `recipient` and returns the amount of shares minted.
+pub(crate) fn mint_lock_tier_shares(
+    deps: DepsMut,
+    lock_tier: u32,
+    deposit_amount: Uint128,
+    recipient: &Addr,
+) -> ContractResult<Uint128> {
+    let mut state = load_share_class(deps.storage, Some(lock_tier))?;
+
+    let mint_amount = state.convert_to_shares(deposit_amount);
+
+    state.staked_base_tokens = state.staked_base_tokens.checked_add(deposit_amount)?;
+    state.vault_token_supply = state.vault_token_supply.checked_add(mint_amount)?;
+    LOCK_TIER_STATES.save(deps.storage, lock_tier, &state)?;
+
+    LOCK_TIER_SHARES.update(
+        deps.storage,
+        (lock_tier, recipient),
+        |shares| -> ContractResult<_> { Ok(shares.unwrap_or_default().checked_add(mint_amount)?) },
+    )?;
+
+    Ok(mint_amount)
+}
+
+/// Burns `burn_amount` shares of `lock_tier` owned by `owner` and returns the
+/// amount of base tokens that should be released.
+pub(crate) fn burn_lock_tier_shares(
+    deps: DepsMut,
+    lock_tier: u32,
+    burn_amount: Uint128,
+    owner: &Addr,
+) -> ContractResult<Uint128> {
+    let mut state = load_share_class(deps.storage, Some(lock_tier))?;
+
+    let release_amount = state.convert_to_assets(burn_amount);
+
+    LOCK_TIER_SHARES.update(
+        deps.storage,
+        (lock_tier, owner),
+        |shares| -> ContractResult<_> { Ok(shares.unwrap_or_default().checked_sub(burn_amount)?) },
+    )?;
+
+    state.staked_base_tokens = state.staked_base_tokens.checked_sub(release_amount)?;
+    state.vault_token_supply = state.vault_token_supply.checked_sub(burn_amount)?;
+    LOCK_TIER_STATES.save(deps.storage, lock_tier, &state)?;
+
+    Ok(release_amount)
+}
+
+/// Adds `amount` newly staked base tokens to the vault token and the lock
+/// tiers in proportion to their staked base tokens multiplied by their weight.
+/// Rounding dust, and all of `amount` if nothing is staked yet, is added to
+/// the vault token.
+pub(crate) fn distribute_compounded_base_tokens(
+    storage: &mut dyn Storage,
+    lock_tiers: &[LockTier],
+    amount: Uint128,
+) -> StdResult<()> {
+    let mut state = STATE.load(storage)?;
+    let mut tier_states = LOCK_TIER_STATES
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (lock_tier, tier_state) = item?;
+            let weight = lock_tiers
+                .get(lock_tier as usize)
+                .map_or(Decimal::one(), |tier| tier.weight);
+            Ok((
+                lock_tier,
+                tier_state.staked_base_tokens * weight,
+                tier_state,
+            ))
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let total_weight = tier_states
+        .iter()
+        .try_fold(state.staked_base_tokens, |total, (_, weight, _)| {
+            total.checked_add(*weight)
+        })?;
+
+    let mut distributed = Uint128::zero();
+    if !total_weight.is_zero() {
+        for (lock_tier, weight, tier_state) in tier_states.iter_mut() {
+            let tier_amount = amount.multiply_ratio(*weight, total_weight);
+            if tier_amount.is_zero() {
+                continue;
+            }
+            tier_state.staked_base_tokens =
+                tier_state.staked_base_tokens.checked_add(tier_amount)?;
+            LOCK_TIER_STATES.save(storage, *lock_tier, tier_state)?;
+            distributed += tier_amount;
         }
-        .into(),
-        release_amount,
-    ))
+    }
+
+    state.staked_base_tokens = state.staked_base_tokens.checked_add(amount - distributed)?;
+    STATE.save(storage, &state)
+}
+
+/// Asserts that exactly `amount` vault tokens were sent with `info`. Cw20 vault
+/// tokens can not be sent as funds and must be sent to the vault with
+/// `Cw20ExecuteMsg::Send` instead.
+pub fn assert_vault_tokens_sent(
+    info: &MessageInfo,
+    vault_token: &VaultToken,
+    amount: Uint128,
+) -> ContractResult<()> {
+    match vault_token {
+        VaultToken::TokenFactory { denom, .. } => assert_correct_funds(info, denom, amount),
+        VaultToken::Cw20 { .. } => Err(ContractError::Cw20VaultTokenMustBeSent {}),
+    }
+}
+
+/// Queries the Astroport incentives contract for the assets that are
+/// currently distributed as rewards to stakers of `staking.lp_token`, both
+/// internal (ASTRO) and external incentives. Returns no assets if the vault
+/// does not stake its LP tokens.
+pub(crate) fn query_incentives_reward_tokens(
+    deps: Deps,
+    staking: &StakingMode,
+) -> StdResult<Vec<AssetInfo>> {
+    let staking = match staking {
+        StakingMode::Astroport(staking) => staking,
+        StakingMode::Hold => return Ok(vec![]),
+    };
+
+    let reward_infos: Vec<astroport_v5::incentives::RewardInfo> = deps.querier.query_wasm_smart(
+        staking.incentives.to_string(),
+        &astroport_v5::incentives::QueryMsg::RewardInfo {
+            lp_token: staking.lp_token.to_string(),
+        },
+    )?;
+
+    let mut reward_tokens: Vec<AssetInfo> = vec![];
+    for reward_info in reward_infos {
+        let asset_info = match reward_info.reward.asset_info() {
+            astroport_v5::asset::AssetInfo::Token { contract_addr } => {
+                AssetInfo::Cw20(contract_addr.clone())
+            }
+            astroport_v5::asset::AssetInfo::NativeToken { denom } => {
+                AssetInfo::Native(denom.clone())
+            }
+        };
+        // The same asset can be distributed by several schedules
+        if !reward_tokens.contains(&asset_info) {
+            reward_tokens.push(asset_info);
+        }
+    }
+    Ok(reward_tokens)
 }
 
 /// A trait to convert a type into a `CosmosMsg` Execute variant that calls the
@@ -144,3 +297,145 @@ impl IsZero for Duration {
         }
     }
 }
+
+/// Returns all balances that the vault holds outside of the LP tokens that are
+/// staked or held on behalf of its depositors. All native balances are
+/// included, but only cw20 balances of the base token, reward tokens and pool
+/// assets can be queried.
+pub(crate) fn query_idle_balances(deps: Deps, env: &Env) -> StdResult<Vec<Asset>> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let pool = POOL.load(deps.storage)?;
+    let base_token = BASE_TOKEN.load(deps.storage)?;
+    let held_base_tokens = STAKING.load(deps.storage)?.held_base_tokens(deps.storage)?;
+
+    let mut balances: Vec<Asset> = deps
+        .querier
+        .query_all_balances(&env.contract.address)?
+        .into_iter()
+        .map(|coin| Asset::new(AssetInfo::Native(coin.denom), coin.amount))
+        .collect();
+
+    let mut cw20s: Vec<AssetInfo> = vec![];
+    for asset_info in cfg
+        .reward_tokens
+        .iter()
+        .chain(pool.pool_assets.iter())
+        .chain(std::iter::once(&base_token))
+    {
+        if matches!(asset_info, AssetInfo::Cw20(_)) && !cw20s.contains(asset_info) {
+            cw20s.push(asset_info.clone());
+        }
+    }
+    for asset_info in cw20s {
+        let amount = asset_info.query_balance(&deps.querier, &env.contract.address)?;
+        balances.push(Asset::new(asset_info, amount));
+    }
+
+    // LP tokens held on behalf of depositors are not idle
+    for asset in balances.iter_mut() {
+        if asset.info == base_token {
+            asset.amount = asset.amount.checked_sub(held_base_tokens)?;
+        }
+    }
+    balances.retain(|asset| !asset.amount.is_zero());
+
+    Ok(balances)
+}
+
+/// Returns the amount of base tokens in the vault's balance that are neither
+/// staked or held on behalf of depositors, nor part of the `discount_tokens`
+/// that were just deposited. These were sent directly to the vault and are
+/// treated as implicit donations when compounding.
+pub(crate) fn query_untracked_base_tokens(
+    deps: Deps,
+    env: &Env,
+    discount_tokens: Uint128,
+) -> StdResult<Uint128> {
+    let base_token = BASE_TOKEN.load(deps.storage)?;
+    let held_base_tokens = STAKING.load(deps.storage)?.held_base_tokens(deps.storage)?;
+    let balance = base_token.query_balance(&deps.querier, &env.contract.address)?;
+
+    Ok(balance
+        .saturating_sub(discount_tokens)
+        .saturating_sub(held_base_tokens))
+}
+
+/// Returns a token factory message that sets the bank metadata of the vault
+/// token to `metadata`, with unset fields derived from the pool assets. The
+/// metadata of cw20 vault tokens is set when they are instantiated and can not
+/// be changed.
+pub(crate) fn set_vault_token_metadata_msg(
+    deps: Deps,
+    env: &Env,
+    metadata: &VaultTokenMetadata,
+) -> ContractResult<CosmosMsg> {
+    let pool = POOL.load(deps.storage)?;
+    match VAULT_TOKEN.load(deps.storage)? {
+        VaultToken::TokenFactory { denom, flavor } => Ok(flavor
+            .set_denom_metadata_msg(env, metadata.to_bank_metadata(&denom, &pool.pool_assets)?)),
+        VaultToken::Cw20 { .. } => Err(ContractError::Cw20VaultTokenMetadataImmutable {}),
+    }
+}
+
+/// Returns a message to mint an NFT for the unlocking position `claim` to its
+/// owner, if unlocking positions are NFTs. The id of the NFT is the id of the
+/// unlocking position.
+pub(crate) fn mint_unlocking_position_nft_msg(
+    deps: Deps,
+    claim: &Claim,
+) -> StdResult<Option<CosmosMsg>> {
+    UNLOCKING_POSITION_NFT
+        .may_load(deps.storage)?
+        .map(|nft| {
+            Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: nft.to_string(),
+                msg: to_json_binary(&Cw721ExecuteMsg::Mint {
+                    token_id: claim.id.to_string(),
+                    owner: claim.owner.to_string(),
+                    token_uri: None,
+                    extension: None,
+                })?,
+                funds: vec![],
+            }))
+        })
+        .transpose()
+}
+
+/// Returns a message to release the NFT of the unlocking position with id
+/// `lockup_id`, which the vault holds while the message sent along with it is
+/// executed, if unlocking positions are NFTs. The NFT is burned if the position
+/// has been removed from `Claims`, and otherwise sent back to `holder`.
+pub(crate) fn release_unlocking_position_nft_msg(
+    deps: Deps,
+    lockup_id: u64,
+    holder: &Addr,
+) -> StdResult<Option<CosmosMsg>> {
+    let nft = match UNLOCKING_POSITION_NFT.may_load(deps.storage)? {
+        Some(nft) => nft,
+        None => return Ok(None),
+    };
+    let msg = match state::claims().query_claim_by_id(deps, lockup_id) {
+        Ok(_) => Cw721ExecuteMsg::TransferNft {
+            recipient: holder.to_string(),
+            token_id: lockup_id.to_string(),
+        },
+        Err(_) => Cw721ExecuteMsg::Burn {
+            token_id: lockup_id.to_string(),
+        },
+    };
+
+    Ok(Some(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: nft.to_string(),
+        msg: to_json_binary(&msg)?,
+        funds: vec![],
+    })))
+}
+
+/// Returns an error if unlocking positions are NFTs, for messages that act on
+/// unlocking positions without the NFT being sent to the vault.
+pub(crate) fn assert_unlocking_positions_not_nfts(deps: Deps) -> ContractResult<()> {
+    if UNLOCKING_POSITION_NFT.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::UnlockingPositionsAreNfts {});
+    }
+    Ok(())
+}
@@ -22,3 +22,6 @@ pub mod helpers;
 /// Contains the Claims struct for storing claims on locked LP tokens
 /// (UnlockingPositions).
 pub mod claims;
+
+/// Contains the storage migrations that are run in the migrate entrypoint.
+pub mod migrations;
@@ -0,0 +1,82 @@
+use cosmwasm_std::{DepsMut, Env};
+use semver::Version;
+
+use crate::error::{ContractError, ContractResult};
+use crate::msg::MigrateMsg;
+use crate::state::{
+    self, StakingMode, TokenFactoryFlavor, VaultToken, CONFIG, LEGACY_STAKING,
+    LEGACY_VAULT_TOKEN_DENOM, STAKING, VAULT_TOKEN,
+};
+
+/// A storage migration that is run when migrating from a contract version
+/// older than `version`.
+struct MigrationStep {
+    /// The first contract version that uses the storage layout created by this
+    /// step.
+    version: &'static str,
+    /// The function that migrates the storage.
+    migrate: fn(DepsMut, &Env, &MigrateMsg) -> ContractResult<()>,
+}
+
+/// All storage migrations, ordered by version. A step is run for every stored
+/// contract version older than the step's version, so steps must be
+/// idempotent.
+const MIGRATIONS: &[MigrationStep] = &[MigrationStep {
+    version: "0.6.0",
+    migrate: migrate_to_v0_6_0,
+}];
+
+/// Runs all storage migrations for contract versions newer than
+/// `from_version` in order and returns the versions of the steps that were
+/// run.
+pub fn run_migrations(
+    mut deps: DepsMut,
+    env: &Env,
+    from_version: &Version,
+    msg: &MigrateMsg,
+) -> ContractResult<Vec<&'static str>> {
+    let mut applied = vec![];
+    for step in MIGRATIONS {
+        if from_version < &Version::parse(step.version)? {
+            (step.migrate)(deps.branch(), env, msg)?;
+            applied.push(step.version);
+        }
+    }
+    Ok(applied)
+}
+
+/// Converts the stored Astroport staking config to a `StakingMode` and the
+/// stored vault token denom to a `VaultToken`, stores the total amount of base
+/// tokens in claims and sets the config update delay.
+fn migrate_to_v0_6_0(deps: DepsMut, _env: &Env, msg: &MigrateMsg) -> ContractResult<()> {
+    if STAKING.may_load(deps.storage)?.is_none() {
+        let staking = LEGACY_STAKING.load(deps.storage)?;
+        STAKING.save(deps.storage, &StakingMode::Astroport(staking))?;
+        LEGACY_STAKING.remove(deps.storage);
+    }
+
+    if VAULT_TOKEN.may_load(deps.storage)?.is_none() {
+        let denom = LEGACY_VAULT_TOKEN_DENOM.load(deps.storage)?;
+        VAULT_TOKEN.save(
+            deps.storage,
+            &VaultToken::TokenFactory {
+                denom,
+                flavor: TokenFactoryFlavor::Osmosis,
+            },
+        )?;
+        LEGACY_VAULT_TOKEN_DENOM.remove(deps.storage);
+    }
+
+    state::claims().migrate_total_amount(deps.storage)?;
+    state::claims().migrate_indexes(deps.storage)?;
+
+    // Configs stored by older versions have no config update delay, which
+    // would deserialize as zero and disable the timelock
+    let mut config = CONFIG.load(deps.storage)?;
+    config.config_update_delay = msg
+        .config_update_delay
+        .ok_or(ContractError::ConfigUpdateDelayRequired {})?;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(())
+}
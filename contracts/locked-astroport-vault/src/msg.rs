@@ -1,28 +1,39 @@
-use apollo_cw_asset::AssetInfoUnchecked;
+use apollo_cw_asset::{AssetInfoUnchecked, AssetUnchecked};
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{to_json_binary, Addr, Coin, CosmosMsg, Env, StdResult, Uint128};
+use cosmwasm_std::{
+    from_json, to_json_binary, Addr, Coin, CosmosMsg, Empty, Env, StdResult, Uint128,
+};
+use cw20::{Cw20Coin, Cw20ReceiveMsg, MinterResponse};
+use cw721::Cw721ReceiveMsg;
 use cw_dex_router::helpers::CwDexRouterUnchecked;
 use cw_ownable::Action as OwnerAction;
+use cw_utils::{Duration, Expiration};
 use cw_vault_standard::extensions::force_unlock::ForceUnlockExecuteMsg;
 use cw_vault_standard::extensions::lockup::{LockupExecuteMsg, LockupQueryMsg};
 use liquidity_helper::LiquidityHelperUnchecked;
 use strum::{EnumCount, EnumVariantNames};
 
 use crate::helpers::IntoInternalCall;
-use crate::state::{ConfigUpdates, FeeConfig};
+use crate::state::{
+    ConfigUpdates, FeeConfig, StakingModeUnchecked, TokenFactoryFlavor, VaultTokenMetadata,
+};
 
 #[cw_serde]
 pub struct InstantiateMsg {
     /// Contract owner
     pub owner: String,
-    /// Vault token sub-denom
+    /// Vault token sub-denom. Only used by the token factory vault token
+    /// backend.
     pub vault_token_subdenom: String,
+    /// How the vault token is created. Defaults to the token factory module.
+    pub vault_token_backend: Option<VaultTokenBackend>,
     /// Address of the pool.
     pub pool_addr: String,
-    /// Address of the astroport incentives contract
-    pub astroport_incentives_addr: String,
-    /// Lock duration in seconds
-    pub lock_duration: u64,
+    /// Address of the astroport incentives contract. If not set, the vault
+    /// holds the LP tokens without staking them.
+    pub astroport_incentives_addr: Option<String>,
+    /// Lock duration, either in seconds or in blocks
+    pub lock_duration: Duration,
     /// Reward tokens
     pub reward_tokens: Vec<AssetInfoUnchecked>,
     /// Whether or not deposits are enabled
@@ -42,6 +53,72 @@ pub struct InstantiateMsg {
     pub deposit_fee: Option<FeeConfig<String>>,
     /// A fee that is taken on withdrawals
     pub withdrawal_fee: Option<FeeConfig<String>>,
+    /// The number of seconds that must pass between proposing a config update
+    /// and executing it.
+    pub config_update_delay: u64,
+    /// Bank metadata for the vault token. If set, the metadata is set when the
+    /// vault token is created.
+    pub vault_token_metadata: Option<VaultTokenMetadata>,
+    /// If set, the vault instantiates a cw721-base compatible contract with
+    /// this code id and mints every unlocking position as an NFT in it. The
+    /// holder of the NFT then acts on the position by sending the NFT to the
+    /// vault with a `ReceiveNftMsg`.
+    pub unlocking_position_nft_code_id: Option<u64>,
+}
+
+/// How the vault token that represents shares of the vault is created.
+#[cw_serde]
+pub enum VaultTokenBackend {
+    /// Create a native vault token with the token factory module, using
+    /// `InstantiateMsg::vault_token_subdenom` as the subdenom.
+    TokenFactory {
+        /// The token factory module of the chain. Defaults to
+        /// `TokenFactoryFlavor::Osmosis`.
+        flavor: Option<TokenFactoryFlavor>,
+    },
+    /// Instantiate a cw20-base compatible contract with code id `code_id` as
+    /// the vault token, with the vault as its minter. The name, symbol and
+    /// decimals of the token are taken from
+    /// `InstantiateMsg::vault_token_metadata`. For chains without the token
+    /// factory module.
+    Cw20 { code_id: u64 },
+}
+
+/// The instantiate message of cw20-base compatible contracts.
+#[cw_serde]
+pub struct Cw20InstantiateMsg {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub initial_balances: Vec<Cw20Coin>,
+    pub mint: Option<MinterResponse>,
+}
+
+/// The instantiate message of cw721-base compatible contracts.
+#[cw_serde]
+pub struct Cw721InstantiateMsg {
+    pub name: String,
+    pub symbol: String,
+    pub minter: String,
+}
+
+/// The messages of cw721-base compatible contracts that the vault sends as the
+/// minter of the unlocking position NFTs.
+#[cw_serde]
+pub enum Cw721ExecuteMsg {
+    Mint {
+        token_id: String,
+        owner: String,
+        token_uri: Option<String>,
+        extension: Option<Empty>,
+    },
+    TransferNft {
+        recipient: String,
+        token_id: String,
+    },
+    Burn {
+        token_id: String,
+    },
 }
 
 #[cw_serde]
@@ -63,6 +140,10 @@ pub enum InternalMsg {
         /// discount the amount of tokens that were just deposited so we
         /// don't try to stake them twice.
         discount_tokens: Uint128,
+        /// The amount of base tokens that were in the vault's balance without
+        /// being accounted for before compounding. These are implicit
+        /// donations.
+        untracked_tokens: Uint128,
     },
     /// Deposit into the vault after compounding
     Deposit {
@@ -71,12 +152,31 @@ pub enum InternalMsg {
         /// The recipient of the vault token.
         recipient: Addr,
     },
+    /// Deposit into a lock tier after compounding
+    DepositToLockTier {
+        /// The index of the lock tier in `Config::lock_tiers`.
+        lock_tier: u32,
+        /// The amount of base tokens to deposit.
+        amount: Uint128,
+        /// The recipient of the shares of the lock tier.
+        recipient: Addr,
+    },
     Redeem {
         /// The address which should receive the withdrawn base tokens.
         recipient: Addr,
         /// The amount of vault tokens sent to the contract.
         amount: Uint128,
     },
+    /// Stake the LP tokens of the new pool after migrating the vault's
+    /// liquidity and convert the vault's accounting to the new base token.
+    StakeMigratedLps {
+        /// The amount of old base tokens that were withdrawn from the old
+        /// pool.
+        old_base_token_amount: Uint128,
+        /// The minimum amount of new base tokens that must have been received
+        /// from the new pool.
+        min_base_token_amount: Uint128,
+    },
 }
 
 impl IntoInternalCall for InternalMsg {
@@ -90,13 +190,197 @@ impl IntoInternalCall for InternalMsg {
 #[cw_serde]
 #[allow(clippy::large_enum_variant)]
 pub enum ApolloExtensionExecuteMsg {
-    /// Update the configuration of the vault.
+    /// Update the configuration of the vault. Only updates that can not be
+    /// used to redirect funds, such as disabling deposits, shortening the lock
+    /// duration or lowering fees, can be applied this way. All other updates
+    /// must go through `ProposeConfigUpdate` and `ExecuteConfigUpdate`.
     UpdateConfig {
         // The config updates.
         updates: ConfigUpdates<String>,
+        /// If true, the new lock duration is also applied to existing
+        /// unlocking positions, so that none is released later than its
+        /// creation plus the new lock duration. The positions are updated in
+        /// batches with `ApplyLockDurationReduction`. Requires
+        /// `updates.lock_duration` to be set and no earlier reduction to be
+        /// pending. Positions created afterwards are not affected, and any
+        /// later change of the lock duration cancels the pending reduction.
+        shorten_existing_claims: Option<bool>,
+    },
+    /// Propose a config update that can be executed once the config update
+    /// delay has passed.
+    ProposeConfigUpdate {
+        /// The config updates.
+        updates: ConfigUpdates<String>,
+        /// The staking mode to migrate the vault to, if any. All staked LP
+        /// tokens are unstaked from the current staking mode and staked in the
+        /// new one when the proposal is executed.
+        staking_mode: Option<StakingModeUnchecked>,
+        /// The address of an Astroport pair with the same pool assets to
+        /// migrate all liquidity of the vault to, if any, for example when
+        /// Astroport deploys a new pair version or when changing pair type.
+        /// When the proposal is executed, all LP tokens are withdrawn from the
+        /// current pool and provided to the new pair, and the new LP tokens
+        /// become the vault's base token. Unlocking claims are converted to
+        /// the new base token. Can not be combined with `staking_mode`.
+        pool: Option<String>,
+    },
+    /// Execute a previously proposed config update whose delay has passed.
+    ExecuteConfigUpdate {
+        /// The id of the proposal to execute.
+        proposal_id: u64,
+        /// The minimum amounts to receive when migrating the vault's liquidity
+        /// to a new pool. Required if the proposal migrates the pool.
+        pool_migration_min_out: Option<PoolMigrationMinOut>,
+    },
+    /// Cancel a previously proposed config update.
+    CancelConfigUpdate {
+        /// The id of the proposal to cancel.
+        proposal_id: u64,
     },
+    /// Stop staking the LP tokens and hold them in the vault instead. All
+    /// staked LP tokens are unstaked. Migrating to any other staking mode must
+    /// go through `ProposeConfigUpdate` and `ExecuteConfigUpdate`.
+    UpdateStakingMode {
+        /// The staking mode to migrate the vault to.
+        staking_mode: StakingModeUnchecked,
+    },
+    /// Send tokens that were accidentally sent to the vault to `recipient`.
+    /// The base token, reward tokens, pool assets and the vault token can not
+    /// be rescued. Only callable by the owner.
+    RescueTokens {
+        /// The assets to send.
+        assets: Vec<AssetUnchecked>,
+        /// The address to send the assets to.
+        recipient: String,
+    },
+    /// Donate base tokens to the vault. The base tokens are staked without
+    /// minting vault tokens or lock tier shares, and are distributed to the
+    /// vault token and the lock tiers like compounded base tokens, raising the
+    /// value of all vault tokens and lock tier shares. Cw20 base tokens must be
+    /// donated with `ReceiveMsg::Donate` instead.
+    Donate {},
+    /// Set the bank metadata of the vault token. Fields that are not set are
+    /// derived from the pool assets. Only callable by the owner.
+    SetVaultTokenMetadata {
+        /// The metadata to set.
+        metadata: VaultTokenMetadata,
+    },
+    /// Withdraw the base tokens of up to `limit` matured unlocking positions
+    /// of the caller in a single transfer. Like
+    /// `LockupExecuteMsg::WithdrawUnlocked`, but for all matured positions.
+    WithdrawAllUnlocked {
+        /// The address which should receive the withdrawn base tokens. If not
+        /// set, the caller address will be used instead.
+        recipient: Option<String>,
+        /// The maximum number of positions to withdraw. Defaults to 10.
+        limit: Option<u32>,
+    },
+    /// Withdraw an unlocking position of the caller before it has matured.
+    /// The emergency unlock penalty is taken from its base tokens. Matured
+    /// positions should be withdrawn with `LockupExecuteMsg::WithdrawUnlocked`
+    /// instead, which takes no penalty.
+    EmergencyWithdrawUnlocking {
+        /// The id of the unlocking position to withdraw.
+        lockup_id: u64,
+        /// The address which should receive the withdrawn base tokens. If not
+        /// set, the caller address will be used instead.
+        recipient: Option<String>,
+    },
+    /// Apply the lock duration reduction of the last `UpdateConfig` with
+    /// `shorten_existing_claims` to up to `limit` more unlocking positions.
+    /// Callable by anyone until all positions have been processed. Positions
+    /// created before version 0.6.0 are not shortened.
+    ApplyLockDurationReduction {
+        /// The maximum number of positions to process. Defaults to 10.
+        limit: Option<u32>,
+    },
+    /// Convert up to `limit` more unlocking positions that were created before
+    /// the last pool migration to the new base token. Until a position has
+    /// been converted, it can not be withdrawn, cancelled or split. Callable
+    /// by anyone until all positions have been processed.
+    ApplyClaimsRescale {
+        /// The maximum number of positions to process. Defaults to 10.
+        limit: Option<u32>,
+    },
+    /// Cancel an unlocking position of the caller, or part of it, and mint
+    /// vault tokens for its base tokens at the current exchange rate.
+    /// Positions unlocked from a lock tier are cancelled into shares of that
+    /// tier instead. The withdrawal fee that was taken when the position was
+    /// created is not refunded.
+    CancelUnlock {
+        /// The id of the unlocking position to cancel.
+        lockup_id: u64,
+        /// The amount of base tokens to remove from the position. If not set,
+        /// the whole position is cancelled.
+        amount: Option<Uint128>,
+    },
+    /// Transfer an unlocking position of the caller to `new_owner`, who can
+    /// then withdraw it once it has matured.
+    TransferUnlockingPosition {
+        /// The id of the unlocking position to transfer.
+        lockup_id: u64,
+        /// The address of the new owner of the unlocking position.
+        new_owner: String,
+    },
+    /// Split `amount` base tokens off an unlocking position of the caller into
+    /// a new unlocking position with the same release time. The amounts of
+    /// the two positions sum to the amount of the original position.
+    SplitUnlockingPosition {
+        /// The id of the unlocking position to split.
+        lockup_id: u64,
+        /// The amount of base tokens to move to the new position. Must be less
+        /// than the amount of the position.
+        amount: Uint128,
+        /// The owner of the new position. If not set, the caller address will
+        /// be used instead.
+        new_owner: Option<String>,
+    },
+    /// Deposit base tokens into the lock tier with index `lock_tier` in
+    /// `Config::lock_tiers`. Instead of vault tokens, the recipient receives
+    /// shares of the tier, which can not be transferred and receive a share of
+    /// the compounded rewards according to the tier's weight. The base tokens
+    /// must be sent as funds, or with `ReceiveMsg::DepositToLockTier` if the
+    /// base token is a cw20 token.
+    DepositToLockTier {
+        /// The index of the lock tier in `Config::lock_tiers`.
+        lock_tier: u32,
+        /// The amount of base tokens to deposit.
+        amount: Uint128,
+        /// The address which should receive the shares of the lock tier. If
+        /// not set, the caller address will be used instead.
+        recipient: Option<String>,
+    },
+    /// Burn `amount` shares of the lock tier `lock_tier` owned by the caller
+    /// and create an unlocking position for the released base tokens, which
+    /// matures after the lock duration of the tier. The withdrawal fee is
+    /// taken like when unlocking vault tokens.
+    UnlockFromLockTier {
+        /// The index of the lock tier in `Config::lock_tiers`.
+        lock_tier: u32,
+        /// The amount of shares of the lock tier to unlock.
+        amount: Uint128,
+    },
+    /// Shut the vault down for good, e.g. if the pool or the incentives
+    /// contract is compromised. All LP tokens are unstaked and held by the
+    /// vault, deposits and compounding are disabled and every unlocking
+    /// position is matured. Afterwards, vault tokens and lock tier shares are
+    /// redeemed for their share of the LP tokens without a lock period. Only
+    /// callable by the owner.
+    Shutdown {},
     /// Compounds the vault
     Compound {},
+    /// Sells all balances of at most `Config::dust_threshold` that are not
+    /// the base token, a reward token, a pool asset or the vault token into
+    /// the reward liquidation target, then provides liquidity and stakes the
+    /// LP tokens. Only callable by the owner.
+    SweepDust {},
+    /// Queries the Astroport incentives contract for the reward tokens that
+    /// are distributed to stakers of the base token and proposes a config
+    /// update that appends any new ones that the router can route to the
+    /// reward liquidation target to `Config::reward_tokens`. The proposal
+    /// must be executed with `ExecuteConfigUpdate` after the config update
+    /// delay. Can only be called by the owner.
+    DiscoverRewardTokens {},
 }
 
 impl IntoInternalCall for ApolloExtensionExecuteMsg {
@@ -139,6 +423,7 @@ impl IntoInternalCall for ExtensionExecuteMsg {
 #[cw_ownable::cw_ownable_query]
 #[cw_serde]
 #[derive(QueryResponses)]
+#[allow(clippy::large_enum_variant)]
 pub enum ApolloExtensionQueryMsg {
     /// Returns the current config.
     #[returns(crate::state::Config)]
@@ -158,9 +443,85 @@ pub enum ApolloExtensionQueryMsg {
     /// Returns the non-configurable state of the contract.
     #[returns(crate::state::StateResponse)]
     State {},
+
+    /// Applies the given updates to the current config and validates the
+    /// result without storing it. Returns the resulting config, or all
+    /// validation failures if the resulting config is invalid.
+    #[returns(crate::state::ValidateConfigUpdateResponse)]
+    ValidateConfigUpdate { updates: ConfigUpdates<String> },
+
+    /// Returns the rewards that can currently be claimed from the staking
+    /// contract, as well as the reward token balances already held by the
+    /// vault.
+    #[returns(crate::state::PendingRewardsResponse)]
+    PendingRewards {},
+
+    /// Estimates the performance fees, swap outputs and base tokens minted if
+    /// the vault was compounded in the current block.
+    #[returns(crate::state::SimulateCompoundResponse)]
+    SimulateCompound {},
+
+    /// Returns all balances that the vault holds outside of the LP tokens that
+    /// are staked or held on behalf of depositors. Only cw20 balances of the
+    /// base token, reward tokens and pool assets are included.
+    #[returns(Vec<apollo_cw_asset::Asset>)]
+    IdleBalances {},
+
+    /// Returns the cumulative amounts of base tokens donated to the vault.
+    #[returns(crate::state::Donations)]
+    Donations {},
+
+    /// Returns a summary of the position of `address` in the vault, including
+    /// its vault token balance, the value of it, its lock tier shares and its
+    /// unlocking claims.
+    #[returns(crate::state::UserPositionResponse)]
+    UserPosition { address: String },
+
+    /// Returns all unlocking positions in order of their ids. If unlocking
+    /// positions are NFTs, the owner of each position is the holder of the
+    /// NFT.
+    #[returns(Vec<cw_vault_standard::extensions::lockup::UnlockingPosition>)]
+    AllUnlockingPositions {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    /// Returns the unlocking positions that are released at or after `from`
+    /// and before `to`, in order of their release. `from` and `to` must both
+    /// be heights or both be times. `start_after` is the release time and id
+    /// of the last position of the previous page. If unlocking positions are
+    /// NFTs, the owner of each position is the holder of the NFT.
+    #[returns(Vec<cw_vault_standard::extensions::lockup::UnlockingPosition>)]
+    UnlockingPositionsReleasedBetween {
+        from: Expiration,
+        to: Expiration,
+        start_after: Option<(Expiration, u64)>,
+        limit: Option<u32>,
+    },
+
+    /// Returns the total amount of base tokens in all unlocking positions.
+    #[returns(Uint128)]
+    TotalUnlocking {},
+
+    /// Returns all lock tiers together with the state of their share classes.
+    #[returns(Vec<crate::state::LockTierResponse>)]
+    LockTiers {},
+
+    /// Returns the shares of every lock tier that `address` owns.
+    #[returns(Vec<crate::state::LockTierPositionResponse>)]
+    LockTierPositions { address: String },
+
+    /// Returns the config update proposals that have not yet been executed or
+    /// cancelled.
+    #[returns(Vec<crate::state::ConfigUpdateProposal>)]
+    ConfigUpdateProposals {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
 }
 
 #[cw_serde]
+#[allow(clippy::large_enum_variant)]
 pub enum ExtensionQueryMsg {
     /// Execute an Apollo extension query.
     Apollo(ApolloExtensionQueryMsg),
@@ -169,9 +530,192 @@ pub enum ExtensionQueryMsg {
     Lockup(LockupQueryMsg),
 }
 
-pub type ExecuteMsg = cw_vault_standard::VaultStandardExecuteMsg<ExtensionExecuteMsg>;
+/// The execute message of the vault. This mirrors
+/// `cw_vault_standard::VaultStandardExecuteMsg` with an added `Receive`
+/// variant, so that cw20 vault tokens can be sent to the vault. All messages of
+/// the vault standard deserialize into the variants of the same name.
+#[cw_serde]
+#[allow(clippy::large_enum_variant)]
+pub enum ExecuteMsg {
+    /// Called to deposit into the vault. See
+    /// `VaultStandardExecuteMsg::Deposit`.
+    Deposit {
+        /// The amount of base tokens to deposit.
+        amount: Uint128,
+        /// The optional recipient of the vault token. If not set, the caller
+        /// address will be used instead.
+        recipient: Option<String>,
+    },
+    /// Called to redeem vault tokens. The vault tokens must be sent as funds.
+    /// See `VaultStandardExecuteMsg::Redeem`.
+    Redeem {
+        /// An optional field containing which address should receive the
+        /// withdrawn base tokens. If not set, the caller address will be used
+        /// instead.
+        recipient: Option<String>,
+        /// The amount of vault tokens sent to the contract.
+        amount: Uint128,
+    },
+    /// Called to execute functionality of any enabled extensions.
+    VaultExtension(ExtensionExecuteMsg),
+    /// Called by a cw20 token contract when tokens are sent to the vault with
+    /// `Cw20ExecuteMsg::Send`. Accepts the cw20 vault token and, if the base
+    /// token is a cw20 token, the base token. The message must be a
+    /// `ReceiveMsg`.
+    Receive(Cw20ReceiveMsg),
+    /// Called by the unlocking position NFT contract when an NFT is sent to
+    /// the vault with `SendNft`. The message must be a `ReceiveNftMsg`.
+    ReceiveNft(Cw721ReceiveMsg),
+}
+
+/// The messages that can be sent along with cw20 tokens in
+/// `Cw20ExecuteMsg::Send`. The sender of the cw20 tokens is treated as the
+/// caller. `Deposit` and `DepositToLockTier` must be sent with the cw20 base
+/// token, all other messages with the cw20 vault token.
+#[cw_serde]
+pub enum ReceiveMsg {
+    /// Deposit the sent base tokens. See `ExecuteMsg::Deposit`.
+    Deposit {
+        /// The address which should receive the vault tokens. If not set, the
+        /// sender of the base tokens will be used instead.
+        recipient: Option<String>,
+    },
+    /// Deposit the sent base tokens into a lock tier. See
+    /// `ApolloExtensionExecuteMsg::DepositToLockTier`.
+    DepositToLockTier {
+        /// The index of the lock tier in `Config::lock_tiers`.
+        lock_tier: u32,
+        /// The address which should receive the shares of the lock tier. If
+        /// not set, the sender of the base tokens will be used instead.
+        recipient: Option<String>,
+    },
+    /// Donate the sent base tokens to the vault. See
+    /// `ApolloExtensionExecuteMsg::Donate`.
+    Donate {},
+    /// Redeem the vault tokens. See `ExecuteMsg::Redeem`.
+    Redeem {
+        /// The address which should receive the withdrawn base tokens. If not
+        /// set, the sender of the vault tokens will be used instead.
+        recipient: Option<String>,
+    },
+    /// Start unlocking the vault tokens. See `LockupExecuteMsg::Unlock`.
+    Unlock {},
+    /// Withdraw the base tokens of the vault tokens immediately, minus the
+    /// emergency unlock penalty. See `LockupExecuteMsg::EmergencyUnlock`.
+    EmergencyUnlock {},
+    /// Force redeem the vault tokens. The sender of the vault tokens must be
+    /// whitelisted. See `ForceUnlockExecuteMsg::ForceRedeem`.
+    ForceRedeem {
+        /// The address which should receive the withdrawn base tokens. If not
+        /// set, the sender of the vault tokens will be used instead.
+        recipient: Option<String>,
+    },
+}
+
+/// The messages that can be sent along with unlocking position NFTs in
+/// `SendNft`. If unlocking positions are NFTs, this is the only way to act on
+/// them. The sender of the NFT is treated as the caller and the owner of the
+/// position. The vault burns the NFT if the position is removed, and otherwise
+/// sends it back to the sender.
+#[cw_serde]
+pub enum ReceiveNftMsg {
+    /// Withdraw the matured position. See
+    /// `LockupExecuteMsg::WithdrawUnlocked`.
+    WithdrawUnlocked {
+        /// The address which should receive the withdrawn base tokens. If not
+        /// set, the sender of the NFT will be used instead.
+        recipient: Option<String>,
+    },
+    /// Cancel the position, or part of it. See
+    /// `ApolloExtensionExecuteMsg::CancelUnlock`.
+    CancelUnlock {
+        /// The amount of base tokens to remove from the position. If not set,
+        /// the whole position is cancelled.
+        amount: Option<Uint128>,
+    },
+    /// Withdraw the position before it has matured. See
+    /// `ApolloExtensionExecuteMsg::EmergencyWithdrawUnlocking`.
+    EmergencyWithdrawUnlocking {
+        /// The address which should receive the withdrawn base tokens. If not
+        /// set, the sender of the NFT will be used instead.
+        recipient: Option<String>,
+    },
+    /// Force withdraw the position, or part of it. The sender of the NFT must
+    /// be whitelisted. See `ForceUnlockExecuteMsg::ForceWithdrawUnlocking`.
+    ForceWithdrawUnlocking {
+        /// The amount of base tokens to withdraw. If not set, the whole
+        /// position is withdrawn.
+        amount: Option<Uint128>,
+        /// The address which should receive the withdrawn base tokens. If not
+        /// set, the sender of the NFT will be used instead.
+        recipient: Option<String>,
+    },
+}
+
+impl ExecuteMsg {
+    /// Returns true if the message can still be executed after the vault has
+    /// been shut down. Only messages that let depositors exit the vault, or
+    /// that can not move funds into it, are allowed. Emergency unlocks are not
+    /// allowed, since there is no lock period to skip.
+    pub fn is_allowed_after_shutdown(&self) -> bool {
+        match self {
+            ExecuteMsg::Deposit { .. } => false,
+            ExecuteMsg::Redeem { .. } => true,
+            ExecuteMsg::Receive(cw20_msg) => !matches!(
+                from_json::<ReceiveMsg>(&cw20_msg.msg),
+                Ok(ReceiveMsg::Deposit { .. }
+                    | ReceiveMsg::DepositToLockTier { .. }
+                    | ReceiveMsg::Donate {}
+                    | ReceiveMsg::EmergencyUnlock {})
+            ),
+            ExecuteMsg::ReceiveNft(nft_msg) => !matches!(
+                from_json::<ReceiveNftMsg>(&nft_msg.msg),
+                Ok(ReceiveNftMsg::CancelUnlock { .. }
+                    | ReceiveNftMsg::EmergencyWithdrawUnlocking { .. })
+            ),
+            ExecuteMsg::VaultExtension(msg) => match msg {
+                ExtensionExecuteMsg::Internal(msg) => matches!(msg, InternalMsg::Redeem { .. }),
+                ExtensionExecuteMsg::Lockup(msg) => {
+                    !matches!(msg, LockupExecuteMsg::EmergencyUnlock { .. })
+                }
+                ExtensionExecuteMsg::ForceUnlock(_) => true,
+                ExtensionExecuteMsg::UpdateOwnership(_) => true,
+                ExtensionExecuteMsg::Apollo(msg) => matches!(
+                    msg,
+                    ApolloExtensionExecuteMsg::UpdateConfig { .. }
+                        | ApolloExtensionExecuteMsg::RescueTokens { .. }
+                        | ApolloExtensionExecuteMsg::SetVaultTokenMetadata { .. }
+                        | ApolloExtensionExecuteMsg::WithdrawAllUnlocked { .. }
+                        | ApolloExtensionExecuteMsg::ApplyLockDurationReduction { .. }
+                        | ApolloExtensionExecuteMsg::ApplyClaimsRescale { .. }
+                        | ApolloExtensionExecuteMsg::TransferUnlockingPosition { .. }
+                        | ApolloExtensionExecuteMsg::SplitUnlockingPosition { .. }
+                        | ApolloExtensionExecuteMsg::UnlockFromLockTier { .. }
+                ),
+            },
+        }
+    }
+}
+
+/// The minimum amounts to receive when migrating the vault's liquidity to a
+/// new pool, to protect the migration against price manipulation.
+#[cw_serde]
+pub struct PoolMigrationMinOut {
+    /// The minimum amounts of the pool assets to receive when withdrawing
+    /// liquidity from the current pool.
+    pub withdrawn_assets: Vec<AssetUnchecked>,
+    /// The minimum amount of LP tokens to receive when providing liquidity to
+    /// the new pool.
+    pub base_tokens: Uint128,
+}
 
 pub type QueryMsg = cw_vault_standard::VaultStandardQueryMsg<ExtensionQueryMsg>;
 
 #[cw_serde]
-pub struct MigrateMsg {}
+#[derive(Default)]
+pub struct MigrateMsg {
+    /// The number of seconds that must pass between proposing a config update
+    /// and executing it. Required when migrating from versions before 0.6.0,
+    /// since their config has no delay, and ignored otherwise.
+    pub config_update_delay: Option<u64>,
+}
@@ -1,19 +1,33 @@
-use cosmwasm_std::{Addr, Decimal, Deps, Order, StdError, StdResult};
+use apollo_cw_asset::{Asset, AssetList};
+use cosmwasm_std::{Addr, Decimal, Deps, Env, Order, StdError, StdResult, Uint128};
+use cw721::{Cw721QueryMsg, OwnerOfResponse, TokensResponse};
+use cw_dex_astroport::AstroportPool;
 use cw_storage_plus::Bound;
+use cw_utils::Expiration;
 use cw_vault_standard::extensions::lockup::UnlockingPosition;
 use cw_vault_standard::{VaultInfoResponse, VaultStandardInfoResponse};
+use optional_struct::Applyable;
 use strum::VariantNames;
 
-use crate::helpers::INITIAL_VAULT_TOKENS_PER_BASE_TOKEN;
+use crate::claims::Claim;
+use crate::helpers::{self, INITIAL_VAULT_TOKENS_PER_BASE_TOKEN};
 use crate::msg::ExtensionExecuteMsg;
 use crate::state::{
-    self, StateResponse, BASE_TOKEN, FORCE_WITHDRAW_WHITELIST, POOL, STAKING, STATE,
-    VAULT_TOKEN_DENOM,
+    self, ConfigUnchecked, ConfigUpdateProposal, ConfigUpdates, ConfigValidationError, Donations,
+    LockTierPositionResponse, LockTierResponse, PendingRewardsResponse, SimulateCompoundResponse,
+    StateResponse, UserPositionResponse, ValidateConfigUpdateResponse, BASE_TOKEN, CONFIG,
+    CONFIG_UPDATE_PROPOSALS, DONATIONS, FORCE_WITHDRAW_WHITELIST, LOCK_DURATION_REDUCTION,
+    LOCK_TIER_SHARES, LOCK_TIER_STATES, POOL, SHUTDOWN, STAKING, STATE, UNLOCKING_POSITION_NFT,
+    VAULT_TOKEN,
 };
 
 /// The default limit for pagination
 pub const DEFAULT_LIMIT: u32 = 10;
 
+/// The maximum number of claims that are summarized by the `UserPosition`
+/// query
+pub const USER_POSITION_CLAIMS_LIMIT: u32 = 100;
+
 pub fn query_vault_standard_info(_deps: Deps) -> StdResult<VaultStandardInfoResponse> {
     Ok(VaultStandardInfoResponse {
         version: "0.4.1-rc.1".to_string(),
@@ -26,11 +40,11 @@ pub fn query_vault_standard_info(_deps: Deps) -> StdResult<VaultStandardInfoResp
 
 pub fn query_vault_info(deps: Deps) -> StdResult<VaultInfoResponse> {
     let base_token = BASE_TOKEN.load(deps.storage)?;
-    let vault_token_denom = VAULT_TOKEN_DENOM.load(deps.storage)?;
+    let vault_token = VAULT_TOKEN.load(deps.storage)?;
 
     Ok(VaultInfoResponse {
         base_token: base_token.to_string(),
-        vault_token: vault_token_denom,
+        vault_token: vault_token.to_string(),
     })
 }
 
@@ -40,18 +54,105 @@ pub fn query_unlocking_positions(
     start_after: Option<u64>,
     limit: Option<u32>,
 ) -> StdResult<Vec<UnlockingPosition>> {
-    let claims = state::claims().query_claims_for_owner(
-        deps,
-        &deps.api.addr_validate(&owner)?,
-        start_after,
-        limit,
-    )?;
+    let claims =
+        query_claims_for_owner(deps, &deps.api.addr_validate(&owner)?, start_after, limit)?;
 
     Ok(claims.into_iter().map(|(_id, claim)| claim).collect())
 }
 
+/// Returns the unlocking positions of `owner`. If unlocking positions are NFTs,
+/// these are the positions whose NFTs are held by `owner`, paginated in the
+/// order of the NFT contract, which sorts token ids as strings. The owner
+/// stored in `Claims` is then replaced by the holder of the NFT.
+fn query_claims_for_owner(
+    deps: Deps,
+    owner: &Addr,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(u64, Claim)>> {
+    let nft = match UNLOCKING_POSITION_NFT.may_load(deps.storage)? {
+        Some(nft) => nft,
+        None => {
+            return state::claims().query_claims_for_owner(deps, owner, start_after, limit);
+        }
+    };
+
+    let tokens: TokensResponse = deps.querier.query_wasm_smart(
+        nft.to_string(),
+        &Cw721QueryMsg::Tokens {
+            owner: owner.to_string(),
+            start_after: start_after.map(|id| id.to_string()),
+            limit,
+        },
+    )?;
+    tokens
+        .tokens
+        .iter()
+        .map(|token_id| {
+            let id = token_id
+                .parse::<u64>()
+                .map_err(|e| StdError::generic_err(e.to_string()))?;
+            let claim = state::claims().query_claim_by_id(deps, id)?;
+            Ok((
+                id,
+                Claim {
+                    owner: owner.clone(),
+                    ..claim
+                },
+            ))
+        })
+        .collect()
+}
+
 pub fn query_unlocking_position(deps: Deps, id: u64) -> StdResult<UnlockingPosition> {
     let claim = state::claims().query_claim_by_id(deps, id)?;
+
+    with_nft_holder(deps, claim)
+}
+
+pub fn query_all_unlocking_positions(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<UnlockingPosition>> {
+    let claims = state::claims().query_all_claims(deps, start_after, limit)?;
+
+    claims
+        .into_iter()
+        .map(|(_id, claim)| with_nft_holder(deps, claim))
+        .collect()
+}
+
+pub fn query_unlocking_positions_released_between(
+    deps: Deps,
+    from: Expiration,
+    to: Expiration,
+    start_after: Option<(Expiration, u64)>,
+    limit: Option<u32>,
+) -> StdResult<Vec<UnlockingPosition>> {
+    let claims = state::claims().query_claims_by_release(deps, from, to, start_after, limit)?;
+
+    claims
+        .into_iter()
+        .map(|(_id, claim)| with_nft_holder(deps, claim))
+        .collect()
+}
+
+/// Returns `claim` with its owner replaced by the holder of its NFT, if
+/// unlocking positions are NFTs. The owner stored in `Claims` is only updated
+/// when the NFT is sent to the vault.
+fn with_nft_holder(deps: Deps, mut claim: Claim) -> StdResult<Claim> {
+    if let Some(nft) = UNLOCKING_POSITION_NFT.may_load(deps.storage)? {
+        let nft_owner: OwnerOfResponse = deps.querier.query_wasm_smart(
+            nft.to_string(),
+            &Cw721QueryMsg::OwnerOf {
+                token_id: claim.id.to_string(),
+                include_expired: None,
+            },
+        )?;
+        claim.owner = deps.api.addr_validate(&nft_owner.owner)?;
+    }
+
     Ok(claim)
 }
 
@@ -74,18 +175,353 @@ pub fn query_force_withdraw_whitelist(
     Ok(whitelist)
 }
 
+pub fn query_validate_config_update(
+    deps: Deps,
+    updates: ConfigUpdates<String>,
+) -> StdResult<ValidateConfigUpdateResponse> {
+    let mut config: ConfigUnchecked = CONFIG.load(deps.storage)?.into();
+
+    updates.apply_to(&mut config);
+
+    Ok(match config.check_all(deps) {
+        Ok(config) => ValidateConfigUpdateResponse {
+            config: Some(config),
+            errors: vec![],
+        },
+        Err(errors) => ValidateConfigUpdateResponse {
+            config: None,
+            errors: errors
+                .into_iter()
+                .map(|(field, error)| ConfigValidationError {
+                    field,
+                    error: error.to_string(),
+                })
+                .collect(),
+        },
+    })
+}
+
+pub fn query_config_update_proposals(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<ConfigUpdateProposal>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT) as usize;
+    let min = start_after.map(Bound::exclusive);
+
+    CONFIG_UPDATE_PROPOSALS
+        .range(deps.storage, min, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, proposal)| proposal))
+        .collect()
+}
+
+pub fn query_user_position(
+    deps: Deps,
+    env: Env,
+    address: String,
+) -> StdResult<UserPositionResponse> {
+    let address = deps.api.addr_validate(&address)?;
+
+    let vault_token_balance = VAULT_TOKEN
+        .load(deps.storage)?
+        .to_asset_info()
+        .query_balance(&deps.querier, &address)?;
+    let base_token_value = helpers::convert_to_assets(deps, None, vault_token_balance);
+    let pool_assets_value = query_pool_assets_value(deps, base_token_value)?;
+
+    let lock_tier_positions = query_lock_tier_positions(deps, address.to_string())?;
+
+    // Only the first claims are read, so that the query can not run out of gas
+    let mut claims =
+        query_claims_for_owner(deps, &address, None, Some(USER_POSITION_CLAIMS_LIMIT + 1))?;
+    let claims_truncated = claims.len() > USER_POSITION_CLAIMS_LIMIT as usize;
+    claims.truncate(USER_POSITION_CLAIMS_LIMIT as usize);
+
+    let mut res = UserPositionResponse {
+        vault_token_balance,
+        base_token_value,
+        pool_assets_value,
+        lock_tier_positions,
+        claims_truncated,
+        unlocking_claims: 0,
+        unlocking_base_tokens: Uint128::zero(),
+        matured_claims: 0,
+        matured_base_tokens: Uint128::zero(),
+        next_release_at: None,
+        lock_tier_claims_base_tokens: vec![],
+    };
+    // All claims are matured once the vault has been shut down
+    let shut_down = state::is_shut_down(deps.storage)?;
+    for (id, claim) in claims {
+        if let Some(lock_tier) = state::claims().query_lock_tier(deps.storage, id)? {
+            match res
+                .lock_tier_claims_base_tokens
+                .iter_mut()
+                .find(|(tier, _)| *tier == lock_tier)
+            {
+                Some((_, amount)) => *amount += claim.base_token_amount,
+                None => res
+                    .lock_tier_claims_base_tokens
+                    .push((lock_tier, claim.base_token_amount)),
+            }
+        }
+        if shut_down || claim.release_at.is_expired(&env.block) {
+            res.matured_claims += 1;
+            res.matured_base_tokens += claim.base_token_amount;
+        } else {
+            res.unlocking_claims += 1;
+            res.unlocking_base_tokens += claim.base_token_amount;
+            if res
+                .next_release_at
+                .is_none_or(|next| claim.release_at < next)
+            {
+                res.next_release_at = Some(claim.release_at);
+            }
+        }
+    }
+
+    Ok(res)
+}
+
+pub fn query_lock_tiers(deps: Deps) -> StdResult<Vec<LockTierResponse>> {
+    let cfg = CONFIG.load(deps.storage)?;
+    cfg.lock_tiers
+        .into_iter()
+        .enumerate()
+        .map(|(lock_tier, tier)| {
+            let lock_tier = lock_tier as u32;
+            let state = LOCK_TIER_STATES
+                .may_load(deps.storage, lock_tier)?
+                .unwrap_or_default();
+            Ok(LockTierResponse {
+                lock_tier,
+                lock_duration: tier.lock_duration,
+                weight: tier.weight,
+                staked_base_tokens: state.staked_base_tokens,
+                total_shares: state.vault_token_supply,
+            })
+        })
+        .collect()
+}
+
+pub fn query_lock_tier_positions(
+    deps: Deps,
+    address: String,
+) -> StdResult<Vec<LockTierPositionResponse>> {
+    let address = deps.api.addr_validate(&address)?;
+    let lock_tiers = CONFIG.load(deps.storage)?.lock_tiers.len() as u32;
+
+    let mut positions = vec![];
+    for lock_tier in 0..lock_tiers {
+        let shares = LOCK_TIER_SHARES
+            .may_load(deps.storage, (lock_tier, &address))?
+            .unwrap_or_default();
+        if shares.is_zero() {
+            continue;
+        }
+        positions.push(LockTierPositionResponse {
+            lock_tier,
+            shares,
+            base_token_value: helpers::convert_to_assets(deps, Some(lock_tier), shares),
+        });
+    }
+    Ok(positions)
+}
+
+/// Returns the amounts of the pool assets that `base_token_amount` LP tokens
+/// are worth at the current pool reserves.
+fn query_pool_assets_value(deps: Deps, base_token_amount: Uint128) -> StdResult<Vec<Asset>> {
+    let pool = POOL.load(deps.storage)?;
+    let pool_res: astroport_v5::pair::PoolResponse = deps.querier.query_wasm_smart(
+        pool.pair_addr.to_string(),
+        &astroport_v5::pair::QueryMsg::Pool {},
+    )?;
+    if pool_res.total_share.is_zero() {
+        return Ok(vec![]);
+    }
+
+    Ok(pool
+        .pool_assets
+        .iter()
+        .map(|asset_info| {
+            let reserve = pool_res
+                .assets
+                .iter()
+                .find(|reserve| reserve.info.to_string() == asset_info.to_string())
+                .map(|reserve| reserve.amount)
+                .unwrap_or_default();
+            Asset::new(
+                asset_info.clone(),
+                reserve.multiply_ratio(base_token_amount, pool_res.total_share),
+            )
+        })
+        .collect())
+}
+
 pub fn query_state(deps: Deps) -> StdResult<StateResponse> {
     let state = STATE.load(deps.storage)?;
     Ok(StateResponse {
         base_token: BASE_TOKEN.load(deps.storage)?,
-        vault_token_denom: VAULT_TOKEN_DENOM.load(deps.storage)?,
+        vault_token: VAULT_TOKEN.load(deps.storage)?,
         pool: POOL.load(deps.storage)?,
         staked_base_tokens: state.staked_base_tokens,
         vault_token_supply: state.vault_token_supply,
         staking: STAKING.load(deps.storage)?,
+        unlocking_position_nft: UNLOCKING_POSITION_NFT.may_load(deps.storage)?,
+        lock_duration_reduction: LOCK_DURATION_REDUCTION.may_load(deps.storage)?,
+        claims_rescale: state::claims().query_rescale(deps.storage)?,
+        shutdown: SHUTDOWN.may_load(deps.storage)?,
+    })
+}
+
+/// Returns the rewards that can be claimed from the staking contract. Astroport
+/// throws an error on query pending rewards if we have never staked before, so
+/// in that case we return no rewards.
+fn query_claimable_rewards(deps: Deps, env: &Env) -> StdResult<AssetList> {
+    let staking = STAKING.load(deps.storage)?;
+    Ok(staking
+        .query_pending_rewards(&deps.querier, &env.contract.address)
+        .unwrap_or_else(|_| AssetList::new()))
+}
+
+pub fn query_pending_rewards(deps: Deps, env: Env) -> StdResult<PendingRewardsResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+
+    let claimable = query_claimable_rewards(deps, &env)?;
+    let in_contract = AssetList::query_asset_info_balances(
+        cfg.reward_tokens,
+        &deps.querier,
+        &env.contract.address,
+    )?;
+
+    Ok(PendingRewardsResponse {
+        claimable: claimable.to_vec(),
+        in_contract: in_contract.to_vec(),
+    })
+}
+
+pub fn query_simulate_compound(deps: Deps, env: Env) -> StdResult<SimulateCompoundResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let pool = POOL.load(deps.storage)?;
+
+    // Query the balances of all reward tokens and pool assets after claiming
+    // rewards
+    let mut asset_infos = cfg.reward_tokens.clone();
+    for asset_info in &pool.pool_assets {
+        if !asset_infos.contains(asset_info) {
+            asset_infos.push(asset_info.clone());
+        }
+    }
+    let mut balances =
+        AssetList::query_asset_info_balances(asset_infos, &deps.querier, &env.contract.address)?;
+    balances.add_many(&query_claimable_rewards(deps, &env)?)?;
+
+    // Calculate performance fees and tokens to sell in the same way as
+    // `execute_sell_tokens`
+    let mut rewards = AssetList::new();
+    let mut performance_fees = AssetList::new();
+    let mut tokens_to_sell = AssetList::new();
+    for asset_info in &cfg.reward_tokens {
+        let balance = balances
+            .find(asset_info)
+            .map(|asset| asset.amount)
+            .unwrap_or_default();
+        let fee_amount = balance * cfg.performance_fee.fee_rate;
+        let amount_to_sell = balance - fee_amount;
+
+        if balance > Uint128::zero() {
+            rewards.add(&Asset::new(asset_info.clone(), balance))?;
+        }
+        if fee_amount > Uint128::zero() {
+            performance_fees.add(&Asset::new(asset_info.clone(), fee_amount))?;
+        }
+        if amount_to_sell > Uint128::zero() && asset_info != &cfg.reward_liquidation_target {
+            tokens_to_sell.add(&Asset::new(asset_info.clone(), amount_to_sell))?;
+        }
+    }
+    balances.deduct_many(&performance_fees)?;
+    balances.deduct_many(&tokens_to_sell)?;
+
+    // Simulate selling the reward tokens for the reward liquidation target
+    let liquidation_target_received = if tokens_to_sell.len() > 0 {
+        cfg.router.simulate_basket_liquidate(
+            &deps.querier,
+            tokens_to_sell.clone(),
+            &cfg.reward_liquidation_target,
+        )?
+    } else {
+        Uint128::zero()
+    };
+    balances.add(&Asset::new(
+        cfg.reward_liquidation_target.clone(),
+        liquidation_target_received,
+    ))?;
+
+    // Simulate providing liquidity with all pool asset balances
+    let provide_liquidity_assets: AssetList = balances
+        .iter()
+        .filter(|asset| pool.pool_assets.contains(&asset.info) && !asset.amount.is_zero())
+        .cloned()
+        .collect::<Vec<_>>()
+        .into();
+    let base_tokens_minted = estimate_base_tokens_minted(deps, &pool, &provide_liquidity_assets)?;
+
+    // Untracked base tokens are only staked if implicit donations are accepted
+    let untracked_base_tokens = if cfg.reject_implicit_donations {
+        Uint128::zero()
+    } else {
+        helpers::query_untracked_base_tokens(deps, &env, Uint128::zero())?
+    };
+
+    Ok(SimulateCompoundResponse {
+        rewards: rewards.to_vec(),
+        performance_fees: performance_fees.to_vec(),
+        tokens_to_sell: tokens_to_sell.to_vec(),
+        liquidation_target_received,
+        provide_liquidity_assets: provide_liquidity_assets.to_vec(),
+        base_tokens_minted,
+        base_tokens_to_stake: base_tokens_minted + untracked_base_tokens,
     })
 }
 
+/// Estimates the amount of base tokens minted when providing `assets` as
+/// liquidity through the liquidity helper. Each asset is valued at its share of
+/// the pool reserves, ignoring price impact and fees of the swaps the liquidity
+/// helper makes to balance the assets.
+fn estimate_base_tokens_minted(
+    deps: Deps,
+    pool: &AstroportPool,
+    assets: &AssetList,
+) -> StdResult<Uint128> {
+    if assets.len() == 0 {
+        return Ok(Uint128::zero());
+    }
+
+    let pool_res: astroport_v5::pair::PoolResponse = deps.querier.query_wasm_smart(
+        pool.pair_addr.to_string(),
+        &astroport_v5::pair::QueryMsg::Pool {},
+    )?;
+    let num_assets = Uint128::from(pool_res.assets.len() as u128);
+
+    let mut minted = Uint128::zero();
+    for asset in assets.iter() {
+        let reserve = pool_res
+            .assets
+            .iter()
+            .find(|reserve| reserve.info.to_string() == asset.info.to_string())
+            .map(|reserve| reserve.amount)
+            .unwrap_or_default();
+        if reserve.is_zero() {
+            continue;
+        }
+        minted += pool_res
+            .total_share
+            .multiply_ratio(asset.amount, reserve.checked_mul(num_assets)?);
+    }
+    Ok(minted)
+}
+
 pub fn vault_token_exchange_rate(deps: Deps, quote_denom: String) -> StdResult<Decimal> {
     let state = STATE.load(deps.storage)?;
     let base_token = BASE_TOKEN.load(deps.storage)?;
@@ -106,3 +542,11 @@ pub fn vault_token_exchange_rate(deps: Deps, quote_denom: String) -> StdResult<D
         Err(StdError::generic_err("Locked Astroport Vault only supports vault token exchange rate quoted in the base token"))
     }
 }
+
+pub fn query_idle_balances(deps: Deps, env: Env) -> StdResult<Vec<Asset>> {
+    helpers::query_idle_balances(deps, &env)
+}
+
+pub fn query_donations(deps: Deps) -> StdResult<Donations> {
+    Ok(DONATIONS.may_load(deps.storage)?.unwrap_or_default())
+}
@@ -1,17 +1,30 @@
-use crate::claims::Claims;
+use crate::claims::{Claims, ClaimsRescale};
+use crate::helpers::INITIAL_VAULT_TOKENS_PER_BASE_TOKEN;
 use apollo_cw_asset::{Asset, AssetInfo, AssetInfoBase, AssetList};
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, CosmosMsg, Decimal, Deps, Env, StdError, StdResult, Uint128};
+use cosmwasm_std::{
+    coin, coins, to_json_binary, Addr, Api, BankMsg, Binary, BlockInfo, CosmosMsg, Decimal, Deps,
+    Env, Order, QuerierWrapper, Response, StdError, StdResult, Storage, Timestamp, Uint128,
+    WasmMsg,
+};
+use cw20::Cw20ExecuteMsg;
 use cw_address_like::AddressLike;
+use cw_dex_astroport::cw_dex::traits::{Rewards, Stake, Unstake};
+use cw_dex_astroport::cw_dex::CwDexError;
 use cw_dex_astroport::{AstroportPool, AstroportStaking};
-use cw_dex_router::helpers::CwDexRouterBase;
+use cw_dex_router::helpers::{CwDexRouter, CwDexRouterBase};
 use cw_item_set::Set;
-use cw_storage_plus::Item;
-use cw_utils::Duration;
+use cw_storage_plus::{Item, Map};
+use cw_utils::{Duration, Expiration};
 use liquidity_helper::LiquidityHelperBase;
 use optional_struct::{optional_struct, Applyable};
+use osmosis_std::types::cosmos::bank::v1beta1::{DenomUnit, Metadata};
+use osmosis_std::types::osmosis::tokenfactory::v1beta1::{
+    MsgBurn, MsgCreateDenom, MsgMint, MsgSetDenomMetadata,
+};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::fmt::Display;
 
 /// Stores the configurable values for the contract.
 pub const CONFIG: Item<Config> = Item::new("config");
@@ -19,29 +32,77 @@ pub const CONFIG: Item<Config> = Item::new("config");
 /// Stores the Astroport pool that the vault compounds.
 pub const POOL: Item<AstroportPool> = Item::new("pool");
 
-/// Stores the Astroport staking contract that the vault uses to stake the LP
-/// tokens.
-pub const STAKING: Item<AstroportStaking> = Item::new("staking");
+/// Stores how the vault stakes its LP tokens.
+pub const STAKING: Item<StakingMode> = Item::new("staking_mode");
+
+/// The Astroport staking config that was stored by versions of the contract
+/// before `StakingMode` was introduced. Only read when migrating.
+pub const LEGACY_STAKING: Item<AstroportStaking> = Item::new("staking");
 
 /// The base token that is accepted for deposits and that the vault accrues more
 /// of over time. In this case it is an Astroport LP token.
 pub const BASE_TOKEN: Item<AssetInfo> = Item::new("base_token");
 
-/// The denom of the native vault token that represents shares of the vault.
-pub const VAULT_TOKEN_DENOM: Item<String> = Item::new("vault_token_denom");
+/// Stores the token that represents shares of the vault.
+pub const VAULT_TOKEN: Item<VaultToken> = Item::new("vault_token");
+
+/// Stores the address of the cw721 contract in which unlocking positions are
+/// minted as NFTs. Only set if unlocking positions are NFTs.
+pub const UNLOCKING_POSITION_NFT: Item<Addr> = Item::new("unlocking_position_nft");
+
+/// The denom of the native vault token that was stored by versions of the
+/// contract before `VaultToken` was introduced. Only read when migrating.
+pub const LEGACY_VAULT_TOKEN_DENOM: Item<String> = Item::new("vault_token_denom");
+
+/// Stores the cumulative amounts of base tokens donated to the vault.
+pub const DONATIONS: Item<Donations> = Item::new("donations");
 
 /// Stores the state of the vault.
 pub const STATE: Item<VaultState> = Item::new("state");
 
+/// Stores the share class of each lock tier, keyed by the index of the tier in
+/// `Config::lock_tiers`. The `vault_token_supply` of a lock tier is the total
+/// amount of shares of the tier, which are not represented by a token.
+pub const LOCK_TIER_STATES: Map<u32, VaultState> = Map::new("lock_tier_states");
+
+/// Stores the amount of shares of each lock tier that an address owns.
+pub const LOCK_TIER_SHARES: Map<(u32, &Addr), Uint128> = Map::new("lock_tier_shares");
+
 /// Stores a set of addresses that are allowed to force withdraw. This is used
 /// in the case of liquidations when the vault tokens are used as collateral in
 /// lending protocols such as Mars.
 pub const FORCE_WITHDRAW_WHITELIST: Set<&Addr> = Set::new("whitelist");
 
+/// Stores config updates that have been proposed by the owner and are waiting
+/// for the config update delay to pass before they can be executed.
+pub const CONFIG_UPDATE_PROPOSALS: Map<u64, ConfigUpdateProposal> =
+    Map::new("config_update_proposals");
+
+/// The id that will be assigned to the next config update proposal.
+pub const NEXT_CONFIG_UPDATE_PROPOSAL_ID: Item<u64> = Item::new("next_config_update_proposal_id");
+
+/// Stores a lock duration reduction that is still being applied to existing
+/// claims. Removed once all claims have been processed.
+pub const LOCK_DURATION_REDUCTION: Item<LockDurationReduction> =
+    Item::new("lock_duration_reduction");
+
+/// Stores when the vault was shut down. Only set once the vault has been shut
+/// down with `Shutdown`.
+pub const SHUTDOWN: Item<Shutdown> = Item::new("shutdown");
+
 /// Stores unlocking positions that are created upon redeeming vault tokens if
 /// the vault has a lockup duration.
 pub fn claims() -> Claims<'static> {
-    Claims::new("claims", "claims_index", "num_claims")
+    Claims::new(
+        "claims",
+        "claims_index",
+        "claims_release_at_index",
+        "claims_created_at",
+        "claims_lock_tier",
+        "num_claims",
+        "claims_total",
+        "claims_rescale",
+    )
 }
 
 #[cw_serde]
@@ -179,8 +240,62 @@ impl From<FeeConfig<Addr>> for FeeConfig<String> {
     }
 }
 
+#[cw_serde]
+#[derive(Default)]
+/// The penalty that is taken when base tokens leave the vault before the end of
+/// the lock duration with `EmergencyUnlock` or `EmergencyWithdrawUnlocking`.
+pub enum EmergencyUnlockPenalty<T: AddressLike> {
+    /// Emergency unlocks are disabled.
+    #[default]
+    Disabled,
+    /// The penalty is left staked in the vault and distributed like compounded
+    /// base tokens, which increases the value of the remaining vault tokens
+    /// and lock tier shares.
+    ToHolders {
+        /// The fraction of the base tokens that is taken as a penalty.
+        penalty_rate: Decimal,
+    },
+    /// The penalty is sent to fee recipients.
+    ToFeeRecipients(FeeConfig<T>),
+}
+
+impl EmergencyUnlockPenalty<String> {
+    /// Validates the penalty and returns an `EmergencyUnlockPenalty<Addr>`.
+    pub fn check(&self, deps: &Deps) -> StdResult<EmergencyUnlockPenalty<Addr>> {
+        match self {
+            Self::Disabled => Ok(EmergencyUnlockPenalty::Disabled),
+            Self::ToHolders { penalty_rate } => {
+                if *penalty_rate > Decimal::one() {
+                    return Err(StdError::generic_err(
+                        "Penalty rate can't be higher than 100%",
+                    ));
+                }
+                Ok(EmergencyUnlockPenalty::ToHolders {
+                    penalty_rate: *penalty_rate,
+                })
+            }
+            Self::ToFeeRecipients(fee) => {
+                Ok(EmergencyUnlockPenalty::ToFeeRecipients(fee.check(deps)?))
+            }
+        }
+    }
+}
+
+impl From<EmergencyUnlockPenalty<Addr>> for EmergencyUnlockPenalty<String> {
+    fn from(value: EmergencyUnlockPenalty<Addr>) -> Self {
+        match value {
+            EmergencyUnlockPenalty::Disabled => Self::Disabled,
+            EmergencyUnlockPenalty::ToHolders { penalty_rate } => Self::ToHolders { penalty_rate },
+            EmergencyUnlockPenalty::ToFeeRecipients(fee) => Self::ToFeeRecipients(fee.into()),
+        }
+    }
+}
+
 #[optional_struct(ConfigUpdates)]
 #[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+// `EmergencyUnlockPenalty<T>` implements `Default` for every `T`, so don't let
+// serde require `T: Default` for the defaulted fields.
+#[serde(bound(deserialize = "T: Deserialize<'de>"))]
 pub struct ConfigBase<T: AddressLike> {
     /// The duration of the lock period for deposits. This can be set to zero
     /// seconds to disable locking.
@@ -204,6 +319,30 @@ pub struct ConfigBase<T: AddressLike> {
     pub deposit_fee: FeeConfig<T>,
     /// A fee that is taken on withdrawals
     pub withdrawal_fee: FeeConfig<T>,
+    /// The number of seconds that must pass between proposing a config update
+    /// and executing it. Only updates that can not be used to redirect funds
+    /// may bypass this delay.
+    #[serde(default)]
+    pub config_update_delay: u64,
+    /// Whether base tokens that are sent directly to the vault, rather than
+    /// with `Donate`, should be left unstaked instead of being added to the
+    /// vault's assets when compounding.
+    #[serde(default)]
+    pub reject_implicit_donations: bool,
+    /// The penalty that is taken on emergency unlocks. Emergency unlocks are
+    /// disabled by default.
+    #[serde(default)]
+    pub emergency_unlock_penalty: EmergencyUnlockPenalty<T>,
+    /// The lock tiers that depositors can choose instead of receiving vault
+    /// tokens. A tier is identified by its index, so tiers can be changed or
+    /// added but not removed.
+    #[serde(default)]
+    pub lock_tiers: Vec<LockTier>,
+    /// The largest idle balance of a token that `SweepDust` sells. Larger
+    /// balances are assumed to have been sent to the vault by mistake and are
+    /// left to be rescued with `RescueTokens`.
+    #[serde(default)]
+    pub dust_threshold: Uint128,
 }
 
 pub type Config = ConfigBase<Addr>;
@@ -213,70 +352,171 @@ impl ConfigUnchecked {
     /// Checks that the all the values on the `ConfigUnchecked` are valid and
     /// returns a `Config`.
     pub fn check(&self, deps: Deps) -> StdResult<Config> {
-        let api = deps.api;
+        self.check_all(deps)
+            .map_err(|mut errors| errors.remove(0).1)
+    }
 
-        // Check that the lock duration is specified in seconds
-        match self.lock_duration {
-            Duration::Time(_) => {}
-            _ => {
-                return Err(StdError::generic_err(
-                    "lock_duration must be specified in seconds",
-                ))
-            }
-        }
+    /// Performs the same validation as [`ConfigUnchecked::check`], but instead
+    /// of returning on the first failure, it returns all failures paired with
+    /// the name of the config field that caused them.
+    pub fn check_all(&self, deps: Deps) -> Result<Config, Vec<(String, StdError)>> {
+        let api = deps.api;
+        let mut errors: Vec<(String, StdError)> = vec![];
 
         // Validate reward tokens
-        let reward_tokens = self
-            .reward_tokens
-            .iter()
-            .map(|asset_info| asset_info.check(api))
-            .collect::<StdResult<Vec<_>>>()?;
-        let reward_liquidation_target = self.reward_liquidation_target.check(api)?;
-        let router = self.router.check(api)?;
+        let reward_tokens = collect_error(
+            &mut errors,
+            "reward_tokens",
+            self.reward_tokens
+                .iter()
+                .map(|asset_info| asset_info.check(api))
+                .collect::<StdResult<Vec<_>>>(),
+        );
+        let reward_liquidation_target = collect_error(
+            &mut errors,
+            "reward_liquidation_target",
+            self.reward_liquidation_target.check(api),
+        );
+        let router = collect_error(&mut errors, "router", self.router.check(api));
 
         // Reward liquidation target must be one of the pool assets
-        let pool_assets = POOL.load(deps.storage)?.pool_assets;
-        if !pool_assets.contains(&reward_liquidation_target) {
-            return Err(StdError::generic_err(format!(
-                "Reward liquidation target {:?} is not in the pool assets {:?}",
-                reward_liquidation_target, pool_assets
-            )));
+        if let Some(reward_liquidation_target) = &reward_liquidation_target {
+            let pool_assets = POOL.load(deps.storage).map(|pool| pool.pool_assets);
+            let res = pool_assets.and_then(|pool_assets| {
+                if pool_assets.contains(reward_liquidation_target) {
+                    Ok(())
+                } else {
+                    Err(StdError::generic_err(format!(
+                        "Reward liquidation target {:?} is not in the pool assets {:?}",
+                        reward_liquidation_target, pool_assets
+                    )))
+                }
+            });
+            collect_error(&mut errors, "reward_liquidation_target", res);
         }
 
         // Check that the router can route between all reward assets and the
-        // reward liquidation target. We discard the actual path because we
-        // don't need it here. We just need to make sure the paths exist.
-        for asset in &reward_tokens {
-            // We skip the reward liquidation target because we don't need to
-            // route from it.
-            if asset == &reward_liquidation_target {
-                continue;
+        // reward liquidation target.
+        if let (Some(reward_tokens), Some(reward_liquidation_target), Some(router)) =
+            (&reward_tokens, &reward_liquidation_target, &router)
+        {
+            for asset in reward_tokens {
+                collect_error(
+                    &mut errors,
+                    "reward_tokens",
+                    check_route(deps, router, asset, reward_liquidation_target),
+                );
             }
-            // We map the error here because the error coming from the router is
-            // not passed along into the query error, and thus we will otherwise
-            // just see "Querier contract error" and no more information.
-            router
-                .query_path_for_pair(&deps.querier, asset, &reward_liquidation_target)
-                .map_err(|_| {
-                    StdError::generic_err(format!(
-                        "Could not read path in cw-dex-router for {:?} -> {:?}",
-                        asset, reward_liquidation_target
-                    ))
-                })?;
-        }
-
-        Ok(Config {
-            lock_duration: self.lock_duration,
+        }
+
+        let liquidity_helper = collect_error(
+            &mut errors,
+            "liquidity_helper",
+            self.liquidity_helper.check(api),
+        );
+        let performance_fee = collect_error(
+            &mut errors,
+            "performance_fee",
+            self.performance_fee.check(&deps),
+        );
+        let deposit_fee = collect_error(&mut errors, "deposit_fee", self.deposit_fee.check(&deps));
+        let withdrawal_fee = collect_error(
+            &mut errors,
+            "withdrawal_fee",
+            self.withdrawal_fee.check(&deps),
+        );
+        let emergency_unlock_penalty = collect_error(
+            &mut errors,
+            "emergency_unlock_penalty",
+            self.emergency_unlock_penalty.check(&deps),
+        );
+        let lock_tiers = collect_error(
+            &mut errors,
+            "lock_tiers",
+            self.lock_tiers
+                .iter()
+                .map(|lock_tier| lock_tier.check().map(|_| lock_tier.clone()))
+                .collect::<StdResult<Vec<_>>>(),
+        );
+
+        match (
             reward_tokens,
-            deposits_enabled: self.deposits_enabled,
             router,
             reward_liquidation_target,
-            liquidity_helper: self.liquidity_helper.check(api)?,
-            performance_fee: self.performance_fee.check(&deps)?,
-            deposit_fee: self.deposit_fee.check(&deps)?,
-            withdrawal_fee: self.withdrawal_fee.check(&deps)?,
-        })
+            liquidity_helper,
+            performance_fee,
+            deposit_fee,
+            withdrawal_fee,
+            emergency_unlock_penalty,
+            lock_tiers,
+        ) {
+            (
+                Some(reward_tokens),
+                Some(router),
+                Some(reward_liquidation_target),
+                Some(liquidity_helper),
+                Some(performance_fee),
+                Some(deposit_fee),
+                Some(withdrawal_fee),
+                Some(emergency_unlock_penalty),
+                Some(lock_tiers),
+            ) if errors.is_empty() => Ok(Config {
+                lock_duration: self.lock_duration,
+                reward_tokens,
+                deposits_enabled: self.deposits_enabled,
+                router,
+                reward_liquidation_target,
+                liquidity_helper,
+                performance_fee,
+                deposit_fee,
+                withdrawal_fee,
+                config_update_delay: self.config_update_delay,
+                reject_implicit_donations: self.reject_implicit_donations,
+                emergency_unlock_penalty,
+                lock_tiers,
+                dust_threshold: self.dust_threshold,
+            }),
+            _ => Err(errors),
+        }
+    }
+}
+
+/// Pushes the error of `res` paired with `field` onto `errors` if `res` is an
+/// error, and returns the value of `res` as an `Option`.
+fn collect_error<T>(
+    errors: &mut Vec<(String, StdError)>,
+    field: &str,
+    res: StdResult<T>,
+) -> Option<T> {
+    res.map_err(|err| errors.push((field.to_string(), err)))
+        .ok()
+}
+
+/// Checks that the router can route from `asset` to
+/// `reward_liquidation_target`. We discard the actual path because we don't
+/// need it here. We just need to make sure the path exists.
+pub fn check_route(
+    deps: Deps,
+    router: &CwDexRouter,
+    asset: &AssetInfo,
+    reward_liquidation_target: &AssetInfo,
+) -> StdResult<()> {
+    // We don't need to route from the reward liquidation target itself.
+    if asset == reward_liquidation_target {
+        return Ok(());
     }
+    // We map the error here because the error coming from the router is
+    // not passed along into the query error, and thus we will otherwise
+    // just see "Querier contract error" and no more information.
+    router
+        .query_path_for_pair(&deps.querier, asset, reward_liquidation_target)
+        .map_err(|_| {
+            StdError::generic_err(format!(
+                "Could not read path in cw-dex-router for {:?} -> {:?}",
+                asset, reward_liquidation_target
+            ))
+        })?;
+    Ok(())
 }
 
 impl From<Config> for ConfigUnchecked {
@@ -291,11 +531,382 @@ impl From<Config> for ConfigUnchecked {
             performance_fee: value.performance_fee.into(),
             deposit_fee: value.deposit_fee.into(),
             withdrawal_fee: value.withdrawal_fee.into(),
+            config_update_delay: value.config_update_delay,
+            reject_implicit_donations: value.reject_implicit_donations,
+            emergency_unlock_penalty: value.emergency_unlock_penalty.into(),
+            lock_tiers: value.lock_tiers,
+            dust_threshold: value.dust_threshold,
+        }
+    }
+}
+
+impl ConfigUpdates<String> {
+    /// Returns true if the updates can be applied immediately, without going
+    /// through the config update timelock. This is only the case for updates
+    /// that can not be used to redirect funds, i.e. disabling deposits or
+    /// emergency unlocks, shortening the lock duration, lowering the dust
+    /// threshold and lowering fee rates without changing the fee recipients.
+    pub fn can_bypass_timelock(&self, current: &Config) -> bool {
+        is_lock_duration_decrease(&self.lock_duration, &current.lock_duration)
+            && self.reward_tokens.is_none()
+            && self.deposits_enabled != Some(true)
+            && self.router.is_none()
+            && self.reward_liquidation_target.is_none()
+            && self.liquidity_helper.is_none()
+            && self.config_update_delay.is_none()
+            && matches!(
+                self.emergency_unlock_penalty,
+                None | Some(EmergencyUnlockPenalty::Disabled)
+            )
+            && self.lock_tiers.is_none()
+            && self
+                .dust_threshold
+                .is_none_or(|dust_threshold| dust_threshold <= current.dust_threshold)
+            && is_fee_decrease(&self.performance_fee, &current.performance_fee)
+            && is_fee_decrease(&self.deposit_fee, &current.deposit_fee)
+            && is_fee_decrease(&self.withdrawal_fee, &current.withdrawal_fee)
+    }
+}
+
+/// Returns true if `update` is either not set or shortens the lock duration
+/// without changing its unit.
+fn is_lock_duration_decrease(update: &Option<Duration>, current: &Duration) -> bool {
+    match (update, current) {
+        (None, _) => true,
+        (Some(Duration::Time(new)), Duration::Time(current)) => new <= current,
+        (Some(Duration::Height(new)), Duration::Height(current)) => new <= current,
+        _ => false,
+    }
+}
+
+/// Returns true if `update` is either not set, sets the fee rate to zero, or
+/// lowers the fee rate while keeping the same fee recipients.
+fn is_fee_decrease(update: &Option<FeeConfig<String>>, current: &FeeConfig<Addr>) -> bool {
+    match update {
+        None => true,
+        Some(fee) if fee.fee_rate.is_zero() => true,
+        Some(fee) => {
+            fee.fee_rate <= current.fee_rate
+                && fee.fee_recipients == FeeConfig::<String>::from(current.clone()).fee_recipients
+        }
+    }
+}
+
+#[cw_serde]
+/// A single failure from validating a config.
+pub struct ConfigValidationError {
+    /// The name of the config field that failed validation.
+    pub field: String,
+    /// The error message.
+    pub error: String,
+}
+
+#[cw_serde]
+/// The response to the `ValidateConfigUpdate` query.
+pub struct ValidateConfigUpdateResponse {
+    /// The config that would result from applying the updates, or `None` if
+    /// the resulting config is invalid.
+    pub config: Option<Config>,
+    /// All failures from validating the resulting config.
+    pub errors: Vec<ConfigValidationError>,
+}
+
+#[cw_serde]
+/// A config update that has been proposed by the owner and can be executed
+/// once the config update delay has passed.
+pub struct ConfigUpdateProposal {
+    /// The id of the proposal.
+    pub id: u64,
+    /// The proposed config updates.
+    pub updates: ConfigUpdates<String>,
+    /// The proposed staking mode, if it should be changed.
+    pub staking_mode: Option<StakingModeUnchecked>,
+    /// The address of the pool to migrate the vault's liquidity to, if any.
+    #[serde(default)]
+    pub pool: Option<String>,
+    /// The time at which the proposal was created.
+    pub proposed_at: Timestamp,
+    /// The time after which the proposal can be executed.
+    pub executable_at: Timestamp,
+}
+
+#[cw_serde]
+#[derive(Default)]
+/// The cumulative amounts of base tokens donated to the vault. Donations are
+/// added to the vault's assets without minting vault tokens.
+pub struct Donations {
+    /// Base tokens donated with the `Donate` message.
+    pub explicit: Uint128,
+    /// Base tokens that were sent directly to the vault and staked when
+    /// compounding.
+    pub implicit: Uint128,
+}
+
+#[cw_serde]
+/// How the vault stakes its LP tokens.
+pub enum StakingMode {
+    /// The LP tokens are staked in the Astroport incentives contract.
+    Astroport(AstroportStaking),
+    /// The LP tokens are held by the vault without being staked. Used for
+    /// pools that are not incentivized.
+    Hold,
+}
+
+#[cw_serde]
+/// An unvalidated `StakingMode` used in messages.
+pub enum StakingModeUnchecked {
+    /// Stake the LP tokens in the given Astroport incentives contract.
+    Astroport { incentives: String },
+    /// Hold the LP tokens in the vault without staking them.
+    Hold {},
+}
+
+impl StakingModeUnchecked {
+    /// Validates the staking mode and returns a `StakingMode` for staking
+    /// `lp_token`.
+    pub fn check(&self, api: &dyn Api, lp_token: &AssetInfo) -> StdResult<StakingMode> {
+        Ok(match self {
+            StakingModeUnchecked::Astroport { incentives } => {
+                StakingMode::Astroport(AstroportStaking {
+                    lp_token: lp_token.clone(),
+                    incentives: api.addr_validate(incentives)?,
+                })
+            }
+            StakingModeUnchecked::Hold {} => StakingMode::Hold,
+        })
+    }
+}
+
+impl StakingMode {
+    /// Returns a response that stakes `amount` LP tokens.
+    pub fn stake(&self, deps: Deps, env: &Env, amount: Uint128) -> Result<Response, CwDexError> {
+        match self {
+            StakingMode::Astroport(staking) => staking.stake(deps, env, amount),
+            StakingMode::Hold => Ok(Response::new()),
+        }
+    }
+
+    /// Returns a response that unstakes `amount` LP tokens into the vault.
+    pub fn unstake(&self, deps: Deps, env: &Env, amount: Uint128) -> Result<Response, CwDexError> {
+        match self {
+            StakingMode::Astroport(staking) => staking.unstake(deps, env, amount),
+            StakingMode::Hold => Ok(Response::new()),
+        }
+    }
+
+    /// Returns a response that claims all pending rewards.
+    pub fn claim_rewards(&self, deps: Deps, env: &Env) -> Result<Response, CwDexError> {
+        match self {
+            StakingMode::Astroport(staking) => staking.claim_rewards(deps, env),
+            StakingMode::Hold => Ok(Response::new()),
+        }
+    }
+
+    /// Queries the rewards that can be claimed by `user`.
+    pub fn query_pending_rewards(
+        &self,
+        querier: &QuerierWrapper,
+        user: &Addr,
+    ) -> Result<AssetList, CwDexError> {
+        match self {
+            StakingMode::Astroport(staking) => staking.query_pending_rewards(querier, user),
+            StakingMode::Hold => Ok(AssetList::new()),
+        }
+    }
+
+    /// Returns the amount of LP tokens that the vault holds on behalf of its
+    /// depositors without them being staked, i.e. the amount of LP tokens in
+    /// the vault's balance that should not be treated as newly compounded.
+    pub fn held_base_tokens(&self, storage: &dyn Storage) -> StdResult<Uint128> {
+        match self {
+            StakingMode::Astroport(_) => Ok(Uint128::zero()),
+            StakingMode::Hold => {
+                let state = STATE.load(storage)?;
+                Ok(state
+                    .staked_base_tokens
+                    .checked_add(lock_tier_base_tokens(storage)?)?
+                    .checked_add(claims().query_total_amount(storage)?)?)
+            }
+        }
+    }
+
+    /// Returns the total amount of LP tokens that is staked or held on behalf
+    /// of the vault's depositors, including LP tokens in unlocking claims.
+    pub fn query_staked_amount(&self, deps: Deps, env: &Env) -> StdResult<Uint128> {
+        match self {
+            StakingMode::Astroport(staking) => deps.querier.query_wasm_smart(
+                staking.incentives.to_string(),
+                &astroport_v5::incentives::QueryMsg::Deposit {
+                    lp_token: staking.lp_token.to_string(),
+                    user: env.contract.address.to_string(),
+                },
+            ),
+            StakingMode::Hold => self.held_base_tokens(deps.storage),
+        }
+    }
+}
+
+#[cw_serde]
+#[derive(Default)]
+/// The protobuf package of the token factory module of a chain. The messages
+/// of all supported packages have the same fields, so only their type URLs
+/// differ.
+pub enum TokenFactoryFlavor {
+    /// `osmosis.tokenfactory.v1beta1`, used by Osmosis, Neutron and Terra.
+    #[default]
+    Osmosis,
+    /// `injective.tokenfactory.v1beta1`, used by Injective.
+    Injective,
+    /// `seiprotocol.seichain.tokenfactory`, used by Sei.
+    Sei,
+}
+
+impl TokenFactoryFlavor {
+    /// Returns `msg`, which must be an `osmosis.tokenfactory.v1beta1` message,
+    /// as a stargate message with the type URL of this flavor.
+    pub fn stargate_msg<M>(&self, type_url: &str, msg: M) -> CosmosMsg
+    where
+        Binary: From<M>,
+    {
+        let msg_name = type_url.rsplit('.').next().unwrap_or(type_url);
+        let package = match self {
+            TokenFactoryFlavor::Osmosis => "osmosis.tokenfactory.v1beta1",
+            TokenFactoryFlavor::Injective => "injective.tokenfactory.v1beta1",
+            TokenFactoryFlavor::Sei => "seiprotocol.seichain.tokenfactory",
+        };
+        CosmosMsg::Stargate {
+            type_url: format!("/{package}.{msg_name}"),
+            value: Binary::from(msg),
+        }
+    }
+
+    /// Returns a message that creates the denom `factory/{vault}/{subdenom}`.
+    pub fn create_denom_msg(&self, env: &Env, subdenom: String) -> CosmosMsg {
+        self.stargate_msg(
+            MsgCreateDenom::TYPE_URL,
+            MsgCreateDenom {
+                sender: env.contract.address.to_string(),
+                subdenom,
+            },
+        )
+    }
+
+    /// Returns a message that mints `amount` of `denom` to the vault.
+    pub fn mint_msg(&self, env: &Env, denom: &str, amount: Uint128) -> CosmosMsg {
+        self.stargate_msg(
+            MsgMint::TYPE_URL,
+            MsgMint {
+                sender: env.contract.address.to_string(),
+                amount: Some(coin(amount.u128(), denom).into()),
+            },
+        )
+    }
+
+    /// Returns a message that burns `amount` of `denom` held by the vault.
+    pub fn burn_msg(&self, env: &Env, denom: &str, amount: Uint128) -> CosmosMsg {
+        self.stargate_msg(
+            MsgBurn::TYPE_URL,
+            MsgBurn {
+                sender: env.contract.address.to_string(),
+                amount: Some(coin(amount.u128(), denom).into()),
+            },
+        )
+    }
+
+    /// Returns a message that sets the bank metadata of a denom created by the
+    /// vault.
+    pub fn set_denom_metadata_msg(&self, env: &Env, metadata: Metadata) -> CosmosMsg {
+        self.stargate_msg(
+            MsgSetDenomMetadata::TYPE_URL,
+            MsgSetDenomMetadata {
+                sender: env.contract.address.to_string(),
+                metadata: Some(metadata),
+            },
+        )
+    }
+}
+
+#[cw_serde]
+/// The token that represents shares of the vault.
+pub enum VaultToken {
+    /// A native token that was created by the vault with the token factory
+    /// module.
+    TokenFactory {
+        denom: String,
+        /// The token factory module used to mint and burn the token. Vault
+        /// tokens stored before flavors were introduced are Osmosis tokens.
+        #[serde(default)]
+        flavor: TokenFactoryFlavor,
+    },
+    /// A cw20 token that was instantiated by the vault, with the vault as its
+    /// minter.
+    Cw20 { addr: Addr },
+}
+
+impl Display for VaultToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VaultToken::TokenFactory { denom, .. } => write!(f, "{denom}"),
+            VaultToken::Cw20 { addr } => write!(f, "{addr}"),
+        }
+    }
+}
+
+impl VaultToken {
+    /// Returns the vault token as an `AssetInfo`.
+    pub fn to_asset_info(&self) -> AssetInfo {
+        match self {
+            VaultToken::TokenFactory { denom, .. } => AssetInfo::Native(denom.clone()),
+            VaultToken::Cw20 { addr } => AssetInfo::Cw20(addr.clone()),
+        }
+    }
+
+    /// Returns the messages to mint `amount` vault tokens to `recipient`.
+    pub fn mint_msgs(
+        &self,
+        env: &Env,
+        amount: Uint128,
+        recipient: &Addr,
+    ) -> StdResult<Vec<CosmosMsg>> {
+        match self {
+            // Token factory tokens are minted to the vault and then sent to
+            // the recipient
+            VaultToken::TokenFactory { denom, flavor } => Ok(vec![
+                flavor.mint_msg(env, denom, amount),
+                BankMsg::Send {
+                    to_address: recipient.to_string(),
+                    amount: coins(amount.u128(), denom),
+                }
+                .into(),
+            ]),
+            VaultToken::Cw20 { addr } => Ok(vec![WasmMsg::Execute {
+                contract_addr: addr.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Mint {
+                    recipient: recipient.to_string(),
+                    amount,
+                })?,
+                funds: vec![],
+            }
+            .into()]),
+        }
+    }
+
+    /// Returns a message to burn `amount` vault tokens held by the vault.
+    pub fn burn_msg(&self, env: &Env, amount: Uint128) -> StdResult<CosmosMsg> {
+        match self {
+            VaultToken::TokenFactory { denom, flavor } => Ok(flavor.burn_msg(env, denom, amount)),
+            VaultToken::Cw20 { addr } => Ok(WasmMsg::Execute {
+                contract_addr: addr.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Burn { amount })?,
+                funds: vec![],
+            }
+            .into()),
         }
     }
 }
 
 #[cw_serde]
+#[derive(Default)]
 /// A struct that represents the state of the vault.
 pub struct VaultState {
     /// The total amount of base tokens staked in the vault.
@@ -304,6 +915,218 @@ pub struct VaultState {
     pub vault_token_supply: Uint128,
 }
 
+impl VaultState {
+    /// Returns the number of shares that `base_token_amount` base tokens are
+    /// worth.
+    pub fn convert_to_shares(&self, base_token_amount: Uint128) -> Uint128 {
+        // vault_token_supply can be zero when staked_base_tokens is not zero, if
+        // there are rewards in the vault before the first deposit since in this
+        // case they would get compounded and the staked_base_tokens would
+        // increase without minting any vault tokens. Therefore, we base the
+        // initial amount of vault tokens on the total amount of base tokens
+        // staked in the vault, rather than just the deposited amount so that the
+        // vault token price cannot be manipulated.
+        if self.staked_base_tokens.is_zero() || self.vault_token_supply.is_zero() {
+            return (self.staked_base_tokens + base_token_amount)
+                * INITIAL_VAULT_TOKENS_PER_BASE_TOKEN;
+        }
+        self.vault_token_supply
+            .multiply_ratio(base_token_amount, self.staked_base_tokens)
+    }
+
+    /// Returns the number of base tokens that `shares` shares are worth.
+    pub fn convert_to_assets(&self, shares: Uint128) -> Uint128 {
+        if self.vault_token_supply.is_zero() {
+            return shares / INITIAL_VAULT_TOKENS_PER_BASE_TOKEN;
+        }
+        self.staked_base_tokens
+            .multiply_ratio(shares, self.vault_token_supply)
+    }
+}
+
+#[cw_serde]
+/// A lock tier that depositors can choose when depositing with
+/// `DepositToLockTier`. Base tokens deposited to a tier are accounted for in a
+/// separate share class, are locked for the tier's lock duration when unlocked
+/// and receive a share of the compounded rewards according to the tier's
+/// weight.
+pub struct LockTier {
+    /// The duration of the lock period when unlocking shares of the tier.
+    pub lock_duration: Duration,
+    /// The weight of the tier's base tokens when compounded base tokens are
+    /// distributed. Base tokens that back vault tokens have a weight of one.
+    pub weight: Decimal,
+}
+
+impl LockTier {
+    /// Checks that the weight of the lock tier is not zero.
+    pub fn check(&self) -> StdResult<()> {
+        if self.weight.is_zero() {
+            return Err(StdError::generic_err(
+                "Lock tier weight must be greater than zero",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Returns true if the vault has been shut down.
+pub fn is_shut_down(storage: &dyn Storage) -> StdResult<bool> {
+    Ok(SHUTDOWN.may_load(storage)?.is_some())
+}
+
+/// Returns the total amount of base tokens staked in all lock tiers.
+pub fn lock_tier_base_tokens(storage: &dyn Storage) -> StdResult<Uint128> {
+    LOCK_TIER_STATES
+        .range(storage, None, None, Order::Ascending)
+        .try_fold(Uint128::zero(), |total, item| {
+            let (_, state) = item?;
+            Ok(total.checked_add(state.staked_base_tokens)?)
+        })
+}
+
+/// The number of decimals of Astroport LP tokens, which is used as the default
+/// number of decimals of the base token.
+pub const DEFAULT_BASE_TOKEN_DECIMALS: u32 = 6;
+
+#[cw_serde]
+#[derive(Default)]
+/// Bank metadata for the vault token, which wallets and explorers use to
+/// display it. Fields that are not set are derived from the denoms of the pool
+/// assets, so they should be set explicitly for pools with e.g. IBC assets.
+pub struct VaultTokenMetadata {
+    /// The name of the vault token. Defaults to e.g. "Apollo ATOM-OSMO Vault
+    /// Token". Required for pools with IBC or cw20 assets.
+    pub name: Option<String>,
+    /// The symbol of the vault token, which is also used as the display denom.
+    /// Defaults to e.g. "apATOM-OSMO". Required for pools with IBC or cw20
+    /// assets.
+    pub symbol: Option<String>,
+    /// A description of the vault token. Required for pools with IBC or cw20
+    /// assets.
+    pub description: Option<String>,
+    /// The number of decimals of the base token. Defaults to 6.
+    pub base_token_decimals: Option<u32>,
+}
+
+impl VaultTokenMetadata {
+    /// Returns the name of the vault token, or the default name for a pool
+    /// with `pool_assets` if no name is set.
+    pub fn name(&self, pool_assets: &[AssetInfo]) -> StdResult<String> {
+        match &self.name {
+            Some(name) => Ok(name.clone()),
+            None => Ok(format!(
+                "Apollo {} Vault Token",
+                pair_display_name(pool_assets)?
+            )),
+        }
+    }
+
+    /// Returns the symbol of the vault token, or the default symbol for a pool
+    /// with `pool_assets` if no symbol is set.
+    pub fn symbol(&self, pool_assets: &[AssetInfo]) -> StdResult<String> {
+        match &self.symbol {
+            Some(symbol) => Ok(symbol.clone()),
+            None => Ok(format!("ap{}", pair_display_name(pool_assets)?)),
+        }
+    }
+
+    /// Returns the description of the vault token, or the default description
+    /// for a pool with `pool_assets` if no description is set.
+    pub fn description(&self, pool_assets: &[AssetInfo]) -> StdResult<String> {
+        match &self.description {
+            Some(description) => Ok(description.clone()),
+            None => Ok(format!(
+                "Apollo vault token for the Astroport {} pool",
+                pair_display_name(pool_assets)?
+            )),
+        }
+    }
+
+    /// Returns the number of decimals of the vault token. Since
+    /// `INITIAL_VAULT_TOKENS_PER_BASE_TOKEN` vault tokens are minted per base
+    /// token, the vault token has that many more decimals than the base token.
+    pub fn decimals(&self) -> u32 {
+        self.base_token_decimals
+            .unwrap_or(DEFAULT_BASE_TOKEN_DECIMALS)
+            + INITIAL_VAULT_TOKENS_PER_BASE_TOKEN.u128().ilog10()
+    }
+
+    /// Returns the bank metadata for the vault token `vault_token_denom` of a
+    /// vault for a pool with `pool_assets`.
+    pub fn to_bank_metadata(
+        &self,
+        vault_token_denom: &str,
+        pool_assets: &[AssetInfo],
+    ) -> StdResult<Metadata> {
+        let symbol = self.symbol(pool_assets)?;
+        Ok(Metadata {
+            description: self.description(pool_assets)?,
+            denom_units: vec![
+                DenomUnit {
+                    denom: vault_token_denom.to_string(),
+                    exponent: 0,
+                    aliases: vec![],
+                },
+                DenomUnit {
+                    denom: symbol.clone(),
+                    exponent: self.decimals(),
+                    aliases: vec![],
+                },
+            ],
+            base: vault_token_denom.to_string(),
+            display: symbol.clone(),
+            name: self.name(pool_assets)?,
+            symbol,
+        })
+    }
+}
+
+/// Native denoms of micro units, whose display name is the denom without the
+/// leading "u".
+const MICRO_UNIT_DENOMS: &[&str] = &[
+    "uatom", "uosmo", "untrn", "uluna", "ujuno", "ustars", "uaxl", "uastro", "ukuji", "uwhale",
+    "usei",
+];
+
+/// Returns a short, human readable name for a pool with `pool_assets`, e.g.
+/// "ATOM-OSMO". Returns an error if any of the assets has no display name.
+fn pair_display_name(pool_assets: &[AssetInfo]) -> StdResult<String> {
+    pool_assets
+        .iter()
+        .map(|asset| {
+            asset_display_name(asset).ok_or_else(|| {
+                StdError::generic_err(format!(
+                    "No display name for {}. The vault token name, symbol and description must \
+                     be set for pools with IBC or cw20 assets",
+                    asset
+                ))
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()
+        .map(|names| names.join("-"))
+}
+
+/// Returns a short, human readable name for `asset`. For native denoms this is
+/// the last path segment of the denom, uppercased and without the leading "u"
+/// for known micro unit denoms, e.g. "uosmo" and "factory/addr/uosmo" both
+/// become "OSMO" while "usdc" becomes "USDC". IBC denoms and cw20 addresses
+/// are not human readable, so `None` is returned for them.
+fn asset_display_name(asset: &AssetInfo) -> Option<String> {
+    match asset {
+        AssetInfo::Native(denom) if denom.starts_with("ibc/") => None,
+        AssetInfo::Native(denom) => {
+            let name = denom.rsplit('/').next().unwrap_or(denom);
+            let name = match MICRO_UNIT_DENOMS.contains(&name) {
+                true => &name[1..],
+                false => name,
+            };
+            Some(name.to_uppercase())
+        }
+        AssetInfo::Cw20(_) => None,
+    }
+}
+
 #[cw_serde]
 pub struct StateResponse {
     /// The total amount of base tokens staked in the vault.
@@ -312,13 +1135,146 @@ pub struct StateResponse {
     pub vault_token_supply: Uint128,
     /// The base token.
     pub base_token: AssetInfo,
-    //// The denom of the native vault token that represents shares of the vault.
-    pub vault_token_denom: String,
+    /// The token that represents shares of the vault.
+    pub vault_token: VaultToken,
     /// The address of the Astroport pool that this vault is compounding rewards
     /// into.
     pub pool: AstroportPool,
-    /// The AstroportStaking object config.
-    pub staking: AstroportStaking,
+    /// How the vault stakes its LP tokens.
+    pub staking: StakingMode,
+    /// The cw721 contract in which unlocking positions are minted as NFTs, if
+    /// unlocking positions are NFTs.
+    pub unlocking_position_nft: Option<Addr>,
+    /// The lock duration reduction that is still being applied to existing
+    /// claims, if any.
+    pub lock_duration_reduction: Option<LockDurationReduction>,
+    /// The conversion of existing claims to the base token of a new pool that
+    /// is still being applied, if any.
+    pub claims_rescale: Option<ClaimsRescale>,
+    /// When the vault was shut down, if it has been shut down.
+    pub shutdown: Option<Shutdown>,
+}
+
+#[cw_serde]
+/// The response to the `UserPosition` query.
+pub struct UserPositionResponse {
+    /// The vault token balance of the user.
+    pub vault_token_balance: Uint128,
+    /// The amount of base tokens that the vault tokens can be redeemed for.
+    pub base_token_value: Uint128,
+    /// The amounts of the pool assets that the base tokens in
+    /// `base_token_value` can be withdrawn for.
+    pub pool_assets_value: Vec<Asset>,
+    /// The shares of the user in the lock tiers, and their values.
+    pub lock_tier_positions: Vec<LockTierPositionResponse>,
+    /// Whether the user has more claims than are summarized below. Only the
+    /// first `USER_POSITION_CLAIMS_LIMIT` claims are summarized, the rest can
+    /// be paginated with the `UnlockingPositions` query.
+    pub claims_truncated: bool,
+    /// The number of claims of the user that are still unlocking.
+    pub unlocking_claims: u32,
+    /// The total amount of base tokens in claims that are still unlocking.
+    pub unlocking_base_tokens: Uint128,
+    /// The number of claims of the user that can be withdrawn.
+    pub matured_claims: u32,
+    /// The total amount of base tokens in claims that can be withdrawn.
+    pub matured_base_tokens: Uint128,
+    /// When the next of the unlocking claims matures, if there are any.
+    pub next_release_at: Option<Expiration>,
+    /// The total amount of base tokens in the summarized claims that were
+    /// unlocked from each lock tier, by lock tier. These are included in the
+    /// unlocking and matured amounts above.
+    pub lock_tier_claims_base_tokens: Vec<(u32, Uint128)>,
+}
+
+#[cw_serde]
+/// A lock tier and its share class, as returned by the `LockTiers` query.
+pub struct LockTierResponse {
+    /// The index of the lock tier in `Config::lock_tiers`.
+    pub lock_tier: u32,
+    /// The duration of the lock period when unlocking shares of the tier.
+    pub lock_duration: Duration,
+    /// The weight of the tier's base tokens when compounded base tokens are
+    /// distributed.
+    pub weight: Decimal,
+    /// The total amount of base tokens staked in the tier.
+    pub staked_base_tokens: Uint128,
+    /// The total amount of shares of the tier.
+    pub total_shares: Uint128,
+}
+
+#[cw_serde]
+/// The shares of a lock tier that an address owns, as returned by the
+/// `LockTierPositions` query.
+pub struct LockTierPositionResponse {
+    /// The index of the lock tier in `Config::lock_tiers`.
+    pub lock_tier: u32,
+    /// The amount of shares of the tier.
+    pub shares: Uint128,
+    /// The amount of base tokens that the shares can be unlocked for.
+    pub base_token_value: Uint128,
+}
+
+#[cw_serde]
+/// The block in which the vault was shut down with `Shutdown`.
+pub struct Shutdown {
+    /// The height of the block.
+    pub height: u64,
+    /// The time of the block.
+    pub time: Timestamp,
+}
+
+impl From<&BlockInfo> for Shutdown {
+    fn from(block: &BlockInfo) -> Self {
+        Self {
+            height: block.height,
+            time: block.time,
+        }
+    }
+}
+
+#[cw_serde]
+/// A lock duration reduction that is applied to existing claims in batches.
+pub struct LockDurationReduction {
+    /// The new lock duration, which claims are shortened to.
+    pub lock_duration: Duration,
+    /// The id of the first claim created after the reduction was started.
+    /// Only claims with lower ids are shortened.
+    pub end: u64,
+    /// The id of the last claim that was processed, if any.
+    pub start_after: Option<u64>,
+}
+
+#[cw_serde]
+/// The response to the `PendingRewards` query.
+pub struct PendingRewardsResponse {
+    /// The rewards that can currently be claimed from the staking contract.
+    pub claimable: Vec<Asset>,
+    /// The balances of the reward tokens that are already held by the vault.
+    pub in_contract: Vec<Asset>,
+}
+
+#[cw_serde]
+/// The response to the `SimulateCompound` query. All values are estimates of
+/// what would happen if the vault was compounded in the current block.
+pub struct SimulateCompoundResponse {
+    /// The reward token balances after claiming rewards from the staking
+    /// contract.
+    pub rewards: Vec<Asset>,
+    /// The performance fees that would be sent to the fee recipients.
+    pub performance_fees: Vec<Asset>,
+    /// The reward tokens that would be sold through the router.
+    pub tokens_to_sell: Vec<Asset>,
+    /// The amount of the reward liquidation target that would be received from
+    /// selling `tokens_to_sell`.
+    pub liquidation_target_received: Uint128,
+    /// The pool assets that liquidity would be provided with.
+    pub provide_liquidity_assets: Vec<Asset>,
+    /// The amount of base tokens that would be minted by providing liquidity.
+    pub base_tokens_minted: Uint128,
+    /// The total amount of base tokens that would be staked, including base
+    /// tokens already held by the vault.
+    pub base_tokens_to_stake: Uint128,
 }
 
 #[cfg(test)]
@@ -515,4 +1471,174 @@ pub mod tests {
         assert_eq!(assets_after_fee.to_vec()[0].amount, Uint128::new(100));
         assert_eq!(assets_after_fee.to_vec()[1].amount, Uint128::new(200));
     }
+
+    #[test]
+    fn vault_token_metadata_defaults_are_derived_from_pool_assets() {
+        let pool_assets = vec![
+            AssetInfo::native("uatom"),
+            AssetInfo::native("factory/addr/uosmo"),
+        ];
+        let metadata = super::VaultTokenMetadata::default()
+            .to_bank_metadata("factory/vault/vault_token", &pool_assets)
+            .unwrap();
+
+        assert_eq!(metadata.name, "Apollo ATOM-OSMO Vault Token");
+        assert_eq!(metadata.symbol, "apATOM-OSMO");
+        assert_eq!(metadata.base, "factory/vault/vault_token");
+        assert_eq!(metadata.display, "apATOM-OSMO");
+        assert_eq!(metadata.denom_units.len(), 2);
+        assert_eq!(metadata.denom_units[0].denom, "factory/vault/vault_token");
+        assert_eq!(metadata.denom_units[0].exponent, 0);
+        assert_eq!(metadata.denom_units[1].denom, "apATOM-OSMO");
+        assert_eq!(metadata.denom_units[1].exponent, 12);
+    }
+
+    #[test]
+    fn vault_token_metadata_overrides_defaults() {
+        let pool_assets = vec![AssetInfo::native("uatom"), AssetInfo::native("uosmo")];
+        let metadata = super::VaultTokenMetadata {
+            name: Some("Vault".to_string()),
+            symbol: Some("VLT".to_string()),
+            description: Some("A vault".to_string()),
+            base_token_decimals: Some(18),
+        }
+        .to_bank_metadata("factory/vault/vault_token", &pool_assets)
+        .unwrap();
+
+        assert_eq!(metadata.name, "Vault");
+        assert_eq!(metadata.symbol, "VLT");
+        assert_eq!(metadata.description, "A vault");
+        assert_eq!(metadata.display, "VLT");
+        assert_eq!(metadata.denom_units[1].denom, "VLT");
+        assert_eq!(metadata.denom_units[1].exponent, 24);
+    }
+
+    #[test]
+    fn vault_token_metadata_only_strips_micro_unit_prefix_of_known_denoms() {
+        let pool_assets = vec![
+            AssetInfo::native("usdc"),
+            AssetInfo::native("factory/addr/uosmo"),
+        ];
+        let metadata = super::VaultTokenMetadata::default();
+
+        assert_eq!(metadata.symbol(&pool_assets).unwrap(), "apUSDC-OSMO");
+    }
+
+    #[test]
+    fn vault_token_metadata_must_be_set_for_ibc_and_cw20_assets() {
+        let ibc_denom = format!("ibc/{}", "A".repeat(64));
+        for pool_assets in [
+            vec![AssetInfo::native(&ibc_denom), AssetInfo::native("uosmo")],
+            vec![
+                AssetInfo::cw20(Addr::unchecked("cw20")),
+                AssetInfo::native("uosmo"),
+            ],
+        ] {
+            let err = super::VaultTokenMetadata::default()
+                .to_bank_metadata("factory/vault/vault_token", &pool_assets)
+                .unwrap_err();
+            assert!(err
+                .to_string()
+                .contains("must be set for pools with IBC or cw20 assets"));
+
+            let metadata = super::VaultTokenMetadata {
+                name: Some("Vault".to_string()),
+                symbol: Some("VLT".to_string()),
+                description: Some("A vault".to_string()),
+                base_token_decimals: None,
+            }
+            .to_bank_metadata("factory/vault/vault_token", &pool_assets)
+            .unwrap();
+            assert_eq!(metadata.symbol, "VLT");
+        }
+    }
+
+    #[test]
+    fn token_factory_flavors_only_differ_in_type_url() {
+        let env = mock_env();
+        let flavors = [
+            (
+                super::TokenFactoryFlavor::Osmosis,
+                "/osmosis.tokenfactory.v1beta1",
+            ),
+            (
+                super::TokenFactoryFlavor::Injective,
+                "/injective.tokenfactory.v1beta1",
+            ),
+            (
+                super::TokenFactoryFlavor::Sei,
+                "/seiprotocol.seichain.tokenfactory",
+            ),
+        ];
+        let osmosis_mint_msg = super::TokenFactoryFlavor::Osmosis.mint_msg(
+            &env,
+            "factory/vault/vault_token",
+            Uint128::new(100),
+        );
+        let CosmosMsg::Stargate {
+            value: osmosis_value,
+            ..
+        } = osmosis_mint_msg
+        else {
+            panic!("Expected a stargate message");
+        };
+
+        for (flavor, package) in flavors {
+            let CosmosMsg::Stargate { type_url, .. } =
+                flavor.create_denom_msg(&env, "vault_token".to_string())
+            else {
+                panic!("Expected a stargate message");
+            };
+            assert_eq!(type_url, format!("{package}.MsgCreateDenom"));
+
+            let CosmosMsg::Stargate { type_url, value } =
+                flavor.mint_msg(&env, "factory/vault/vault_token", Uint128::new(100))
+            else {
+                panic!("Expected a stargate message");
+            };
+            assert_eq!(type_url, format!("{package}.MsgMint"));
+            assert_eq!(value, osmosis_value);
+
+            let CosmosMsg::Stargate { type_url, .. } =
+                flavor.burn_msg(&env, "factory/vault/vault_token", Uint128::new(100))
+            else {
+                panic!("Expected a stargate message");
+            };
+            assert_eq!(type_url, format!("{package}.MsgBurn"));
+        }
+    }
+
+    #[test]
+    fn vault_tokens_without_flavor_are_osmosis_tokens() {
+        let vault_token: super::VaultToken =
+            cosmwasm_std::from_json(br#"{"token_factory":{"denom":"factory/vault/vault_token"}}"#)
+                .unwrap();
+        assert_eq!(
+            vault_token,
+            super::VaultToken::TokenFactory {
+                denom: "factory/vault/vault_token".to_string(),
+                flavor: super::TokenFactoryFlavor::Osmosis,
+            }
+        );
+    }
+
+    #[test]
+    fn lock_duration_decrease_must_keep_unit() {
+        use cw_utils::Duration;
+
+        let current = Duration::Time(100);
+        assert!(super::is_lock_duration_decrease(&None, &current));
+        assert!(super::is_lock_duration_decrease(
+            &Some(Duration::Time(50)),
+            &current
+        ));
+        assert!(!super::is_lock_duration_decrease(
+            &Some(Duration::Time(150)),
+            &current
+        ));
+        assert!(!super::is_lock_duration_decrease(
+            &Some(Duration::Height(50)),
+            &current
+        ));
+    }
 }
@@ -9,7 +9,8 @@ use cw_it::multi_test::MultiTestRunner;
 use cw_it::test_tube::{Account, SigningAccount};
 use cw_it::traits::CwItRunner;
 use cw_it::{OwnedTestRunner, TestRunner};
-use locked_astroport_vault::state::FeeConfig;
+use locked_astroport_vault::msg::InstantiateMsg;
+use locked_astroport_vault::state::{ConfigUnchecked, FeeConfig};
 use locked_astroport_vault_test_helpers::robot::{
     LockedAstroportVaultRobot, LockedVaultDependencies, DENOM_CREATION_FEE,
 };
@@ -149,3 +150,54 @@ pub fn instantiate_vault<'a>(
 
     (robot, treasury_addr)
 }
+
+/// Returns an `InstantiateMsg` for a vault with the same pool and config as
+/// the vault of `robot`, owned by `admin`.
+pub fn instantiate_msg_like<'a>(
+    robot: &LockedAstroportVaultRobot<'a>,
+    dependencies: &'a LockedVaultDependencies<'a>,
+    admin: &SigningAccount,
+) -> InstantiateMsg {
+    let config: ConfigUnchecked = robot.query_config().into();
+    let state = robot.query_state();
+
+    InstantiateMsg {
+        owner: admin.address(),
+        vault_token_subdenom: "testVaultToken".to_string(),
+        vault_token_backend: None,
+        pool_addr: state.pool.pair_addr.to_string(),
+        astroport_incentives_addr: Some(
+            dependencies.astroport_contracts.incentives.address.clone(),
+        ),
+        lock_duration: config.lock_duration,
+        reward_tokens: config.reward_tokens,
+        deposits_enabled: config.deposits_enabled,
+        router: config.router,
+        reward_liquidation_target: config.reward_liquidation_target,
+        liquidity_helper: config.liquidity_helper,
+        astroport_liquidity_manager: state.pool.liquidity_manager.map(|x| x.to_string()),
+        performance_fee: Some(config.performance_fee),
+        deposit_fee: Some(config.deposit_fee),
+        withdrawal_fee: Some(config.withdrawal_fee),
+        config_update_delay: config.config_update_delay,
+        vault_token_metadata: None,
+        unlocking_position_nft_code_id: None,
+    }
+}
+
+/// Instantiates a new vault with `instantiate_msg`.
+pub fn instantiate_with_msg<'a>(
+    runner: &'a TestRunner<'a>,
+    instantiate_msg: &InstantiateMsg,
+    dependencies: &'a LockedVaultDependencies<'a>,
+    admin: &SigningAccount,
+) -> LockedAstroportVaultRobot<'a> {
+    LockedAstroportVaultRobot::new_with_instantiate_msg(
+        runner,
+        LockedAstroportVaultRobot::contract(runner, UNOPTIMIZED_PATH),
+        Coin::from_str(DENOM_CREATION_FEE).unwrap(),
+        instantiate_msg,
+        dependencies,
+        admin,
+    )
+}
@@ -9,7 +9,7 @@ use locked_astroport_vault_test_helpers::robot::LockedAstroportVaultRobot;
 
 use common::{default_instantiate, get_test_runner, DEPS_PATH};
 use locked_astroport_vault::msg::{ExecuteMsg, ExtensionExecuteMsg, InternalMsg};
-use locked_astroport_vault::state::ConfigUpdates;
+use locked_astroport_vault::state::{ConfigUpdates, VaultTokenMetadata};
 use strum::EnumCount;
 
 pub mod common;
@@ -64,6 +64,61 @@ fn update_config_can_only_be_called_by_admin() {
         .update_config(config_updates, Unwrap::Ok, &admin);
 }
 
+#[test]
+fn config_update_proposals_can_only_be_managed_by_admin() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let user = robot.new_user(&admin);
+
+    let config_updates = ConfigUpdates {
+        deposits_enabled: Some(true),
+        ..Default::default()
+    };
+
+    // Try proposing, executing and cancelling as non-admin, should fail. Then try
+    // as admin, should work.
+    robot
+        .propose_config_update(
+            config_updates.clone(),
+            Unwrap::Err("Caller is not the contract's current owner"),
+            &user,
+        )
+        .propose_config_update(config_updates.clone(), Unwrap::Ok, &admin)
+        .propose_config_update(config_updates, Unwrap::Ok, &admin)
+        .increase_time(robot.query_config().config_update_delay)
+        .execute_config_update(
+            0,
+            Unwrap::Err("Caller is not the contract's current owner"),
+            &user,
+        )
+        .execute_config_update(0, Unwrap::Ok, &admin)
+        .cancel_config_update(
+            1,
+            Unwrap::Err("Caller is not the contract's current owner"),
+            &user,
+        )
+        .cancel_config_update(1, Unwrap::Ok, &admin);
+}
+
+#[test]
+fn set_vault_token_metadata_can_only_be_called_by_admin() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let user = robot.new_user(&admin);
+
+    robot.set_vault_token_metadata(
+        VaultTokenMetadata::default(),
+        Unwrap::Err("Caller is not the contract's current owner"),
+        &user,
+    );
+}
+
 #[test]
 #[should_panic(expected = "Caller is not the contract's current owner")]
 fn update_force_withdraw_whitelist_can_only_be_called_by_admin() {
@@ -92,6 +147,7 @@ fn internal_msg_can_only_be_called_by_contract() {
         },
         InternalMsg::StakeLps {
             discount_tokens: Uint128::zero(),
+            untracked_tokens: Uint128::zero(),
         },
         InternalMsg::ProvideLiquidity {},
         InternalMsg::SellTokens {},
@@ -99,10 +155,19 @@ fn internal_msg_can_only_be_called_by_contract() {
             recipient: Addr::unchecked(user.address()),
             amount: Uint128::new(420),
         },
+        InternalMsg::DepositToLockTier {
+            lock_tier: 0,
+            amount: Uint128::new(420),
+            recipient: Addr::unchecked(user.address()),
+        },
         InternalMsg::Redeem {
             recipient: Addr::unchecked(user.address()),
             amount: Uint128::new(420),
         },
+        InternalMsg::StakeMigratedLps {
+            old_base_token_amount: Uint128::new(420),
+            min_base_token_amount: Uint128::new(420),
+        },
     ];
 
     for msg in msgs {
@@ -1,11 +1,15 @@
+use apollo_cw_asset::{Asset, AssetInfo};
 use common::{default_instantiate, instantiate_vault, VaultSetup, DEPS_PATH};
-use cosmwasm_std::{Decimal, Uint128};
+use cosmwasm_std::{coin, Decimal, Uint128};
 use cw_it::helpers::Unwrap;
 use cw_it::robot::TestRobot;
 use cw_it::test_tube::Account;
 use cw_vault_standard_test_helpers::traits::CwVaultStandardRobot;
 use locked_astroport_vault::helpers::INITIAL_VAULT_TOKENS_PER_BASE_TOKEN;
-use locked_astroport_vault_test_helpers::robot::LockedAstroportVaultRobot;
+use locked_astroport_vault::state::ConfigUpdates;
+use locked_astroport_vault_test_helpers::robot::{
+    LockedAstroportVaultRobot, NTRN_DENOM, ONE_DAY_IN_SECS, USDC_DENOM,
+};
 use test_case::test_case;
 
 pub mod common;
@@ -66,3 +70,213 @@ fn rewards_are_compounded_when_in_vault_before_first_deposit() {
         )
         .assert_vt_balance_converted_to_assets_gt(user.address(), deposit_amount);
 }
+
+#[test]
+fn discover_reward_tokens_keeps_configured_reward_tokens() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let user = robot.new_user(&admin);
+
+    // Deposit so that the vault's LP tokens are staked in the incentives contract
+    let reward_tokens = robot.query_config().reward_tokens;
+    robot
+        .deposit(Uint128::new(1_000u128), None, Unwrap::Ok, &user)
+        .discover_reward_tokens(
+            Unwrap::Err("Caller is not the contract's current owner"),
+            &user,
+        )
+        .discover_reward_tokens(Unwrap::Ok, &admin);
+
+    // The pool has no incentives, so no reward tokens should have been proposed
+    assert_eq!(robot.query_config().reward_tokens, reward_tokens);
+    assert!(robot.query_config_update_proposals().is_empty());
+}
+
+#[test]
+fn discover_reward_tokens_proposes_routable_incentive_tokens() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let user = robot.new_user(&admin);
+
+    // Remove NTRN from the reward tokens so that it can be discovered
+    let ntrn = AssetInfo::native(NTRN_DENOM);
+    let mut reward_tokens = robot.query_config().reward_tokens;
+    reward_tokens.retain(|reward_token| reward_token != &ntrn);
+    robot.update_config_with_timelock(
+        ConfigUpdates {
+            reward_tokens: Some(reward_tokens.iter().cloned().map(Into::into).collect()),
+            ..Default::default()
+        },
+        &admin,
+    );
+
+    // Incentivize the pool with NTRN, which can be routed to the reward
+    // liquidation target, and USDC, which can not
+    robot
+        .deposit(Uint128::new(1_000u128), None, Unwrap::Ok, &user)
+        .incentivize(coin(1_000_000_000_000, NTRN_DENOM), 1, &admin)
+        .incentivize(coin(1_000_000_000_000, USDC_DENOM), 1, &admin)
+        .discover_reward_tokens(Unwrap::Ok, &admin);
+
+    // NTRN is proposed, but only added once the proposal is executed
+    let mut expected_reward_tokens = reward_tokens.clone();
+    expected_reward_tokens.push(ntrn);
+    let proposal = robot.query_config_update_proposals().pop().unwrap();
+    assert_eq!(
+        proposal.updates.reward_tokens,
+        Some(
+            expected_reward_tokens
+                .iter()
+                .cloned()
+                .map(Into::into)
+                .collect()
+        )
+    );
+    assert_eq!(robot.query_config().reward_tokens, reward_tokens);
+
+    robot
+        .execute_config_update(
+            proposal.id,
+            Unwrap::Err("can not be executed until"),
+            &admin,
+        )
+        .increase_time(ONE_DAY_IN_SECS)
+        .execute_config_update(proposal.id, Unwrap::Ok, &admin);
+    assert_eq!(robot.query_config().reward_tokens, expected_reward_tokens);
+}
+
+#[test]
+fn simulate_compound_estimates_fees_and_staked_base_tokens() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let user = robot.new_user(&admin);
+
+    let deposit_amount = Uint128::new(1_000_000u128);
+    robot.deposit(deposit_amount, None, Unwrap::Ok, &user);
+
+    // Nothing to compound yet
+    let simulation = robot.query_simulate_compound();
+    assert!(simulation.rewards.is_empty());
+    assert_eq!(simulation.base_tokens_to_stake, Uint128::zero());
+
+    // Donate some reward tokens to the vault
+    let config = robot.query_config();
+    let reward_token = &config.reward_tokens[0];
+    let donation_amount = Uint128::new(1_000_000u128);
+    robot.send_native_tokens(
+        &admin,
+        &robot.vault_addr,
+        donation_amount,
+        reward_token.to_string(),
+    );
+
+    let pending_rewards = robot.query_pending_rewards();
+    assert_eq!(
+        pending_rewards.in_contract,
+        vec![Asset::new(reward_token.clone(), donation_amount)]
+    );
+
+    let simulation = robot.query_simulate_compound();
+    assert_eq!(
+        simulation.performance_fees,
+        vec![Asset::new(
+            reward_token.clone(),
+            donation_amount * config.performance_fee.fee_rate
+        )]
+    );
+    assert!(simulation.liquidation_target_received > Uint128::zero());
+    assert!(simulation.base_tokens_to_stake > Uint128::zero());
+
+    // Compounding should stake roughly the simulated amount of base tokens
+    let total_assets_before = robot.query_total_vault_assets();
+    robot.compound_vault(&user);
+    let staked = robot.query_total_vault_assets() - total_assets_before;
+    assert!(staked > Uint128::zero());
+    assert!(staked.abs_diff(simulation.base_tokens_to_stake) <= staked / Uint128::new(100));
+}
+
+#[test]
+fn sweep_dust_sells_idle_balances_up_to_threshold() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let user = robot.new_user(&admin);
+
+    let deposit_amount = Uint128::new(1_000_000u128);
+    robot.deposit(deposit_amount, None, Unwrap::Ok, &user);
+
+    // Stop treating the last reward token as a reward token, so that a balance
+    // of it is stranded dust
+    let mut reward_tokens = robot.query_config().reward_tokens;
+    let dust_token = reward_tokens.pop().unwrap();
+    robot.update_config_with_timelock(
+        ConfigUpdates {
+            reward_tokens: Some(reward_tokens.into_iter().map(Into::into).collect()),
+            ..Default::default()
+        },
+        &admin,
+    );
+    let dust_amount = Uint128::new(1_000_000u128);
+    robot.send_native_tokens(
+        &admin,
+        &robot.vault_addr,
+        dust_amount,
+        dust_token.to_string(),
+    );
+    let dust = Asset::new(dust_token, dust_amount);
+    assert!(robot.query_idle_balances().contains(&dust));
+
+    // Only the owner can sweep dust, and balances above the threshold, which
+    // defaults to zero, should not be sold
+    robot
+        .sweep_dust(
+            Unwrap::Err("Caller is not the contract's current owner"),
+            &user,
+        )
+        .sweep_dust(Unwrap::Ok, &admin)
+        .update_config(
+            ConfigUpdates {
+                dust_threshold: Some(dust_amount - Uint128::one()),
+                ..Default::default()
+            },
+            Unwrap::Err("must be proposed"),
+            &admin,
+        )
+        .update_config_with_timelock(
+            ConfigUpdates {
+                dust_threshold: Some(dust_amount - Uint128::one()),
+                ..Default::default()
+            },
+            &admin,
+        )
+        .sweep_dust(Unwrap::Ok, &admin);
+    assert!(robot.query_idle_balances().contains(&dust));
+    robot.assert_total_vault_assets_eq(deposit_amount);
+
+    // Balances up to the threshold should be sold and compounded
+    robot
+        .update_config_with_timelock(
+            ConfigUpdates {
+                dust_threshold: Some(dust_amount),
+                ..Default::default()
+            },
+            &admin,
+        )
+        .sweep_dust(Unwrap::Ok, &admin);
+    assert!(!robot
+        .query_idle_balances()
+        .iter()
+        .any(|asset| asset.info == dust.info));
+    assert!(robot.query_total_vault_assets() > deposit_amount);
+}
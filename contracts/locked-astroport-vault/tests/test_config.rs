@@ -1,10 +1,11 @@
 use apollo_cw_asset::AssetInfoUnchecked;
 use common::{default_instantiate, get_test_runner, DEPS_PATH};
-use cosmwasm_std::Decimal;
+use cosmwasm_std::{Addr, Decimal};
 use cw_it::helpers::Unwrap;
+use cw_it::test_tube::Account;
 use cw_utils::Duration;
 use locked_astroport_vault::state::{ConfigUpdates, FeeConfig};
-use locked_astroport_vault_test_helpers::robot::LockedAstroportVaultRobot;
+use locked_astroport_vault_test_helpers::robot::{LockedAstroportVaultRobot, ONE_DAY_IN_SECS};
 
 pub mod common;
 
@@ -20,11 +21,11 @@ fn config_validation_fails_if_reward_liquidation_target_not_in_pool_assets() {
         reward_liquidation_target: Some(AssetInfoUnchecked::native("random_token")),
         ..Default::default()
     };
-    robot.update_config(updates, Unwrap::Err("is not in the pool assets"), &admin);
+    robot.propose_config_update(updates, Unwrap::Err("is not in the pool assets"), &admin);
 }
 
 #[test]
-fn config_validation_fails_if_lock_duration_is_in_blocks() {
+fn lock_duration_can_be_updated_to_blocks() {
     let owned_runner = get_test_runner();
     let runner = owned_runner.as_ref();
     let admin = LockedAstroportVaultRobot::new_admin(&runner);
@@ -35,11 +36,9 @@ fn config_validation_fails_if_lock_duration_is_in_blocks() {
         lock_duration: Some(Duration::Height(420)),
         ..Default::default()
     };
-    robot.update_config(
-        updates,
-        Unwrap::Err("lock_duration must be specified in seconds"),
-        &admin,
-    );
+    robot.update_config_with_timelock(updates, &admin);
+
+    assert_eq!(robot.query_config().lock_duration, Duration::Height(420));
 }
 
 #[test]
@@ -57,7 +56,7 @@ fn config_validation_fails_if_performance_fee_exceeds_100_percent() {
         }),
         ..Default::default()
     };
-    robot.update_config(
+    robot.propose_config_update(
         updates,
         Unwrap::Err("Fee rate can't be higher than 100%"),
         &admin,
@@ -76,9 +75,171 @@ fn config_validation_fails_if_route_not_found_in_cw_dex_router() {
         reward_tokens: Some(vec![AssetInfoUnchecked::native("random_token")]),
         ..Default::default()
     };
-    robot.update_config(
+    robot.propose_config_update(
         updates,
         Unwrap::Err("Could not read path in cw-dex-router for"),
         &admin,
     );
 }
+
+#[test]
+fn router_update_requires_timelock() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+
+    let config = robot.query_config();
+    let updates = ConfigUpdates {
+        router: Some(config.router.into()),
+        ..Default::default()
+    };
+
+    // Applying the update directly should fail. Proposing it and executing before
+    // the delay has passed should also fail. After the delay it should work.
+    robot
+        .update_config(
+            updates.clone(),
+            Unwrap::Err("Config update must be proposed"),
+            &admin,
+        )
+        .propose_config_update(updates, Unwrap::Ok, &admin)
+        .execute_config_update(0, Unwrap::Err("can not be executed until"), &admin)
+        .increase_time(ONE_DAY_IN_SECS)
+        .execute_config_update(0, Unwrap::Ok, &admin);
+
+    assert!(robot.query_config_update_proposals().is_empty());
+}
+
+#[test]
+fn fee_increase_requires_timelock_but_decrease_does_not() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, treasury) = default_instantiate(&runner, &admin, &dependencies);
+
+    let fee_config = |fee_rate: Decimal, recipient: &str| FeeConfig {
+        fee_rate,
+        fee_recipients: vec![(recipient.to_string(), Decimal::percent(100))],
+    };
+
+    // Default performance fee is 5% to the treasury
+    robot
+        .update_config(
+            ConfigUpdates {
+                performance_fee: Some(fee_config(Decimal::percent(6), &treasury.address())),
+                ..Default::default()
+            },
+            Unwrap::Err("Config update must be proposed"),
+            &admin,
+        )
+        .update_config(
+            ConfigUpdates {
+                performance_fee: Some(fee_config(Decimal::percent(1), &admin.address())),
+                ..Default::default()
+            },
+            Unwrap::Err("Config update must be proposed"),
+            &admin,
+        )
+        .update_config(
+            ConfigUpdates {
+                performance_fee: Some(fee_config(Decimal::percent(1), &treasury.address())),
+                ..Default::default()
+            },
+            Unwrap::Ok,
+            &admin,
+        );
+
+    let config = robot.query_config();
+    assert_eq!(config.performance_fee.fee_rate, Decimal::percent(1));
+    assert_eq!(
+        config.performance_fee.fee_recipients,
+        vec![(Addr::unchecked(treasury.address()), Decimal::percent(100))]
+    );
+}
+
+#[test]
+fn cancelled_config_update_can_not_be_executed() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+
+    let updates = ConfigUpdates {
+        lock_duration: Some(Duration::Time(0)),
+        ..Default::default()
+    };
+    robot.propose_config_update(updates.clone(), Unwrap::Ok, &admin);
+
+    let proposals = robot.query_config_update_proposals();
+    assert_eq!(proposals.len(), 1);
+    assert_eq!(proposals[0].updates, updates);
+
+    robot
+        .cancel_config_update(0, Unwrap::Ok, &admin)
+        .increase_time(ONE_DAY_IN_SECS)
+        .execute_config_update(0, Unwrap::Err("not found"), &admin);
+
+    assert!(robot.query_config_update_proposals().is_empty());
+    assert_ne!(robot.query_config().lock_duration, Duration::Time(0));
+}
+
+#[test]
+fn validate_config_update_returns_all_failures() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+
+    let updates = ConfigUpdates {
+        deposit_fee: Some(FeeConfig {
+            fee_rate: Decimal::percent(101),
+            fee_recipients: vec![],
+        }),
+        reward_liquidation_target: Some(AssetInfoUnchecked::native("random_token")),
+        performance_fee: Some(FeeConfig {
+            fee_rate: Decimal::percent(101),
+            fee_recipients: vec![],
+        }),
+        ..Default::default()
+    };
+    let res = robot.query_validate_config_update(updates);
+
+    assert!(res.config.is_none());
+    let fields: Vec<&str> = res.errors.iter().map(|e| e.field.as_str()).collect();
+    assert!(fields.contains(&"deposit_fee"));
+    assert!(fields.contains(&"reward_liquidation_target"));
+    assert!(fields.contains(&"performance_fee"));
+    assert!(res
+        .errors
+        .iter()
+        .any(|e| e.error.contains("is not in the pool assets")));
+}
+
+#[test]
+fn validate_config_update_returns_resulting_config() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+
+    let config = robot.query_config();
+    let updates = ConfigUpdates {
+        lock_duration: Some(Duration::Time(0)),
+        ..Default::default()
+    };
+    let res = robot.query_validate_config_update(updates);
+
+    assert!(res.errors.is_empty());
+    let new_config = res.config.unwrap();
+    assert_eq!(new_config.lock_duration, Duration::Time(0));
+    assert_eq!(new_config.reward_tokens, config.reward_tokens);
+
+    // The config should not have been stored
+    assert_eq!(robot.query_config(), config);
+}
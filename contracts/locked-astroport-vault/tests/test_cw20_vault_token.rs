@@ -0,0 +1,159 @@
+use common::{
+    default_instantiate, get_test_runner, instantiate_msg_like, instantiate_with_msg, DEPS_PATH,
+};
+use cosmwasm_std::{to_json_binary, Uint128};
+use cw20::Cw20ReceiveMsg;
+use cw_it::helpers::Unwrap;
+use cw_it::robot::TestRobot;
+use cw_it::test_tube::{Account, SigningAccount};
+use cw_it::TestRunner;
+use cw_vault_standard_test_helpers::traits::lockup::LockedVaultRobot;
+use cw_vault_standard_test_helpers::traits::CwVaultStandardRobot;
+use locked_astroport_vault::helpers::INITIAL_VAULT_TOKENS_PER_BASE_TOKEN;
+use locked_astroport_vault::msg::{ExecuteMsg, InstantiateMsg, ReceiveMsg, VaultTokenBackend};
+use locked_astroport_vault::state::{VaultToken, VaultTokenMetadata};
+use locked_astroport_vault_test_helpers::robot::{
+    LockedAstroportVaultRobot, LockedVaultDependencies,
+};
+
+pub mod common;
+
+/// Instantiates a vault with a cw20 vault token for the same pool and with
+/// the same config as `robot`. The symbol is set explicitly, since the default
+/// symbol for the test pool is longer than cw20 symbols may be.
+fn instantiate_cw20_vault<'a>(
+    runner: &'a TestRunner<'a>,
+    robot: &LockedAstroportVaultRobot<'a>,
+    dependencies: &'a LockedVaultDependencies<'a>,
+    admin: &SigningAccount,
+) -> LockedAstroportVaultRobot<'a> {
+    let instantiate_msg = InstantiateMsg {
+        vault_token_backend: Some(VaultTokenBackend::Cw20 {
+            code_id: dependencies.astroport_contracts.astro_cw20_token.code_id,
+        }),
+        vault_token_metadata: Some(VaultTokenMetadata {
+            symbol: Some("apWSTETH-ETH".to_string()),
+            ..Default::default()
+        }),
+        ..instantiate_msg_like(robot, dependencies, admin)
+    };
+    instantiate_with_msg(runner, &instantiate_msg, dependencies, admin)
+}
+
+#[test]
+fn cw20_vault_token_can_be_minted_unlocked_and_redeemed() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let robot = instantiate_cw20_vault(&runner, &robot, &dependencies, &admin);
+    let user = robot.new_user(&admin);
+
+    // The vault token should be a cw20 token with the set symbol and the
+    // decimals derived from the base token
+    let vault_token = match robot.query_state().vault_token {
+        VaultToken::Cw20 { addr } => addr,
+        VaultToken::TokenFactory { .. } => panic!("Expected a cw20 vault token"),
+    };
+    let token_info: cw20::TokenInfoResponse = robot
+        .wasm()
+        .query(vault_token.as_str(), &cw20::Cw20QueryMsg::TokenInfo {})
+        .unwrap();
+    assert_eq!(token_info.symbol, "apWSTETH-ETH");
+    assert_eq!(token_info.decimals, 12);
+
+    // Deposit should mint cw20 vault tokens to the user
+    let deposit_amount = Uint128::new(1_000_000);
+    let vault_token_amount = deposit_amount * INITIAL_VAULT_TOKENS_PER_BASE_TOKEN;
+    robot.deposit(deposit_amount, None, Unwrap::Ok, &user);
+    assert_eq!(
+        robot.query_cw20_vault_token_balance(user.address()),
+        vault_token_amount
+    );
+
+    // Unlock half of the vault tokens through the cw20 receive hook and
+    // withdraw them once unlocked
+    let unlock_amount = vault_token_amount / Uint128::new(2);
+    robot
+        .send_cw20_vault_tokens(unlock_amount, ReceiveMsg::Unlock {}, Unwrap::Ok, &user)
+        .assert_unlocking_position_has_props(0, &user.address(), deposit_amount / Uint128::new(2))
+        .increase_time_by_lockup_duration()
+        .withdraw_unlocked(0, None, Unwrap::Ok, &user)
+        .assert_base_token_balance_eq(user.address(), deposit_amount / Uint128::new(2));
+
+    // Redeem the rest. Since the vault is locked, this creates a claim.
+    robot.send_cw20_vault_tokens(
+        unlock_amount,
+        ReceiveMsg::Redeem { recipient: None },
+        Unwrap::Ok,
+        &user,
+    );
+    assert_eq!(
+        robot.query_cw20_vault_token_balance(user.address()),
+        Uint128::zero()
+    );
+    assert_eq!(robot.query_total_vault_token_supply(), Uint128::zero());
+    assert_eq!(robot.query_unlocking_positions(&user.address()).len(), 1);
+}
+
+#[test]
+fn only_accepted_cw20_tokens_can_call_receive() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let robot = instantiate_cw20_vault(&runner, &robot, &dependencies, &admin);
+    let user = robot.new_user(&admin);
+
+    robot.deposit(Uint128::new(1_000_000), None, Unwrap::Ok, &user);
+
+    // Calling the receive hook directly should fail, since the tokens were not
+    // actually sent
+    let err = robot
+        .wasm()
+        .execute(
+            &robot.vault_addr,
+            &ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: user.address(),
+                amount: Uint128::new(1_000_000),
+                msg: to_json_binary(&ReceiveMsg::Unlock {}).unwrap(),
+            }),
+            &[],
+            &user,
+        )
+        .unwrap_err();
+    assert!(err.to_string().contains("Unauthorized"));
+
+    // Deposits must be made with the base token, not the vault token
+    robot.send_cw20_vault_tokens(
+        Uint128::new(1_000_000),
+        ReceiveMsg::Deposit { recipient: None },
+        Unwrap::Err("Invalid cw20 receive message"),
+        &user,
+    );
+
+    // Donations must also be made with the base token
+    robot.send_cw20_vault_tokens(
+        Uint128::new(1_000_000),
+        ReceiveMsg::Donate {},
+        Unwrap::Err("Invalid cw20 receive message"),
+        &user,
+    );
+
+    // Force redeeming through the hook requires the sender to be whitelisted
+    robot.send_cw20_vault_tokens(
+        Uint128::new(1_000_000),
+        ReceiveMsg::ForceRedeem { recipient: None },
+        Unwrap::Err("Unauthorized"),
+        &user,
+    );
+
+    // The metadata of cw20 vault tokens can not be changed
+    robot.set_vault_token_metadata(
+        VaultTokenMetadata::default(),
+        Unwrap::Err("The metadata of cw20 vault tokens can not be changed"),
+        &admin,
+    );
+}
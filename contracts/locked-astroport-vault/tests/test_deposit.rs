@@ -168,12 +168,11 @@ fn deposit_fee_works_with_vault_as_recipient() {
         fee_rate,
         fee_recipients: vec![(robot.vault_addr.clone(), Decimal::percent(100))],
     });
-    robot.update_config(
+    robot.update_config_with_timelock(
         ConfigUpdates {
             deposit_fee,
             ..Default::default()
         },
-        Unwrap::Ok,
         &admin,
     );
 
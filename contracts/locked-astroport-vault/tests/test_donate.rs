@@ -0,0 +1,147 @@
+use common::{default_instantiate, get_test_runner, DEPS_PATH};
+use cosmwasm_std::{Decimal, Uint128};
+use cw_it::helpers::Unwrap;
+use cw_it::robot::TestRobot;
+use cw_it::test_tube::Account;
+use cw_utils::Duration;
+use cw_vault_standard_test_helpers::traits::CwVaultStandardRobot;
+use locked_astroport_vault::helpers::INITIAL_VAULT_TOKENS_PER_BASE_TOKEN;
+use locked_astroport_vault::state::{ConfigUpdates, Donations, LockTier};
+use locked_astroport_vault_test_helpers::robot::LockedAstroportVaultRobot;
+
+pub mod common;
+
+#[test]
+fn donate_adds_to_vault_assets_without_minting() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let user = robot.new_user(&admin);
+    let donor = robot.new_user(&admin);
+
+    let deposit_amount = Uint128::new(1_000_000);
+    let donation_amount = Uint128::new(500_000);
+    robot
+        .deposit(deposit_amount, None, Unwrap::Ok, &user)
+        .donate(donation_amount, Unwrap::Ok, &donor)
+        .assert_total_vault_token_supply_eq(deposit_amount * INITIAL_VAULT_TOKENS_PER_BASE_TOKEN)
+        .assert_total_vault_assets_eq(deposit_amount + donation_amount)
+        .assert_vault_token_balance_eq(donor.address(), 0u128)
+        .assert_base_token_balance_eq(robot.vault_addr(), 0u128);
+
+    assert_eq!(
+        robot.query_donations(),
+        Donations {
+            explicit: donation_amount,
+            implicit: Uint128::zero(),
+        }
+    );
+}
+
+#[test]
+fn donations_are_distributed_to_lock_tiers() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let user = robot.new_user(&admin);
+    let locker = robot.new_user(&admin);
+    let donor = robot.new_user(&admin);
+
+    robot.update_config_with_timelock(
+        ConfigUpdates {
+            lock_tiers: Some(vec![LockTier {
+                lock_duration: Duration::Time(30 * 24 * 60 * 60),
+                weight: Decimal::one(),
+            }]),
+            ..Default::default()
+        },
+        &admin,
+    );
+
+    // The vault token and the lock tier have equal weights and stakes, so the
+    // donation is split evenly between them
+    let deposit_amount = Uint128::new(1_000_000);
+    let donation_amount = Uint128::new(500_000);
+    robot
+        .deposit(deposit_amount, None, Unwrap::Ok, &user)
+        .deposit_to_lock_tier(0, deposit_amount, None, Unwrap::Ok, &locker)
+        .donate(donation_amount, Unwrap::Ok, &donor)
+        .assert_total_vault_assets_eq(deposit_amount + Uint128::new(250_000));
+
+    let lock_tiers = robot.query_lock_tiers();
+    assert_eq!(
+        lock_tiers[0].staked_base_tokens,
+        deposit_amount + Uint128::new(250_000)
+    );
+}
+
+#[test]
+fn base_tokens_sent_directly_are_implicit_donations() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let user = robot.new_user(&admin);
+
+    let deposit_amount = Uint128::new(1_000_000);
+    let donation_amount = Uint128::new(500_000);
+    robot.deposit(deposit_amount, None, Unwrap::Ok, &user);
+    robot.send_native_tokens(
+        &admin,
+        &robot.vault_addr,
+        donation_amount,
+        robot.base_token(),
+    );
+    robot
+        .compound_vault(&admin)
+        .assert_total_vault_assets_eq(deposit_amount + donation_amount);
+
+    assert_eq!(
+        robot.query_donations(),
+        Donations {
+            explicit: Uint128::zero(),
+            implicit: donation_amount,
+        }
+    );
+}
+
+#[test]
+fn implicit_donations_can_be_rejected() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let user = robot.new_user(&admin);
+
+    robot.update_config(
+        ConfigUpdates {
+            reject_implicit_donations: Some(true),
+            ..Default::default()
+        },
+        Unwrap::Ok,
+        &admin,
+    );
+
+    // Base tokens sent directly to the vault should stay unstaked, while
+    // deposits are still staked
+    let deposit_amount = Uint128::new(1_000_000);
+    let donation_amount = Uint128::new(500_000);
+    robot.send_native_tokens(
+        &admin,
+        &robot.vault_addr,
+        donation_amount,
+        robot.base_token(),
+    );
+    robot
+        .deposit(deposit_amount, None, Unwrap::Ok, &user)
+        .compound_vault(&admin)
+        .assert_total_vault_assets_eq(deposit_amount)
+        .assert_base_token_balance_eq(robot.vault_addr(), donation_amount);
+    assert_eq!(robot.query_donations(), Donations::default());
+}
@@ -0,0 +1,172 @@
+use common::{default_instantiate, get_test_runner, DEPS_PATH};
+use cosmwasm_std::{Decimal, Uint128};
+use cw_it::helpers::Unwrap;
+use cw_it::test_tube::Account;
+use cw_utils::Duration;
+use cw_vault_standard_test_helpers::traits::lockup::LockedVaultRobot;
+use cw_vault_standard_test_helpers::traits::CwVaultStandardRobot;
+use locked_astroport_vault::helpers::INITIAL_VAULT_TOKENS_PER_BASE_TOKEN;
+use locked_astroport_vault::state::{ConfigUpdates, EmergencyUnlockPenalty, FeeConfig, LockTier};
+use locked_astroport_vault_test_helpers::robot::LockedAstroportVaultRobot;
+
+pub mod common;
+
+#[test]
+fn emergency_unlock_is_disabled_by_default() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let user = robot.new_user(&admin);
+
+    let deposit_amount = Uint128::new(1_000_000);
+    let vault_tokens = deposit_amount * INITIAL_VAULT_TOKENS_PER_BASE_TOKEN;
+    robot
+        .deposit(deposit_amount, None, Unwrap::Ok, &user)
+        .emergency_unlock(
+            vault_tokens / Uint128::new(2),
+            Unwrap::Err("Emergency unlocks are disabled"),
+            &user,
+        )
+        .unlock(vault_tokens / Uint128::new(2), Unwrap::Ok, &user)
+        .emergency_withdraw_unlocking(
+            0,
+            None,
+            Unwrap::Err("Emergency unlocks are disabled"),
+            &user,
+        );
+}
+
+#[test]
+fn emergency_unlock_leaves_penalty_staked_for_holders() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let user = robot.new_user(&admin);
+    let holder = robot.new_user(&admin);
+
+    // Enabling emergency unlocks requires the timelock
+    let updates = ConfigUpdates {
+        emergency_unlock_penalty: Some(EmergencyUnlockPenalty::ToHolders {
+            penalty_rate: Decimal::percent(10),
+        }),
+        ..Default::default()
+    };
+    robot
+        .update_config(
+            updates.clone(),
+            Unwrap::Err("Config update must be proposed"),
+            &admin,
+        )
+        .update_config_with_timelock(updates, &admin);
+
+    // The user exits immediately and leaves 10% of their base tokens in the
+    // vault
+    let deposit_amount = Uint128::new(1_000_000);
+    let penalty = Uint128::new(100_000);
+    let base_token_balance = robot.query_base_token_balance(user.address());
+    robot
+        .deposit(deposit_amount, None, Unwrap::Ok, &user)
+        .deposit(deposit_amount, None, Unwrap::Ok, &holder)
+        .emergency_unlock(
+            deposit_amount * INITIAL_VAULT_TOKENS_PER_BASE_TOKEN,
+            Unwrap::Ok,
+            &user,
+        )
+        .assert_base_token_balance_eq(user.address(), base_token_balance - penalty)
+        .assert_vault_token_balance_eq(user.address(), 0u128)
+        .assert_total_vault_assets_eq(deposit_amount + penalty);
+
+    assert!(robot.query_unlocking_positions(&user.address()).is_empty());
+}
+
+#[test]
+fn emergency_unlock_penalty_is_shared_with_lock_tiers() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let user = robot.new_user(&admin);
+    let holder = robot.new_user(&admin);
+    let locker = robot.new_user(&admin);
+
+    robot.update_config_with_timelock(
+        ConfigUpdates {
+            emergency_unlock_penalty: Some(EmergencyUnlockPenalty::ToHolders {
+                penalty_rate: Decimal::percent(10),
+            }),
+            lock_tiers: Some(vec![LockTier {
+                lock_duration: Duration::Time(30 * 24 * 60 * 60),
+                weight: Decimal::one(),
+            }]),
+            ..Default::default()
+        },
+        &admin,
+    );
+
+    // The penalty is split evenly between the vault token and the lock tier,
+    // since they have the same amount of base tokens and weight
+    let deposit_amount = Uint128::new(1_000_000);
+    robot
+        .deposit(deposit_amount, None, Unwrap::Ok, &user)
+        .deposit(deposit_amount, None, Unwrap::Ok, &holder)
+        .deposit_to_lock_tier(0, deposit_amount, None, Unwrap::Ok, &locker)
+        .emergency_unlock(
+            deposit_amount * INITIAL_VAULT_TOKENS_PER_BASE_TOKEN,
+            Unwrap::Ok,
+            &user,
+        )
+        .assert_total_vault_assets_eq(deposit_amount + Uint128::new(50_000));
+    assert_eq!(
+        robot.query_lock_tiers()[0].staked_base_tokens,
+        deposit_amount + Uint128::new(50_000)
+    );
+}
+
+#[test]
+fn emergency_withdraw_unlocking_sends_penalty_to_fee_recipients() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let user = robot.new_user(&admin);
+    let recipient = robot.new_user(&admin);
+
+    robot.update_config_with_timelock(
+        ConfigUpdates {
+            emergency_unlock_penalty: Some(EmergencyUnlockPenalty::ToFeeRecipients(FeeConfig {
+                fee_rate: Decimal::percent(10),
+                fee_recipients: vec![(treasury.address(), Decimal::percent(100))],
+            })),
+            ..Default::default()
+        },
+        &admin,
+    );
+
+    // Unlock, then withdraw the unlocking position before it has matured
+    let deposit_amount = Uint128::new(1_000_000);
+    let treasury_balance = robot.query_base_token_balance(treasury.address());
+    let recipient_balance = robot.query_base_token_balance(recipient.address());
+    robot
+        .deposit(deposit_amount, None, Unwrap::Ok, &user)
+        .unlock(
+            deposit_amount * INITIAL_VAULT_TOKENS_PER_BASE_TOKEN,
+            Unwrap::Ok,
+            &user,
+        )
+        .emergency_withdraw_unlocking(0, None, Unwrap::Err("Claim not owned by sender"), &admin)
+        .emergency_withdraw_unlocking(0, Some(recipient.address()), Unwrap::Ok, &user)
+        .assert_base_token_balance_eq(
+            recipient.address(),
+            recipient_balance + Uint128::new(900_000),
+        )
+        .assert_base_token_balance_eq(treasury.address(), treasury_balance + Uint128::new(100_000))
+        .assert_total_vault_assets_eq(0u128);
+
+    assert!(robot.query_unlocking_positions(&user.address()).is_empty());
+}
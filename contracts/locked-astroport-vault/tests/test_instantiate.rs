@@ -1,17 +1,27 @@
 use std::str::FromStr;
 
-use cosmwasm_std::{Addr, Coins};
+use cosmwasm_std::{Addr, Coin, Coins, Uint128};
 use cw_dex_astroport::AstroportStaking;
+use cw_it::helpers::Unwrap;
+use cw_it::test_tube::{Account, Module, Wasm};
 use cw_it::traits::CwItRunner;
 use cw_ownable::Ownership;
 use cw_vault_standard::VaultStandardInfoResponse;
-use locked_astroport_vault::state::StateResponse;
-use locked_astroport_vault_test_helpers::robot::{LockedAstroportVaultRobot, DEFAULT_COINS};
+use cw_vault_standard_test_helpers::traits::lockup::LockedVaultRobot;
+use cw_vault_standard_test_helpers::traits::CwVaultStandardRobot;
+use locked_astroport_vault::helpers::INITIAL_VAULT_TOKENS_PER_BASE_TOKEN;
+use locked_astroport_vault::msg::{InstantiateMsg, VaultTokenBackend};
+use locked_astroport_vault::state::{StakingMode, StateResponse, TokenFactoryFlavor, VaultToken};
+use locked_astroport_vault_test_helpers::robot::{
+    LockedAstroportVaultRobot, DEFAULT_COINS, DENOM_CREATION_FEE,
+};
 
 pub mod common;
 pub use common::get_test_runner;
 
-use crate::common::{default_instantiate, DEPS_PATH};
+use crate::common::{
+    default_instantiate, instantiate_msg_like, instantiate_with_msg, DEPS_PATH, UNOPTIMIZED_PATH,
+};
 
 #[test]
 fn test_instantiation() {
@@ -69,13 +79,81 @@ fn test_instantiation() {
             pool: base_pool.clone(),
             staked_base_tokens: 0u128.into(),
             vault_token_supply: 0u128.into(),
-            staking: AstroportStaking {
+            staking: StakingMode::Astroport(AstroportStaking {
                 lp_token: base_pool.lp_token,
                 incentives: Addr::unchecked(
                     dependencies.astroport_contracts.incentives.address.clone()
                 )
+            }),
+            vault_token: VaultToken::TokenFactory {
+                denom: format!("factory/{}/testVaultToken", robot.vault_addr),
+                flavor: TokenFactoryFlavor::Osmosis,
             },
-            vault_token_denom: format!("factory/{}/testVaultToken", robot.vault_addr),
+            unlocking_position_nft: None,
+            lock_duration_reduction: None,
+            claims_rescale: None,
+            shutdown: None,
         }
     )
 }
+
+#[test]
+fn token_factory_flavor_selects_message_encoding() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+
+    let instantiate_msg = |flavor| InstantiateMsg {
+        vault_token_backend: Some(VaultTokenBackend::TokenFactory {
+            flavor: Some(flavor),
+        }),
+        ..instantiate_msg_like(&robot, &dependencies, &admin)
+    };
+
+    // The Osmosis flavor is understood by the registered token factory module,
+    // so vault tokens can be created, minted and burned
+    let osmosis_robot = instantiate_with_msg(
+        &runner,
+        &instantiate_msg(TokenFactoryFlavor::Osmosis),
+        &dependencies,
+        &admin,
+    );
+    assert_eq!(
+        osmosis_robot.query_state().vault_token,
+        VaultToken::TokenFactory {
+            denom: format!("factory/{}/testVaultToken", osmosis_robot.vault_addr),
+            flavor: TokenFactoryFlavor::Osmosis,
+        }
+    );
+    let user = osmosis_robot.new_user(&admin);
+    let deposit_amount = Uint128::new(1_000_000);
+    osmosis_robot
+        .deposit(deposit_amount, None, Unwrap::Ok, &user)
+        .unlock(
+            deposit_amount * INITIAL_VAULT_TOKENS_PER_BASE_TOKEN,
+            Unwrap::Ok,
+            &user,
+        )
+        .assert_vault_token_balance_eq(user.address(), 0u128);
+
+    // The Injective flavor uses other type URLs, which the Osmosis token factory
+    // module does not handle
+    let code_id = runner
+        .store_code(
+            LockedAstroportVaultRobot::contract(&runner, UNOPTIMIZED_PATH),
+            &admin,
+        )
+        .unwrap();
+    Wasm::new(&runner)
+        .instantiate(
+            code_id,
+            &instantiate_msg(TokenFactoryFlavor::Injective),
+            Some(&admin.address()),
+            Some("Locked Astroport Vault"),
+            &[Coin::from_str(DENOM_CREATION_FEE).unwrap()],
+            &admin,
+        )
+        .unwrap_err();
+}
@@ -0,0 +1,220 @@
+use common::{default_instantiate, get_test_runner, DEPS_PATH};
+use cosmwasm_std::{Decimal, Uint128};
+use cw_it::helpers::Unwrap;
+use cw_it::robot::TestRobot;
+use cw_it::test_tube::Account;
+use cw_utils::Duration;
+use cw_vault_standard_test_helpers::traits::lockup::LockedVaultRobot;
+use cw_vault_standard_test_helpers::traits::CwVaultStandardRobot;
+use locked_astroport_vault::helpers::INITIAL_VAULT_TOKENS_PER_BASE_TOKEN;
+use locked_astroport_vault::state::{ConfigUpdates, LockTier};
+use locked_astroport_vault_test_helpers::robot::LockedAstroportVaultRobot;
+
+pub mod common;
+
+const TIER_LOCK_DURATION: u64 = 30 * 24 * 60 * 60;
+
+#[test]
+fn lock_tiers_receive_weighted_share_of_compounded_base_tokens() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let user = robot.new_user(&admin);
+    let locker = robot.new_user(&admin);
+
+    // Adding lock tiers requires the timelock
+    let updates = ConfigUpdates {
+        lock_tiers: Some(vec![LockTier {
+            lock_duration: Duration::Time(TIER_LOCK_DURATION),
+            weight: Decimal::percent(200),
+        }]),
+        ..Default::default()
+    };
+    robot
+        .update_config(
+            updates.clone(),
+            Unwrap::Err("Config update must be proposed"),
+            &admin,
+        )
+        .update_config_with_timelock(updates, &admin);
+
+    let deposit_amount = Uint128::new(1_000_000);
+    robot
+        .deposit(deposit_amount, None, Unwrap::Ok, &user)
+        .deposit_to_lock_tier(
+            1,
+            deposit_amount,
+            None,
+            Unwrap::Err("Lock tier 1 does not exist"),
+            &locker,
+        )
+        .deposit_to_lock_tier(0, deposit_amount, None, Unwrap::Ok, &locker)
+        .assert_vault_token_balance_eq(locker.address(), 0u128);
+
+    // Compounded base tokens are split 1:2 between the vault token and the
+    // lock tier, since the lock tier has twice the weight
+    robot.send_native_tokens(
+        &admin,
+        &robot.vault_addr,
+        Uint128::new(300_000),
+        robot.base_token(),
+    );
+    robot
+        .compound_vault(&admin)
+        .assert_total_vault_assets_eq(deposit_amount + Uint128::new(100_000));
+
+    let lock_tiers = robot.query_lock_tiers();
+    assert_eq!(lock_tiers.len(), 1);
+    assert_eq!(
+        lock_tiers[0].staked_base_tokens,
+        deposit_amount + Uint128::new(200_000)
+    );
+    let positions = robot.query_lock_tier_positions(locker.address());
+    assert_eq!(positions.len(), 1);
+    assert_eq!(
+        positions[0].shares,
+        deposit_amount * INITIAL_VAULT_TOKENS_PER_BASE_TOKEN
+    );
+    assert_eq!(
+        positions[0].base_token_value,
+        deposit_amount + Uint128::new(200_000)
+    );
+    assert_eq!(
+        robot
+            .query_user_position(locker.address())
+            .lock_tier_positions,
+        positions
+    );
+
+    // Unlocked shares are locked for the lock duration of the tier
+    let base_token_balance = robot.query_base_token_balance(locker.address());
+    robot
+        .unlock_from_lock_tier(
+            0,
+            positions[0].shares + Uint128::one(),
+            Unwrap::Err("Cannot Sub"),
+            &locker,
+        )
+        .unlock_from_lock_tier(0, positions[0].shares, Unwrap::Ok, &locker)
+        .increase_time(TIER_LOCK_DURATION - 1)
+        .withdraw_unlocked(0, None, Unwrap::Err("Claim has not yet matured"), &locker)
+        .increase_time(1)
+        .withdraw_unlocked(0, None, Unwrap::Ok, &locker)
+        .assert_base_token_balance_eq(
+            locker.address(),
+            base_token_balance + deposit_amount + Uint128::new(200_000),
+        );
+
+    assert!(robot.query_lock_tier_positions(locker.address()).is_empty());
+}
+
+#[test]
+fn lock_tiers_can_not_be_removed() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+
+    let lock_tier = LockTier {
+        lock_duration: Duration::Time(TIER_LOCK_DURATION),
+        weight: Decimal::percent(150),
+    };
+    robot
+        .propose_config_update(
+            ConfigUpdates {
+                lock_tiers: Some(vec![LockTier {
+                    weight: Decimal::zero(),
+                    ..lock_tier.clone()
+                }]),
+                ..Default::default()
+            },
+            Unwrap::Err("Lock tier weight must be greater than zero"),
+            &admin,
+        )
+        .update_config_with_timelock(
+            ConfigUpdates {
+                lock_tiers: Some(vec![lock_tier]),
+                ..Default::default()
+            },
+            &admin,
+        )
+        .propose_config_update(
+            ConfigUpdates {
+                lock_tiers: Some(vec![]),
+                ..Default::default()
+            },
+            Unwrap::Err("Lock tiers can not be removed"),
+            &admin,
+        );
+}
+
+#[test]
+fn lock_tier_unlocking_positions_keep_their_tier() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let locker = robot.new_user(&admin);
+
+    robot.update_config_with_timelock(
+        ConfigUpdates {
+            lock_tiers: Some(vec![LockTier {
+                lock_duration: Duration::Time(TIER_LOCK_DURATION),
+                weight: Decimal::one(),
+            }]),
+            ..Default::default()
+        },
+        &admin,
+    );
+
+    let deposit_amount = Uint128::new(1_000_000);
+    let shares = deposit_amount * INITIAL_VAULT_TOKENS_PER_BASE_TOKEN;
+    robot
+        .deposit_to_lock_tier(0, deposit_amount, None, Unwrap::Ok, &locker)
+        .unlock_from_lock_tier(0, shares, Unwrap::Ok, &locker);
+    assert_eq!(
+        robot
+            .query_user_position(locker.address())
+            .lock_tier_claims_base_tokens,
+        vec![(0, deposit_amount)]
+    );
+
+    // Shortening the lock duration of the vault token does not shorten the
+    // position, since it has the lock duration of the tier
+    let release_at = robot.query_all_unlocking_positions(None, None)[0].release_at;
+    robot
+        .update_config_and_shorten_claims(
+            ConfigUpdates {
+                lock_duration: Some(Duration::Time(1)),
+                ..Default::default()
+            },
+            Unwrap::Ok,
+            &admin,
+        )
+        .apply_lock_duration_reduction(None, Unwrap::Ok, &admin);
+    assert_eq!(
+        robot.query_all_unlocking_positions(None, None)[0].release_at,
+        release_at
+    );
+
+    // Cancelling the position mints shares of the tier rather than vault tokens
+    robot
+        .cancel_unlock(
+            0,
+            Some(deposit_amount / Uint128::new(2)),
+            Unwrap::Ok,
+            &locker,
+        )
+        .assert_vault_token_balance_eq(locker.address(), 0u128);
+    let positions = robot.query_lock_tier_positions(locker.address());
+    assert_eq!(positions.len(), 1);
+    assert_eq!(positions[0].shares, shares / Uint128::new(2));
+    assert_eq!(
+        positions[0].base_token_value,
+        deposit_amount / Uint128::new(2)
+    );
+}
@@ -1,16 +1,24 @@
-use common::{default_instantiate, get_test_runner, DEPS_PATH};
-use cosmwasm_std::{attr, coin, Uint128};
+use common::{
+    default_instantiate, get_test_runner, instantiate_msg_like, instantiate_with_msg, DEPS_PATH,
+};
+use cosmwasm_std::{attr, coin, Timestamp, Uint128};
 use cw_it::helpers::Unwrap;
 use cw_it::robot::TestRobot;
-use cw_it::test_tube::Account;
+use cw_it::test_tube::{Account, Module, Wasm};
+use cw_utils::{Duration, Expiration};
 use cw_vault_standard::extensions::lockup::{
-    UNLOCKING_POSITION_ATTR_KEY, UNLOCKING_POSITION_CREATED_EVENT_TYPE,
+    UnlockingPosition, UNLOCKING_POSITION_ATTR_KEY, UNLOCKING_POSITION_CREATED_EVENT_TYPE,
 };
 use cw_vault_standard_test_helpers::traits::lockup::LockedVaultRobot;
 use cw_vault_standard_test_helpers::traits::CwVaultStandardRobot;
 use locked_astroport_vault::helpers::INITIAL_VAULT_TOKENS_PER_BASE_TOKEN;
-use locked_astroport_vault::msg::ExecuteMsg;
-use locked_astroport_vault_test_helpers::robot::LockedAstroportVaultRobot;
+use locked_astroport_vault::msg::{
+    ApolloExtensionQueryMsg, ExecuteMsg, ExtensionQueryMsg, InstantiateMsg, QueryMsg,
+};
+use locked_astroport_vault::state::ConfigUpdates;
+use locked_astroport_vault_test_helpers::robot::{
+    LockedAstroportVaultRobot, ONE_DAY_IN_SECS, TWO_WEEKS_IN_SECS,
+};
 
 pub mod common;
 
@@ -81,3 +89,433 @@ fn unlocking_position_event_emitted_when_vault_has_lockup() {
         })
         .unwrap();
 }
+
+#[test]
+fn user_position_summarizes_vault_tokens_and_claims() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let user = robot.new_user(&admin);
+
+    // Deposit and unlock a quarter of the vault tokens twice, with the first
+    // claim maturing before the second one is created
+    let deposit_amount = Uint128::new(1_000_000);
+    let quarter = deposit_amount * INITIAL_VAULT_TOKENS_PER_BASE_TOKEN / Uint128::new(4);
+    robot
+        .deposit(deposit_amount, None, Unwrap::Ok, &user)
+        .unlock(quarter, Unwrap::Ok, &user)
+        .increase_time_by_lockup_duration()
+        .unlock(quarter, Unwrap::Ok, &user);
+
+    let position = robot.query_user_position(user.address());
+    assert_eq!(position.vault_token_balance, quarter * Uint128::new(2));
+    assert_eq!(position.base_token_value, deposit_amount / Uint128::new(2));
+    assert_eq!(position.pool_assets_value.len(), 2);
+    assert!(position
+        .pool_assets_value
+        .iter()
+        .all(|asset| !asset.amount.is_zero()));
+    assert!(position.lock_tier_positions.is_empty());
+    assert!(!position.claims_truncated);
+    assert_eq!(position.matured_claims, 1);
+    assert_eq!(
+        position.matured_base_tokens,
+        deposit_amount / Uint128::new(4)
+    );
+    assert_eq!(position.unlocking_claims, 1);
+    assert_eq!(
+        position.unlocking_base_tokens,
+        deposit_amount / Uint128::new(4)
+    );
+    assert_eq!(
+        position.next_release_at,
+        Some(robot.query_unlocking_positions(&user.address())[1].release_at)
+    );
+
+    // Users without a position have an empty summary
+    let other_user = robot.new_user(&admin);
+    let position = robot.query_user_position(other_user.address());
+    assert_eq!(position.vault_token_balance, Uint128::zero());
+    assert_eq!(position.unlocking_claims, 0);
+    assert_eq!(position.next_release_at, None);
+}
+
+#[test]
+fn withdraw_all_unlocked_withdraws_all_matured_positions() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let user = robot.new_user(&admin);
+
+    // Create three matured positions and one that is still unlocking
+    let deposit_amount = Uint128::new(1_000_000);
+    let quarter = deposit_amount * INITIAL_VAULT_TOKENS_PER_BASE_TOKEN / Uint128::new(4);
+    robot
+        .deposit(deposit_amount, None, Unwrap::Ok, &user)
+        .withdraw_all_unlocked(None, None, Unwrap::Err("No matured claims"), &user)
+        .unlock(quarter, Unwrap::Ok, &user)
+        .unlock(quarter, Unwrap::Ok, &user)
+        .unlock(quarter, Unwrap::Ok, &user)
+        .increase_time_by_lockup_duration()
+        .unlock(quarter, Unwrap::Ok, &user);
+
+    // Withdraw only the first two positions, then the rest
+    let recipient = robot.new_user(&admin);
+    let recipient_balance = robot.query_base_token_balance(recipient.address());
+    robot
+        .withdraw_all_unlocked(Some(recipient.address()), Some(2), Unwrap::Ok, &user)
+        .assert_base_token_balance_eq(
+            recipient.address(),
+            recipient_balance + deposit_amount / Uint128::new(2),
+        )
+        .withdraw_all_unlocked(None, None, Unwrap::Ok, &user)
+        .assert_base_token_balance_eq(user.address(), deposit_amount / Uint128::new(4));
+
+    // Only the unlocking position is left
+    let positions = robot.query_unlocking_positions(&user.address());
+    assert_eq!(positions.len(), 1);
+    assert_eq!(positions[0].id, 3);
+}
+
+#[test]
+fn cancel_unlock_mints_vault_tokens_for_the_claim() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let user = robot.new_user(&admin);
+    let other_user = robot.new_user(&admin);
+
+    let deposit_amount = Uint128::new(1_000_000);
+    let vault_token_amount = deposit_amount * INITIAL_VAULT_TOKENS_PER_BASE_TOKEN;
+    robot
+        .deposit(deposit_amount, None, Unwrap::Ok, &user)
+        .unlock(vault_token_amount, Unwrap::Ok, &user)
+        .assert_vault_token_balance_eq(user.address(), 0u128)
+        .cancel_unlock(
+            0,
+            None,
+            Unwrap::Err("Claim not owned by sender"),
+            &other_user,
+        );
+
+    // Cancel part of the claim, then the rest. Since the exchange rate is
+    // unchanged, the same amount of vault tokens is minted back.
+    let state_before = robot.query_state();
+    robot
+        .cancel_unlock(0, Some(deposit_amount / Uint128::new(4)), Unwrap::Ok, &user)
+        .assert_vault_token_balance_eq(user.address(), vault_token_amount / Uint128::new(4))
+        .assert_unlocking_position_has_props(
+            0,
+            &user.address(),
+            deposit_amount * Uint128::new(3) / Uint128::new(4),
+        )
+        .cancel_unlock(0, None, Unwrap::Ok, &user)
+        .assert_vault_token_balance_eq(user.address(), vault_token_amount);
+    assert!(robot.query_unlocking_positions(&user.address()).is_empty());
+
+    let state = robot.query_state();
+    assert_eq!(
+        state.staked_base_tokens,
+        state_before.staked_base_tokens + deposit_amount
+    );
+    assert_eq!(
+        state.vault_token_supply,
+        state_before.vault_token_supply + vault_token_amount
+    );
+}
+
+#[test]
+fn unlocking_positions_can_be_split_and_transferred() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let user = robot.new_user(&admin);
+    let buyer = robot.new_user(&admin);
+    let buyer_balance = robot.query_base_token_balance(buyer.address());
+
+    // Split a quarter of the position off for the buyer, then transfer the rest
+    // to the buyer as well
+    let deposit_amount = Uint128::new(1_000_000);
+    let quarter = deposit_amount / Uint128::new(4);
+    robot
+        .deposit(deposit_amount, None, Unwrap::Ok, &user)
+        .unlock(
+            deposit_amount * INITIAL_VAULT_TOKENS_PER_BASE_TOKEN,
+            Unwrap::Ok,
+            &user,
+        )
+        .split_unlocking_position(
+            0,
+            deposit_amount,
+            None,
+            Unwrap::Err("Split amount must be greater than zero"),
+            &user,
+        )
+        .split_unlocking_position(0, quarter, Some(buyer.address()), Unwrap::Ok, &user)
+        .assert_unlocking_position_has_props(0, &user.address(), deposit_amount - quarter)
+        .assert_unlocking_position_has_props(1, &buyer.address(), quarter)
+        .transfer_unlocking_position(
+            0,
+            user.address(),
+            Unwrap::Err("Claim not owned by sender"),
+            &buyer,
+        )
+        .transfer_unlocking_position(0, buyer.address(), Unwrap::Ok, &user)
+        .assert_unlocking_position_has_props(0, &buyer.address(), deposit_amount - quarter);
+    assert!(robot.query_unlocking_positions(&user.address()).is_empty());
+    assert_eq!(robot.query_unlocking_positions(&buyer.address()).len(), 2);
+
+    // Only the buyer can withdraw the positions once they have matured
+    robot
+        .increase_time_by_lockup_duration()
+        .withdraw_unlocked(0, None, Unwrap::Err("Claim not owned by sender"), &user)
+        .withdraw_all_unlocked(None, None, Unwrap::Ok, &buyer)
+        .assert_base_token_balance_eq(buyer.address(), buyer_balance + deposit_amount);
+}
+
+#[test]
+fn unlocking_positions_can_be_queried_across_owners() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let alice = robot.new_user(&admin);
+    let bob = robot.new_user(&admin);
+
+    // Alice unlocks before Bob, so her position is released first
+    let deposit_amount = Uint128::new(1_000_000);
+    let vault_tokens = deposit_amount * INITIAL_VAULT_TOKENS_PER_BASE_TOKEN;
+    let start = robot.query_block_time_seconds();
+    robot
+        .deposit(deposit_amount, None, Unwrap::Ok, &alice)
+        .deposit(deposit_amount, None, Unwrap::Ok, &bob)
+        .unlock(vault_tokens, Unwrap::Ok, &alice)
+        .increase_time(100)
+        .unlock(vault_tokens / Uint128::new(2), Unwrap::Ok, &bob);
+
+    let all = robot.query_all_unlocking_positions(None, None);
+    assert_eq!(all.iter().map(|p| p.id).collect::<Vec<_>>(), vec![0, 1]);
+    assert_eq!(all[0].owner.as_str(), alice.address());
+    assert_eq!(all[1].owner.as_str(), bob.address());
+    assert_eq!(
+        robot.query_all_unlocking_positions(Some(0), None),
+        vec![all[1].clone()]
+    );
+    assert_eq!(
+        robot.query_total_unlocking(),
+        deposit_amount + deposit_amount / Uint128::new(2)
+    );
+
+    // Only Alice's position is released in the first 50 seconds after the lockup
+    // duration
+    let lock_duration = match robot.query_lockup_duration() {
+        Duration::Time(s) => s,
+        Duration::Height(_) => panic!("Expected time duration"),
+    };
+    let released = robot.query_unlocking_positions_released_between(
+        Expiration::AtTime(Timestamp::from_seconds(start)),
+        Expiration::AtTime(Timestamp::from_seconds(start + lock_duration + 50)),
+        None,
+        None,
+    );
+    assert_eq!(released, vec![all[0].clone()]);
+    let released = robot.query_unlocking_positions_released_between(
+        Expiration::AtTime(Timestamp::from_seconds(start)),
+        Expiration::AtTime(Timestamp::from_seconds(start + lock_duration + 200)),
+        Some((all[0].release_at, all[0].id)),
+        None,
+    );
+    assert_eq!(released, vec![all[1].clone()]);
+
+    // Mixing heights and times is rejected
+    let err = Wasm::new(&runner)
+        .query::<_, Vec<UnlockingPosition>>(
+            &robot.vault_addr,
+            &QueryMsg::VaultExtension(ExtensionQueryMsg::Apollo(
+                ApolloExtensionQueryMsg::UnlockingPositionsReleasedBetween {
+                    from: Expiration::AtHeight(0),
+                    to: Expiration::AtTime(Timestamp::from_seconds(start)),
+                    start_after: None,
+                    limit: None,
+                },
+            )),
+        )
+        .unwrap_err();
+    assert!(err.to_string().contains("both be heights or both be times"));
+
+    // The total decreases when a position is withdrawn
+    robot
+        .increase_time_by_lockup_duration()
+        .withdraw_unlocked(0, None, Unwrap::Ok, &alice);
+    assert_eq!(
+        robot.query_total_unlocking(),
+        deposit_amount / Uint128::new(2)
+    );
+    assert_eq!(
+        robot.query_all_unlocking_positions(None, None),
+        vec![all[1].clone()]
+    );
+}
+
+#[test]
+fn unlocking_positions_are_released_after_lock_duration_in_blocks() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let instantiate_msg = InstantiateMsg {
+        lock_duration: Duration::Height(10),
+        ..instantiate_msg_like(&robot, &dependencies, &admin)
+    };
+    let robot = instantiate_with_msg(&runner, &instantiate_msg, &dependencies, &admin);
+    let user = robot.new_user(&admin);
+    assert_eq!(robot.query_lockup_duration(), Duration::Height(10));
+
+    // The position is released at a height, which is not reached by waiting
+    // for more than the lock duration in seconds within a single block
+    let deposit_amount = Uint128::new(1_000_000);
+    let base_token_balance = robot.query_base_token_balance(user.address());
+    robot
+        .deposit(deposit_amount, None, Unwrap::Ok, &user)
+        .unlock(
+            deposit_amount * INITIAL_VAULT_TOKENS_PER_BASE_TOKEN,
+            Unwrap::Ok,
+            &user,
+        );
+    let position = robot.query_unlocking_positions(&user.address()).remove(0);
+    assert!(matches!(position.release_at, Expiration::AtHeight(_)));
+    robot
+        .increase_time(100)
+        .withdraw_unlocked(0, None, Unwrap::Err("Claim has not yet matured"), &user)
+        .increase_time_by_lockup_duration()
+        .withdraw_unlocked(0, None, Unwrap::Ok, &user)
+        .assert_base_token_balance_eq(user.address(), base_token_balance);
+}
+
+#[test]
+fn shortening_lock_duration_can_shorten_existing_positions() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let user = robot.new_user(&admin);
+
+    // Create three unlocking positions
+    let deposit_amount = Uint128::new(900_000);
+    let third = deposit_amount * INITIAL_VAULT_TOKENS_PER_BASE_TOKEN / Uint128::new(3);
+    let base_token_balance = robot.query_base_token_balance(user.address());
+    robot
+        .deposit(deposit_amount, None, Unwrap::Ok, &user)
+        .unlock(third, Unwrap::Ok, &user)
+        .unlock(third, Unwrap::Ok, &user)
+        .unlock(third, Unwrap::Ok, &user);
+
+    // Lengthening the lock duration requires the timelock, and existing
+    // positions can only be shortened together with the lock duration
+    let shorter = ConfigUpdates {
+        lock_duration: Some(Duration::Time(ONE_DAY_IN_SECS)),
+        ..Default::default()
+    };
+    robot
+        .update_config(
+            ConfigUpdates {
+                lock_duration: Some(Duration::Time(TWO_WEEKS_IN_SECS + 1)),
+                ..Default::default()
+            },
+            Unwrap::Err("Config update must be proposed"),
+            &admin,
+        )
+        .update_config_and_shorten_claims(
+            ConfigUpdates::default(),
+            Unwrap::Err("only be shortened when lock_duration is updated"),
+            &admin,
+        )
+        .apply_lock_duration_reduction(None, Unwrap::Err("No lock duration reduction"), &user)
+        .update_config_and_shorten_claims(shorter, Unwrap::Ok, &admin);
+    assert_eq!(
+        robot
+            .query_state()
+            .lock_duration_reduction
+            .unwrap()
+            .lock_duration,
+        Duration::Time(ONE_DAY_IN_SECS)
+    );
+
+    // The positions are only shortened once the reduction is applied, which
+    // anyone can do in batches
+    robot
+        .increase_time(ONE_DAY_IN_SECS)
+        .withdraw_unlocked(0, None, Unwrap::Err("Claim has not yet matured"), &user)
+        .apply_lock_duration_reduction(Some(2), Unwrap::Ok, &admin);
+    assert_eq!(
+        robot
+            .query_state()
+            .lock_duration_reduction
+            .unwrap()
+            .start_after,
+        Some(1)
+    );
+    robot.apply_lock_duration_reduction(Some(2), Unwrap::Ok, &admin);
+    assert!(robot.query_state().lock_duration_reduction.is_none());
+
+    robot
+        .withdraw_all_unlocked(None, None, Unwrap::Ok, &user)
+        .assert_base_token_balance_eq(user.address(), base_token_balance);
+}
+
+#[test]
+fn changing_lock_duration_cancels_pending_lock_duration_reduction() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let user = robot.new_user(&admin);
+
+    let deposit_amount = Uint128::new(900_000);
+    let half = deposit_amount * INITIAL_VAULT_TOKENS_PER_BASE_TOKEN / Uint128::new(2);
+    let shorter = ConfigUpdates {
+        lock_duration: Some(Duration::Time(ONE_DAY_IN_SECS)),
+        ..Default::default()
+    };
+    robot
+        .deposit(deposit_amount, None, Unwrap::Ok, &user)
+        .unlock(half, Unwrap::Ok, &user)
+        .update_config_and_shorten_claims(shorter.clone(), Unwrap::Ok, &admin)
+        .update_config_and_shorten_claims(
+            shorter,
+            Unwrap::Err("lock duration reduction is still being applied"),
+            &admin,
+        );
+
+    // Raising the lock duration again through the timelock drops the pending
+    // reduction, so that positions created afterwards keep their release time
+    robot.update_config_with_timelock(
+        ConfigUpdates {
+            lock_duration: Some(Duration::Time(TWO_WEEKS_IN_SECS)),
+            ..Default::default()
+        },
+        &admin,
+    );
+    assert!(robot.query_state().lock_duration_reduction.is_none());
+    let time = robot.query_block_time_seconds();
+    robot.unlock(half, Unwrap::Ok, &user);
+    let positions = robot.query_unlocking_positions(&user.address());
+    robot.apply_lock_duration_reduction(None, Unwrap::Err("No lock duration reduction"), &user);
+    assert_eq!(robot.query_unlocking_positions(&user.address()), positions);
+    let Expiration::AtTime(release_at) = positions[1].release_at else {
+        panic!("unexpected release time {:?}", positions[1].release_at);
+    };
+    assert!(release_at.seconds() >= time + TWO_WEEKS_IN_SECS);
+}
@@ -0,0 +1,178 @@
+use apollo_cw_asset::{AssetInfoUnchecked, AssetUnchecked};
+use common::{default_instantiate, get_test_runner, DEPS_PATH};
+use cosmwasm_std::Uint128;
+use cw_it::helpers::Unwrap;
+use cw_it::test_tube::Account;
+use cw_vault_standard_test_helpers::traits::lockup::LockedVaultRobot;
+use cw_vault_standard_test_helpers::traits::CwVaultStandardRobot;
+use locked_astroport_vault::helpers::INITIAL_VAULT_TOKENS_PER_BASE_TOKEN;
+use locked_astroport_vault::msg::PoolMigrationMinOut;
+use locked_astroport_vault::state::StakingMode;
+use locked_astroport_vault_test_helpers::robot::{LockedAstroportVaultRobot, WSTETH_DENOM};
+
+pub mod common;
+
+#[test]
+fn migrate_pool_moves_liquidity_and_converts_claims() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let user = robot.new_user(&admin);
+    let vault_addr = robot.vault_addr();
+
+    // Deposit and start unlocking half of the vault tokens
+    let deposit_amount = Uint128::new(1_000_000);
+    let unlock_amount = deposit_amount / Uint128::new(2) * INITIAL_VAULT_TOKENS_PER_BASE_TOKEN;
+    robot
+        .deposit(deposit_amount, None, Unwrap::Ok, &user)
+        .unlock(unlock_amount, Unwrap::Ok, &user);
+    let vault_token_supply = robot.query_total_vault_token_supply();
+
+    // Migrate to a stableswap pair with the same assets
+    let new_pair_addr = robot.create_wsteth_eth_stable_pair(&admin);
+    robot.migrate_pool_with_timelock(
+        &new_pair_addr,
+        PoolMigrationMinOut {
+            withdrawn_assets: vec![],
+            base_tokens: Uint128::one(),
+        },
+        &admin,
+    );
+
+    // The pool, base token and staking should be updated, and all new LP
+    // tokens should be staked
+    let state = robot.query_state();
+    assert_eq!(state.pool.pair_addr.as_str(), new_pair_addr);
+    assert_ne!(state.base_token, base_pool.lp_token);
+    assert_eq!(state.pool.lp_token, state.base_token);
+    match state.staking {
+        StakingMode::Astroport(staking) => assert_eq!(staking.lp_token, state.base_token),
+        StakingMode::Hold => panic!("Staking mode should not change"),
+    }
+    assert_eq!(state.vault_token_supply, vault_token_supply);
+    assert!(!state.staked_base_tokens.is_zero());
+    robot.assert_base_token_balance_eq(&vault_addr, 0u128);
+
+    // The claim should be reported in the new base token, but can only be
+    // withdrawn once it has been converted
+    let claim = robot
+        .query_unlocking_positions(&user.address())
+        .pop()
+        .unwrap();
+    assert!(!claim.base_token_amount.is_zero());
+    assert!(state.claims_rescale.is_some());
+    robot
+        .increase_time_by_lockup_duration()
+        .withdraw_unlocked(
+            claim.id,
+            None,
+            Unwrap::Err("has not been converted to the new base token yet"),
+            &user,
+        )
+        .apply_claims_rescale(None, Unwrap::Ok, &user)
+        .apply_claims_rescale(
+            None,
+            Unwrap::Err("No unlocking positions are being converted"),
+            &user,
+        );
+    let state = robot.query_state();
+    assert!(state.claims_rescale.is_none());
+    robot
+        .withdraw_unlocked(claim.id, None, Unwrap::Ok, &user)
+        .assert_base_token_balance_eq(user.address(), claim.base_token_amount);
+
+    // The remaining vault tokens should be redeemable for the new base token
+    let vault_token_balance = robot.query_vault_token_balance(user.address());
+    robot
+        .unlock(vault_token_balance, Unwrap::Ok, &user)
+        .increase_time_by_lockup_duration()
+        .withdraw_unlocked(claim.id + 1, None, Unwrap::Ok, &user)
+        .assert_vault_token_balance_eq(user.address(), 0u128)
+        .assert_base_token_balance_eq(
+            user.address(),
+            claim.base_token_amount + state.staked_base_tokens,
+        );
+}
+
+#[test]
+fn migrate_pool_validates_new_pool() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let user = robot.new_user(&admin);
+
+    let new_pair_addr = robot.create_wsteth_eth_stable_pair(&admin);
+    robot
+        .propose_pool_migration(
+            &new_pair_addr,
+            Unwrap::Err("Caller is not the contract's current owner"),
+            &user,
+        )
+        .propose_pool_migration(
+            base_pool.pair_addr,
+            Unwrap::Err("New pool is the same as the current pool"),
+            &admin,
+        );
+    assert!(robot.query_config_update_proposals().is_empty());
+}
+
+#[test]
+fn migrate_pool_is_timelocked_and_enforces_min_out() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let user = robot.new_user(&admin);
+    robot.deposit(Uint128::new(1_000_000), None, Unwrap::Ok, &user);
+
+    let new_pair_addr = robot.create_wsteth_eth_stable_pair(&admin);
+    robot.propose_pool_migration(&new_pair_addr, Unwrap::Ok, &admin);
+    let proposal = robot.query_config_update_proposals().pop().unwrap();
+    assert_eq!(proposal.pool, Some(new_pair_addr.clone()));
+
+    let min_out = |withdrawn_amount: u128, base_tokens: u128| PoolMigrationMinOut {
+        withdrawn_assets: vec![AssetUnchecked::new(
+            AssetInfoUnchecked::native(WSTETH_DENOM),
+            withdrawn_amount,
+        )],
+        base_tokens: Uint128::new(base_tokens),
+    };
+
+    // The migration can not be executed before the delay has passed, without
+    // minimum amounts or if the minimum amounts are not received
+    robot
+        .execute_pool_migration(
+            proposal.id,
+            Some(min_out(1, 1)),
+            Unwrap::Err("can not be executed until"),
+            &admin,
+        )
+        .increase_time(robot.query_config().config_update_delay)
+        .execute_pool_migration(
+            proposal.id,
+            None,
+            Unwrap::Err("pool_migration_min_out is required to migrate the pool"),
+            &admin,
+        )
+        .execute_pool_migration(
+            proposal.id,
+            Some(min_out(u128::MAX, 1)),
+            Unwrap::Err("but expected"),
+            &admin,
+        )
+        .execute_pool_migration(
+            proposal.id,
+            Some(min_out(1, u128::MAX)),
+            Unwrap::Err("but at least"),
+            &admin,
+        );
+    assert_eq!(robot.query_state().pool.pair_addr, base_pool.pair_addr);
+
+    robot.execute_pool_migration(proposal.id, Some(min_out(1, 1)), Unwrap::Ok, &admin);
+    assert_eq!(robot.query_state().pool.pair_addr.as_str(), new_pair_addr);
+}
@@ -0,0 +1,67 @@
+use apollo_cw_asset::{AssetInfo, AssetUnchecked};
+use common::{default_instantiate, get_test_runner, DEPS_PATH};
+use cosmwasm_std::Uint128;
+use cw_it::helpers::Unwrap;
+use cw_it::robot::TestRobot;
+use cw_it::test_tube::Account;
+use cw_it::traits::CwItRunner;
+use cw_vault_standard_test_helpers::traits::CwVaultStandardRobot;
+use locked_astroport_vault_test_helpers::robot::{LockedAstroportVaultRobot, USDC_DENOM};
+
+pub mod common;
+
+#[test]
+fn owner_can_rescue_unrelated_tokens() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let user = robot.new_user(&admin);
+    let recipient = runner.init_account(&[]).unwrap();
+
+    let amount = 1_000_000u128;
+    robot.send_native_tokens(&admin, &robot.vault_addr, amount, USDC_DENOM.to_string());
+
+    robot
+        .rescue_tokens(
+            vec![AssetUnchecked::native(USDC_DENOM, amount)],
+            recipient.address(),
+            Unwrap::Err("Caller is not the contract's current owner"),
+            &user,
+        )
+        .rescue_tokens(
+            vec![AssetUnchecked::native(USDC_DENOM, amount)],
+            recipient.address(),
+            Unwrap::Ok,
+            &admin,
+        )
+        .assert_native_token_balance_eq(recipient.address(), USDC_DENOM, amount);
+}
+
+#[test]
+fn depositor_funds_can_not_be_rescued() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let user = robot.new_user(&admin);
+    robot.deposit(Uint128::new(1_000_000), None, Unwrap::Ok, &user);
+
+    let config = robot.query_config();
+    let forbidden = vec![
+        base_pool.lp_token,
+        config.reward_tokens[0].clone(),
+        base_pool.pool_assets[0].clone(),
+        AssetInfo::native(robot.vault_token()),
+    ];
+    for asset_info in forbidden {
+        robot.rescue_tokens(
+            vec![AssetUnchecked::new(asset_info.into(), 1u128)],
+            admin.address(),
+            Unwrap::Err("since it may belong to depositors"),
+            &admin,
+        );
+    }
+}
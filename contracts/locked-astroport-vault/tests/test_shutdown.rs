@@ -0,0 +1,72 @@
+use common::{default_instantiate, get_test_runner, DEPS_PATH};
+use cosmwasm_std::Uint128;
+use cw_it::helpers::Unwrap;
+use cw_it::test_tube::Account;
+use cw_vault_standard_test_helpers::traits::lockup::LockedVaultRobot;
+use cw_vault_standard_test_helpers::traits::CwVaultStandardRobot;
+use locked_astroport_vault::helpers::INITIAL_VAULT_TOKENS_PER_BASE_TOKEN;
+use locked_astroport_vault::state::StakingMode;
+use locked_astroport_vault_test_helpers::robot::LockedAstroportVaultRobot;
+
+pub mod common;
+
+#[test]
+fn shutdown_unstakes_everything_and_releases_depositors() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let user = robot.new_user(&admin);
+    let unlocker = robot.new_user(&admin);
+    let vault_addr = robot.vault_addr();
+
+    // One depositor still holds vault tokens, the other is unlocking
+    let deposit_amount = Uint128::new(1_000_000);
+    let vault_tokens = deposit_amount * INITIAL_VAULT_TOKENS_PER_BASE_TOKEN;
+    robot
+        .deposit(deposit_amount, None, Unwrap::Ok, &user)
+        .deposit(deposit_amount, None, Unwrap::Ok, &unlocker)
+        .unlock(vault_tokens, Unwrap::Ok, &unlocker)
+        .shutdown(
+            Unwrap::Err("Caller is not the contract's current owner"),
+            &user,
+        )
+        .shutdown(Unwrap::Ok, &admin)
+        .assert_base_token_balance_eq(&vault_addr, deposit_amount * Uint128::new(2));
+
+    let state = robot.query_state();
+    assert!(state.shutdown.is_some());
+    assert_eq!(state.staking, StakingMode::Hold);
+    assert!(!robot.query_config().deposits_enabled);
+
+    // Deposits, compounding and emergency unlocks are disabled for good
+    robot
+        .deposit(
+            deposit_amount,
+            None,
+            Unwrap::Err("The vault has been shut down"),
+            &admin,
+        )
+        .sweep_dust(Unwrap::Err("The vault has been shut down"), &admin)
+        .emergency_unlock(
+            vault_tokens,
+            Unwrap::Err("The vault has been shut down"),
+            &user,
+        )
+        .shutdown(Unwrap::Err("The vault has been shut down"), &admin);
+
+    // The unlocking position is matured and vault tokens are released without
+    // a lock period
+    let user_balance = robot.query_base_token_balance(user.address());
+    let unlocker_balance = robot.query_base_token_balance(unlocker.address());
+    robot
+        .withdraw_unlocked(0, None, Unwrap::Ok, &unlocker)
+        .assert_base_token_balance_eq(unlocker.address(), unlocker_balance + deposit_amount)
+        .unlock(vault_tokens, Unwrap::Ok, &user)
+        .assert_base_token_balance_eq(user.address(), user_balance + deposit_amount)
+        .assert_vault_token_balance_eq(user.address(), 0u128)
+        .assert_base_token_balance_eq(&vault_addr, 0u128);
+
+    assert!(robot.query_unlocking_positions(&user.address()).is_empty());
+}
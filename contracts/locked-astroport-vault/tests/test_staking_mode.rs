@@ -0,0 +1,104 @@
+use common::{default_instantiate, get_test_runner, DEPS_PATH};
+use cosmwasm_std::Uint128;
+use cw_it::helpers::Unwrap;
+use cw_it::test_tube::Account;
+use cw_vault_standard_test_helpers::traits::lockup::LockedVaultRobot;
+use cw_vault_standard_test_helpers::traits::CwVaultStandardRobot;
+use locked_astroport_vault::helpers::INITIAL_VAULT_TOKENS_PER_BASE_TOKEN;
+use locked_astroport_vault::state::{StakingMode, StakingModeUnchecked};
+use locked_astroport_vault_test_helpers::robot::LockedAstroportVaultRobot;
+
+pub mod common;
+
+#[test]
+fn migrating_between_staking_modes_keeps_state_consistent() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let user = robot.new_user(&admin);
+    let vault_addr = robot.vault_addr();
+
+    // Deposit and start unlocking half of the vault tokens, so that there are
+    // both staked base tokens and base tokens in claims
+    let deposit_amount = Uint128::new(1_000_000);
+    let unlock_amount = deposit_amount / Uint128::new(2) * INITIAL_VAULT_TOKENS_PER_BASE_TOKEN;
+    robot
+        .deposit(deposit_amount, None, Unwrap::Ok, &user)
+        .unlock(unlock_amount, Unwrap::Ok, &user)
+        .assert_base_token_balance_eq(&vault_addr, 0u128);
+    let state_before = robot.query_state();
+    let astroport_staking = state_before.staking.clone();
+
+    // Stop staking. All LP tokens, including the ones in claims, should be
+    // unstaked and held by the vault.
+    robot
+        .update_staking_mode(StakingModeUnchecked::Hold {}, Unwrap::Ok, &admin)
+        .assert_base_token_balance_eq(&vault_addr, deposit_amount);
+    let state = robot.query_state();
+    assert_eq!(state.staking, StakingMode::Hold);
+    assert_eq!(state.staked_base_tokens, state_before.staked_base_tokens);
+    assert_eq!(state.vault_token_supply, state_before.vault_token_supply);
+
+    // Deposits, compounding and withdrawals should still work while holding.
+    // Compounding should not treat the held LP tokens as rewards, so after the
+    // claim is withdrawn the vault should hold exactly its total assets.
+    robot
+        .deposit(deposit_amount, None, Unwrap::Ok, &admin)
+        .compound_vault(&admin)
+        .increase_time_by_lockup_duration()
+        .withdraw_unlocked(0, None, Unwrap::Ok, &user);
+    let total_vault_assets = robot.query_total_vault_assets();
+    assert!(total_vault_assets >= deposit_amount * Uint128::new(3) / Uint128::new(2));
+    robot.assert_base_token_balance_eq(&vault_addr, total_vault_assets);
+
+    // Migrate back to Astroport staking through the timelock. All held LP
+    // tokens should be staked again.
+    let state_before = robot.query_state();
+    robot
+        .update_staking_mode_with_timelock(
+            StakingModeUnchecked::Astroport {
+                incentives: dependencies.astroport_contracts.incentives.address.clone(),
+            },
+            &admin,
+        )
+        .assert_base_token_balance_eq(&vault_addr, 0u128);
+    let state = robot.query_state();
+    assert_eq!(state.staking, astroport_staking);
+    assert_eq!(state.staked_base_tokens, state_before.staked_base_tokens);
+    assert_eq!(state.vault_token_supply, state_before.vault_token_supply);
+
+    // Redeeming should unstake from Astroport again
+    let vault_token_balance = robot.query_vault_token_balance(user.address());
+    robot
+        .unlock(vault_token_balance, Unwrap::Ok, &user)
+        .increase_time_by_lockup_duration()
+        .withdraw_unlocked(1, None, Unwrap::Ok, &user)
+        .assert_vault_token_balance_eq(user.address(), 0u128);
+}
+
+#[test]
+fn only_hold_staking_mode_can_be_set_without_timelock() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let user = robot.new_user(&admin);
+
+    robot
+        .update_staking_mode(
+            StakingModeUnchecked::Astroport {
+                incentives: user.address(),
+            },
+            Unwrap::Err("Config update must be proposed"),
+            &admin,
+        )
+        .update_staking_mode(
+            StakingModeUnchecked::Hold {},
+            Unwrap::Err("Caller is not the contract's current owner"),
+            &user,
+        )
+        .update_staking_mode(StakingModeUnchecked::Hold {}, Unwrap::Ok, &admin);
+}
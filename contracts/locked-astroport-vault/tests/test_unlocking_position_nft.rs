@@ -0,0 +1,154 @@
+use common::{
+    default_instantiate, get_test_runner, instantiate_msg_like, instantiate_with_msg, DEPS_PATH,
+};
+use cosmwasm_std::Uint128;
+use cw_it::helpers::Unwrap;
+use cw_it::test_tube::{Account, SigningAccount};
+use cw_it::TestRunner;
+use cw_vault_standard_test_helpers::traits::force_unlock::ForceUnlockVaultRobot;
+use cw_vault_standard_test_helpers::traits::lockup::LockedVaultRobot;
+use cw_vault_standard_test_helpers::traits::CwVaultStandardRobot;
+use locked_astroport_vault::helpers::INITIAL_VAULT_TOKENS_PER_BASE_TOKEN;
+use locked_astroport_vault::msg::{InstantiateMsg, ReceiveNftMsg};
+use locked_astroport_vault_test_helpers::robot::{
+    LockedAstroportVaultRobot, LockedVaultDependencies,
+};
+
+pub mod common;
+
+/// Instantiates a vault with unlocking position NFTs for the same pool and
+/// with the same config as `robot`.
+fn instantiate_nft_vault<'a>(
+    runner: &'a TestRunner<'a>,
+    robot: &LockedAstroportVaultRobot<'a>,
+    dependencies: &'a LockedVaultDependencies<'a>,
+    admin: &SigningAccount,
+) -> LockedAstroportVaultRobot<'a> {
+    let code_id = LockedAstroportVaultRobot::store_cw721_code(runner, admin, DEPS_PATH);
+    let instantiate_msg = InstantiateMsg {
+        unlocking_position_nft_code_id: Some(code_id),
+        ..instantiate_msg_like(robot, dependencies, admin)
+    };
+    instantiate_with_msg(runner, &instantiate_msg, dependencies, admin)
+}
+
+#[test]
+fn unlocking_positions_are_withdrawn_by_the_nft_holder() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let robot = instantiate_nft_vault(&runner, &robot, &dependencies, &admin);
+    let user = robot.new_user(&admin);
+    let buyer = robot.new_user(&admin);
+    let buyer_balance = robot.query_base_token_balance(buyer.address());
+
+    // Unlocking mints an NFT for the position to the user
+    let deposit_amount = Uint128::new(1_000_000);
+    robot
+        .deposit(deposit_amount, None, Unwrap::Ok, &user)
+        .unlock(
+            deposit_amount * INITIAL_VAULT_TOKENS_PER_BASE_TOKEN,
+            Unwrap::Ok,
+            &user,
+        );
+    assert_eq!(
+        robot.query_unlocking_position_nft_owner(0).unwrap(),
+        user.address()
+    );
+
+    // Once the NFT is sold, the position belongs to the buyer. Positions can not
+    // be transferred or withdrawn through the vault without sending the NFT.
+    robot
+        .transfer_unlocking_position(
+            0,
+            buyer.address(),
+            Unwrap::Err("Unlocking positions are NFTs"),
+            &user,
+        )
+        .transfer_unlocking_position_nft(0, buyer.address(), &user)
+        .send_unlocking_position_nft(
+            0,
+            ReceiveNftMsg::WithdrawUnlocked { recipient: None },
+            Unwrap::Err("Claim has not yet matured"),
+            &buyer,
+        )
+        .increase_time_by_lockup_duration()
+        .withdraw_unlocked(0, None, Unwrap::Err("Unlocking positions are NFTs"), &buyer)
+        .send_unlocking_position_nft(
+            0,
+            ReceiveNftMsg::WithdrawUnlocked { recipient: None },
+            Unwrap::Err("owner"),
+            &user,
+        );
+    assert!(robot.query_unlocking_positions(&user.address()).is_empty());
+    assert_eq!(robot.query_unlocking_positions(&buyer.address()).len(), 1);
+    assert_eq!(
+        robot.query_all_unlocking_positions(None, None)[0].owner,
+        buyer.address()
+    );
+
+    // The vault holds the NFT while the position is withdrawn and burns it
+    robot
+        .send_unlocking_position_nft(
+            0,
+            ReceiveNftMsg::WithdrawUnlocked { recipient: None },
+            Unwrap::Ok,
+            &buyer,
+        )
+        .assert_base_token_balance_eq(buyer.address(), buyer_balance + deposit_amount);
+    assert!(robot.query_unlocking_position_nft_owner(0).is_err());
+}
+
+#[test]
+fn force_withdraw_of_nft_positions_requires_the_nft() {
+    let owned_runner = get_test_runner();
+    let runner = owned_runner.as_ref();
+    let admin = LockedAstroportVaultRobot::new_admin(&runner);
+    let dependencies = LockedAstroportVaultRobot::instantiate_deps(&runner, &admin, DEPS_PATH);
+    let (robot, _base_pool, _treasury) = default_instantiate(&runner, &admin, &dependencies);
+    let robot = instantiate_nft_vault(&runner, &robot, &dependencies, &admin);
+    let user = robot.new_user(&admin);
+    let liquidator = robot.new_user(&admin);
+
+    let deposit_amount = Uint128::new(1_000_000);
+    robot
+        .deposit(deposit_amount, None, Unwrap::Ok, &user)
+        .unlock(
+            deposit_amount * INITIAL_VAULT_TOKENS_PER_BASE_TOKEN,
+            Unwrap::Ok,
+            &user,
+        )
+        .update_force_withdraw_whitelist(vec![liquidator.address()], vec![], Unwrap::Ok, &admin);
+
+    // A whitelisted address can only force withdraw positions whose NFT it
+    // holds. Partially withdrawing sends the NFT back, and the holder of the
+    // NFT becomes the owner of the position.
+    let half = deposit_amount / Uint128::new(2);
+    let force_withdraw_msg = ReceiveNftMsg::ForceWithdrawUnlocking {
+        amount: Some(half),
+        recipient: None,
+    };
+    robot
+        .force_withdraw_unlocking(
+            0,
+            Some(half),
+            None,
+            Unwrap::Err("Unlocking positions are NFTs"),
+            &liquidator,
+        )
+        .send_unlocking_position_nft(
+            0,
+            force_withdraw_msg.clone(),
+            Unwrap::Err("owner"),
+            &liquidator,
+        )
+        .transfer_unlocking_position_nft(0, liquidator.address(), &user)
+        .send_unlocking_position_nft(0, force_withdraw_msg, Unwrap::Ok, &liquidator)
+        .assert_unlocking_position_has_props(0, &liquidator.address(), half);
+    assert_eq!(
+        robot.query_unlocking_position_nft_owner(0).unwrap(),
+        liquidator.address()
+    );
+}
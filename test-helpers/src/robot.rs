@@ -1,12 +1,16 @@
 use std::collections::HashSet;
 use std::str::FromStr;
 
-use apollo_cw_asset::AssetInfo;
+use apollo_cw_asset::{Asset, AssetInfo, AssetUnchecked};
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{coin, coins, to_json_binary, Addr, Coin, Coins, Decimal, Empty, Uint128};
+use cosmwasm_std::{
+    coin, coins, to_json_binary, Addr, Coin, Coins, Decimal, Empty, StdError, StdResult, Uint128,
+};
 use cw20::{Cw20ExecuteMsg, Cw20QueryMsg};
-use cw_dex_astroport::astroport::asset::AssetInfo as AstroAssetInfo;
-use cw_dex_astroport::astroport::factory::PairType;
+use cw_dex_astroport::astroport::asset::{Asset as AstroAsset, AssetInfo as AstroAssetInfo};
+use cw_dex_astroport::astroport::factory::{ExecuteMsg as FactoryExecuteMsg, PairType};
+use cw_dex_astroport::astroport::incentives::{ExecuteMsg as IncentivesExecuteMsg, InputSchedule};
+use cw_dex_astroport::astroport::pair::StablePoolParams;
 
 use cw_dex_astroport::AstroportPool;
 use cw_dex_router::operations::{SwapOperationUnchecked, SwapOperationsListUnchecked};
@@ -22,8 +26,8 @@ use cw_it::test_tube::{Account, Module, Runner, SigningAccount, Wasm};
 use cw_it::traits::CwItRunner;
 use cw_it::{Artifact, ContractType, TestRunner};
 use cw_ownable::Ownership;
-use cw_utils::Duration;
-use cw_vault_standard::extensions::lockup::{LockupQueryMsg, UnlockingPosition};
+use cw_utils::{Duration, Expiration};
+use cw_vault_standard::extensions::lockup::{LockupExecuteMsg, LockupQueryMsg, UnlockingPosition};
 use cw_vault_standard::{VaultInfoResponse, VaultStandardInfoResponse, VaultStandardQueryMsg};
 use cw_vault_standard_test_helpers::traits::force_unlock::ForceUnlockVaultRobot;
 use cw_vault_standard_test_helpers::traits::lockup::LockedVaultRobot;
@@ -31,17 +35,27 @@ use cw_vault_standard_test_helpers::traits::CwVaultStandardRobot;
 use liquidity_helper::LiquidityHelperUnchecked;
 use locked_astroport_vault::msg::{
     ApolloExtensionExecuteMsg, ApolloExtensionQueryMsg, ExecuteMsg, ExtensionExecuteMsg,
-    ExtensionQueryMsg, InstantiateMsg, QueryMsg,
+    ExtensionQueryMsg, InstantiateMsg, MigrateMsg, PoolMigrationMinOut, QueryMsg, ReceiveMsg,
+    ReceiveNftMsg,
+};
+use locked_astroport_vault::state::{
+    Config, ConfigBase, ConfigUpdateProposal, ConfigUpdates, Donations, FeeConfig,
+    LockTierPositionResponse, LockTierResponse, PendingRewardsResponse, SimulateCompoundResponse,
+    StakingModeUnchecked, StateResponse, UserPositionResponse, ValidateConfigUpdateResponse,
+    VaultToken, VaultTokenMetadata,
 };
-use locked_astroport_vault::state::{Config, ConfigBase, ConfigUpdates, FeeConfig, StateResponse};
 
 use crate::router::CwDexRouterRobot;
 
 pub const LOCKED_ASTROPORT_VAULT_WASM_NAME: &str = "locked_astroport_vault.wasm";
 pub const ASTROPORT_LIQUIDITY_HELPER_WASM_NAME: &str = "astroport_liquidity_helper.wasm";
+pub const CW721_BASE_WASM_NAME: &str = "cw721_base.wasm";
 pub const ASTROPORT_ARTIFACTS_DIR: &str = "astroport-artifacts";
 
 pub const TWO_WEEKS_IN_SECS: u64 = 1_209_600;
+pub const BLOCK_TIME_IN_SECS: u64 = 5;
+
+pub const ONE_DAY_IN_SECS: u64 = 86_400;
 
 pub const WSTETH_DENOM: &str = "uwsteth";
 pub const ETH_DENOM: &str = "ueth";
@@ -118,6 +132,31 @@ impl<'a> LockedAstroportVaultRobot<'a> {
             .unwrap()
     }
 
+    /// Uploads a cw721-base contract, which can be used for unlocking position
+    /// NFTs, and returns its code id.
+    pub fn store_cw721_code(
+        runner: &'a TestRunner<'a>,
+        signer: &SigningAccount,
+        _artifacts_dir: &str,
+    ) -> u64 {
+        let cw721_contract = match runner {
+            #[cfg(feature = "osmosis-test-tube")]
+            TestRunner::OsmosisTestApp(_) => ContractType::Artifact(Artifact::Local(format!(
+                "{}/{}",
+                _artifacts_dir, CW721_BASE_WASM_NAME
+            ))),
+            TestRunner::MultiTest(_) => {
+                ContractType::MultiTestContract(Box::new(ContractWrapper::new_with_empty(
+                    cw721_base::entry::execute,
+                    cw721_base::entry::instantiate,
+                    cw721_base::entry::query,
+                )))
+            }
+            _ => panic!("Unsupported runner"),
+        };
+        runner.store_code(cw721_contract, signer).unwrap()
+    }
+
     // Uploads and instantiates dependencies for the LockedAstroportVaultRobot.
     pub fn instantiate_deps(
         runner: &'a TestRunner<'a>,
@@ -232,7 +271,7 @@ impl<'a> LockedAstroportVaultRobot<'a> {
         performance_fee: Option<FeeConfig<String>>,
         deposit_fee: Option<FeeConfig<String>>,
         withdrawal_fee: Option<FeeConfig<String>>,
-        lock_duration: u64,
+        lock_duration: Duration,
         dependencies: &LockedVaultDependencies<'a>,
         signer: &SigningAccount,
     ) -> (Self, AstroportPool, AstroportPool) {
@@ -360,6 +399,7 @@ impl<'a> LockedAstroportVaultRobot<'a> {
         let instantiate_msg = InstantiateMsg {
             owner: signer.address().to_string(),
             vault_token_subdenom: "testVaultToken".to_string(),
+            vault_token_backend: None,
             lock_duration,
             reward_tokens: vec![astro.into(), axl.into(), ntrn.clone().into()],
             deposits_enabled: true,
@@ -381,7 +421,12 @@ impl<'a> LockedAstroportVaultRobot<'a> {
             } else {
                 Some(liq_manager.to_string())
             },
-            astroport_incentives_addr: dependencies.astroport_contracts.incentives.address.clone(),
+            astroport_incentives_addr: Some(
+                dependencies.astroport_contracts.incentives.address.clone(),
+            ),
+            config_update_delay: ONE_DAY_IN_SECS,
+            vault_token_metadata: None,
+            unlocking_position_nft_code_id: None,
         };
 
         (
@@ -417,7 +462,7 @@ impl<'a> LockedAstroportVaultRobot<'a> {
             performance_fee,
             deposit_fee,
             withdrawal_fee,
-            0,
+            Duration::Time(0),
             dependencies,
             signer,
         )
@@ -651,7 +696,8 @@ impl<'a> LockedAstroportVaultRobot<'a> {
         let init_msg = InstantiateMsg {
             owner: signer.address(),
             vault_token_subdenom: "testVaultToken".to_string(),
-            lock_duration: TWO_WEEKS_IN_SECS,
+            vault_token_backend: None,
+            lock_duration: Duration::Time(TWO_WEEKS_IN_SECS),
             reward_tokens: vec![astro.into(), axl.into(), ntrn.into()],
             deposits_enabled: true,
             performance_fee,
@@ -670,7 +716,10 @@ impl<'a> LockedAstroportVaultRobot<'a> {
             } else {
                 Some(liq_manager.to_string())
             },
-            astroport_incentives_addr: astroport_contracts.incentives.address.clone(),
+            astroport_incentives_addr: Some(astroport_contracts.incentives.address.clone()),
+            config_update_delay: ONE_DAY_IN_SECS,
+            vault_token_metadata: None,
+            unlocking_position_nft_code_id: None,
         };
 
         (
@@ -686,14 +735,14 @@ impl<'a> LockedAstroportVaultRobot<'a> {
         )
     }
 
-    pub fn migrate(&self, new_code_id: u64, signer: &SigningAccount) -> &Self {
+    pub fn migrate(&self, new_code_id: u64, msg: &MigrateMsg, signer: &SigningAccount) -> &Self {
         self.runner
             .execute::<_, MsgMigrateContractResponse>(
                 MsgMigrateContract {
                     sender: signer.address(),
                     contract: self.vault_addr.clone(),
                     code_id: new_code_id,
-                    msg: to_json_binary(&Empty {}).unwrap().0,
+                    msg: to_json_binary(msg).unwrap().0,
                 },
                 "/cosmwasm.wasm.v1.MsgMigrateContract",
                 signer,
@@ -778,6 +827,28 @@ impl<'a> LockedAstroportVaultRobot<'a> {
             .deposit_with_funds(amount, recipient, &[], unwrap_choice, signer)
     }
 
+    /// Deposit cw20 base tokens into the vault with a single
+    /// `Cw20ExecuteMsg::Send` and the `ReceiveMsg::Deposit` hook.
+    pub fn deposit_cw20_with_send(
+        &self,
+        amount: Uint128,
+        recipient: Option<String>,
+        unwrap_choice: Unwrap,
+        signer: &SigningAccount,
+    ) -> &Self {
+        unwrap_choice.unwrap(self.wasm().execute(
+            &self.base_token(),
+            &Cw20ExecuteMsg::Send {
+                contract: self.vault_addr.clone(),
+                amount,
+                msg: to_json_binary(&ReceiveMsg::Deposit { recipient }).unwrap(),
+            },
+            &[],
+            signer,
+        ));
+        self
+    }
+
     /// Update the config of the vault and return a reference to the robot.
     pub fn update_config(
         &self,
@@ -788,7 +859,10 @@ impl<'a> LockedAstroportVaultRobot<'a> {
         unwrap_choice.unwrap(self.wasm().execute(
             &self.vault_addr,
             &ExecuteMsg::VaultExtension(ExtensionExecuteMsg::Apollo(
-                ApolloExtensionExecuteMsg::UpdateConfig { updates },
+                ApolloExtensionExecuteMsg::UpdateConfig {
+                    updates,
+                    shorten_existing_claims: None,
+                },
             )),
             &[],
             signer,
@@ -796,115 +870,997 @@ impl<'a> LockedAstroportVaultRobot<'a> {
         self
     }
 
-    /// Calls `ExecuteMsg::Redeem` to redeem vault tokens from the vault
-    pub fn redeem(
+    /// Update the config of the vault, also shortening existing unlocking
+    /// positions to the new lock duration, and return a reference to the robot.
+    pub fn update_config_and_shorten_claims(
         &self,
-        amount: Uint128,
-        recipient: Option<String>,
+        updates: ConfigUpdates<String>,
         unwrap_choice: Unwrap,
-        funds: Option<Vec<Coin>>,
         signer: &SigningAccount,
     ) -> &Self {
         unwrap_choice.unwrap(self.wasm().execute(
             &self.vault_addr,
-            &ExecuteMsg::Redeem { amount, recipient },
-            &funds.unwrap_or_else(|| coins(amount.u128(), self.vault_token())),
+            &ExecuteMsg::VaultExtension(ExtensionExecuteMsg::Apollo(
+                ApolloExtensionExecuteMsg::UpdateConfig {
+                    updates,
+                    shorten_existing_claims: Some(true),
+                },
+            )),
+            &[],
             signer,
         ));
         self
     }
 
-    /// Compounds the rewards in the vault
-    pub fn compound_vault(&self, signer: &SigningAccount) -> &Self {
+    /// Applies a pending lock duration reduction to up to `limit` unlocking
+    /// positions and return a reference to the robot.
+    pub fn apply_lock_duration_reduction(
+        &self,
+        limit: Option<u32>,
+        unwrap_choice: Unwrap,
+        signer: &SigningAccount,
+    ) -> &Self {
+        unwrap_choice.unwrap(self.wasm().execute(
+            &self.vault_addr,
+            &ExecuteMsg::VaultExtension(ExtensionExecuteMsg::Apollo(
+                ApolloExtensionExecuteMsg::ApplyLockDurationReduction { limit },
+            )),
+            &[],
+            signer,
+        ));
+        self
+    }
+
+    /// Converts up to `limit` unlocking positions to the base token of the
+    /// pool that the vault migrated to and return a reference to the robot.
+    pub fn apply_claims_rescale(
+        &self,
+        limit: Option<u32>,
+        unwrap_choice: Unwrap,
+        signer: &SigningAccount,
+    ) -> &Self {
+        unwrap_choice.unwrap(self.wasm().execute(
+            &self.vault_addr,
+            &ExecuteMsg::VaultExtension(ExtensionExecuteMsg::Apollo(
+                ApolloExtensionExecuteMsg::ApplyClaimsRescale { limit },
+            )),
+            &[],
+            signer,
+        ));
+        self
+    }
+
+    /// Propose a config update and return a reference to the robot.
+    pub fn propose_config_update(
+        &self,
+        updates: ConfigUpdates<String>,
+        unwrap_choice: Unwrap,
+        signer: &SigningAccount,
+    ) -> &Self {
+        unwrap_choice.unwrap(self.wasm().execute(
+            &self.vault_addr,
+            &ExecuteMsg::VaultExtension(ExtensionExecuteMsg::Apollo(
+                ApolloExtensionExecuteMsg::ProposeConfigUpdate {
+                    updates,
+                    staking_mode: None,
+                    pool: None,
+                },
+            )),
+            &[],
+            signer,
+        ));
+        self
+    }
+
+    /// Execute a proposed config update and return a reference to the robot.
+    pub fn execute_config_update(
+        &self,
+        proposal_id: u64,
+        unwrap_choice: Unwrap,
+        signer: &SigningAccount,
+    ) -> &Self {
+        unwrap_choice.unwrap(self.wasm().execute(
+            &self.vault_addr,
+            &ExecuteMsg::VaultExtension(ExtensionExecuteMsg::Apollo(
+                ApolloExtensionExecuteMsg::ExecuteConfigUpdate {
+                    proposal_id,
+                    pool_migration_min_out: None,
+                },
+            )),
+            &[],
+            signer,
+        ));
+        self
+    }
+
+    /// Cancel a proposed config update and return a reference to the robot.
+    pub fn cancel_config_update(
+        &self,
+        proposal_id: u64,
+        unwrap_choice: Unwrap,
+        signer: &SigningAccount,
+    ) -> &Self {
+        unwrap_choice.unwrap(self.wasm().execute(
+            &self.vault_addr,
+            &ExecuteMsg::VaultExtension(ExtensionExecuteMsg::Apollo(
+                ApolloExtensionExecuteMsg::CancelConfigUpdate { proposal_id },
+            )),
+            &[],
+            signer,
+        ));
+        self
+    }
+
+    /// Proposes a config update, waits for the config update delay to pass and
+    /// then executes the proposal.
+    pub fn update_config_with_timelock(
+        &self,
+        updates: ConfigUpdates<String>,
+        signer: &SigningAccount,
+    ) -> &Self {
+        self.propose_config_update(updates, Unwrap::Ok, signer);
+        let proposal = self.query_config_update_proposals().pop().unwrap();
+        self.increase_time(self.query_config().config_update_delay)
+            .execute_config_update(proposal.id, Unwrap::Ok, signer)
+    }
+
+    /// Migrates the vault to a new staking mode without the timelock and
+    /// return a reference to the robot.
+    pub fn update_staking_mode(
+        &self,
+        staking_mode: StakingModeUnchecked,
+        unwrap_choice: Unwrap,
+        signer: &SigningAccount,
+    ) -> &Self {
+        unwrap_choice.unwrap(self.wasm().execute(
+            &self.vault_addr,
+            &ExecuteMsg::VaultExtension(ExtensionExecuteMsg::Apollo(
+                ApolloExtensionExecuteMsg::UpdateStakingMode { staking_mode },
+            )),
+            &[],
+            signer,
+        ));
+        self
+    }
+
+    /// Proposes migrating the vault to a new staking mode, waits for the config
+    /// update delay to pass and then executes the proposal.
+    pub fn update_staking_mode_with_timelock(
+        &self,
+        staking_mode: StakingModeUnchecked,
+        signer: &SigningAccount,
+    ) -> &Self {
         self.wasm()
             .execute(
                 &self.vault_addr,
                 &ExecuteMsg::VaultExtension(ExtensionExecuteMsg::Apollo(
-                    ApolloExtensionExecuteMsg::Compound {},
+                    ApolloExtensionExecuteMsg::ProposeConfigUpdate {
+                        updates: ConfigUpdates::default(),
+                        staking_mode: Some(staking_mode),
+                        pool: None,
+                    },
                 )),
                 &[],
                 signer,
             )
             .unwrap();
-        self
+        let proposal = self.query_config_update_proposals().pop().unwrap();
+        self.increase_time(self.query_config().config_update_delay)
+            .execute_config_update(proposal.id, Unwrap::Ok, signer)
     }
 
-    /// Updates the contract's ownership
-    pub fn update_ownership(
+    /// Proposes migrating the vault's liquidity to a new pair and return a
+    /// reference to the robot.
+    pub fn propose_pool_migration(
         &self,
-        action: cw_ownable::Action,
+        new_pool_addr: impl Into<String>,
         unwrap_choice: Unwrap,
         signer: &SigningAccount,
     ) -> &Self {
-        let msg = ExecuteMsg::VaultExtension(ExtensionExecuteMsg::UpdateOwnership(action));
-        unwrap_choice.unwrap(self.wasm().execute(&self.vault_addr, &msg, &[], signer));
+        unwrap_choice.unwrap(self.wasm().execute(
+            &self.vault_addr,
+            &ExecuteMsg::VaultExtension(ExtensionExecuteMsg::Apollo(
+                ApolloExtensionExecuteMsg::ProposeConfigUpdate {
+                    updates: ConfigUpdates::default(),
+                    staking_mode: None,
+                    pool: Some(new_pool_addr.into()),
+                },
+            )),
+            &[],
+            signer,
+        ));
         self
     }
 
-    /// Increases the test runner's block time by the given number of seconds
-    pub fn increase_time(&self, seconds: u64) -> &Self {
-        self.runner.increase_time(seconds).unwrap();
+    /// Executes a proposed pool migration with the given minimum amounts and
+    /// return a reference to the robot.
+    pub fn execute_pool_migration(
+        &self,
+        proposal_id: u64,
+        min_out: Option<PoolMigrationMinOut>,
+        unwrap_choice: Unwrap,
+        signer: &SigningAccount,
+    ) -> &Self {
+        unwrap_choice.unwrap(self.wasm().execute(
+            &self.vault_addr,
+            &ExecuteMsg::VaultExtension(ExtensionExecuteMsg::Apollo(
+                ApolloExtensionExecuteMsg::ExecuteConfigUpdate {
+                    proposal_id,
+                    pool_migration_min_out: min_out,
+                },
+            )),
+            &[],
+            signer,
+        ));
         self
     }
 
-    pub fn increase_time_by_lockup_duration(&self) -> &Self {
-        let seconds = match self.query_lockup_duration() {
-            Duration::Time(s) => s,
-            Duration::Height(_) => panic!("Expected time duration, got height duration"),
-        };
-        self.increase_time(seconds);
-        self
+    /// Proposes migrating the vault's liquidity to a new pair, waits for the
+    /// config update delay to pass and then executes the proposal with the
+    /// given minimum amounts.
+    pub fn migrate_pool_with_timelock(
+        &self,
+        new_pool_addr: impl Into<String>,
+        min_out: PoolMigrationMinOut,
+        signer: &SigningAccount,
+    ) -> &Self {
+        self.propose_pool_migration(new_pool_addr, Unwrap::Ok, signer);
+        let proposal = self.query_config_update_proposals().pop().unwrap();
+        self.increase_time(self.query_config().config_update_delay)
+            .execute_pool_migration(proposal.id, Some(min_out), Unwrap::Ok, signer)
     }
 
-    // Queries //
-
-    /// Queries the ownership info of the vault
-    pub fn query_ownership(&self) -> Ownership<Addr> {
+    /// Creates a stableswap WSTETH/ETH pair and returns its address. The
+    /// factory only allows one pair per set of assets, so the existing
+    /// WSTETH/ETH pair is deregistered first. The deregistered pair keeps
+    /// working. Stableswap pairs require the precisions of native denoms to be
+    /// in the coin registry.
+    pub fn create_wsteth_eth_stable_pair(&self, signer: &SigningAccount) -> String {
+        let asset_infos = [
+            asset_info_to_astroport_v5_assetinfo(&AssetInfo::native(WSTETH_DENOM)),
+            asset_info_to_astroport_v5_assetinfo(&AssetInfo::native(ETH_DENOM)),
+        ];
         self.wasm()
-            .query::<_, cw_ownable::Ownership<Addr>>(
-                &self.vault_addr,
-                &QueryMsg::VaultExtension(ExtensionQueryMsg::Apollo(
-                    ApolloExtensionQueryMsg::Ownership {},
-                )),
+            .execute(
+                &self.astroport_contracts.factory.address,
+                &FactoryExecuteMsg::Deregister {
+                    asset_infos: asset_infos.to_vec(),
+                },
+                &[],
+                signer,
             )
-            .unwrap()
+            .unwrap();
+        self.add_denom_precision_to_coin_registry(WSTETH_DENOM, 6, signer)
+            .add_denom_precision_to_coin_registry(ETH_DENOM, 6, signer);
+        let (pair_addr, _lp_token) = create_astroport_pair(
+            self.runner,
+            &self.astroport_contracts.factory.address,
+            PairType::Stable {},
+            asset_infos,
+            Some(
+                to_json_binary(&StablePoolParams {
+                    amp: 10,
+                    owner: None,
+                })
+                .unwrap(),
+            ),
+            signer,
+            Some([Uint128::from(INITIAL_LIQ), Uint128::from(INITIAL_LIQ)]),
+            &[Coin::from_str(DENOM_CREATION_FEE).unwrap()],
+        );
+        pair_addr
     }
 
-    /// Queries the contract version info of the vault
-    pub fn query_contract_version(&self) -> cw2::ContractVersion {
-        self.wasm()
-            .query::<_, cw2::ContractVersion>(
-                &self.vault_addr,
-                &QueryMsg::VaultExtension(ExtensionQueryMsg::Apollo(
-                    ApolloExtensionQueryMsg::ContractVersion {},
-                )),
-            )
-            .unwrap()
+    /// Calls `ExecuteMsg::Redeem` to redeem vault tokens from the vault
+    pub fn redeem(
+        &self,
+        amount: Uint128,
+        recipient: Option<String>,
+        unwrap_choice: Unwrap,
+        funds: Option<Vec<Coin>>,
+        signer: &SigningAccount,
+    ) -> &Self {
+        unwrap_choice.unwrap(self.wasm().execute(
+            &self.vault_addr,
+            &ExecuteMsg::Redeem { amount, recipient },
+            &funds.unwrap_or_else(|| coins(amount.u128(), self.vault_token())),
+            signer,
+        ));
+        self
     }
 
-    /// Queries all the unlocking positions of the given owner
-    pub fn query_unlocking_positions(&self, owner: &str) -> Vec<UnlockingPosition> {
+    /// Compounds the rewards in the vault
+    pub fn compound_vault(&self, signer: &SigningAccount) -> &Self {
         self.wasm()
-            .query::<_, Vec<UnlockingPosition>>(
+            .execute(
                 &self.vault_addr,
-                &QueryMsg::VaultExtension(ExtensionQueryMsg::Lockup(
-                    LockupQueryMsg::UnlockingPositions {
-                        owner: owner.to_string(),
-                        start_after: None,
-                        limit: None,
-                    },
+                &ExecuteMsg::VaultExtension(ExtensionExecuteMsg::Apollo(
+                    ApolloExtensionExecuteMsg::Compound {},
                 )),
+                &[],
+                signer,
             )
-            .unwrap()
+            .unwrap();
+        self
     }
 
-    /// Queries the config of the vault
-    pub fn query_config(&self) -> ConfigBase<Addr> {
-        self.wasm()
-            .query::<_, Config>(
-                &self.vault_addr,
-                &QueryMsg::VaultExtension(ExtensionQueryMsg::Apollo(
-                    ApolloExtensionQueryMsg::Config {},
+    /// Sells idle balances up to the dust threshold into the reward
+    /// liquidation target and return a reference to the robot.
+    pub fn sweep_dust(&self, unwrap_choice: Unwrap, signer: &SigningAccount) -> &Self {
+        unwrap_choice.unwrap(self.wasm().execute(
+            &self.vault_addr,
+            &ExecuteMsg::VaultExtension(ExtensionExecuteMsg::Apollo(
+                ApolloExtensionExecuteMsg::SweepDust {},
+            )),
+            &[],
+            signer,
+        ));
+        self
+    }
+
+    /// Withdraws all matured unlocking positions of `signer` and return a
+    /// reference to the robot.
+    pub fn withdraw_all_unlocked(
+        &self,
+        recipient: Option<String>,
+        limit: Option<u32>,
+        unwrap_choice: Unwrap,
+        signer: &SigningAccount,
+    ) -> &Self {
+        unwrap_choice.unwrap(self.wasm().execute(
+            &self.vault_addr,
+            &ExecuteMsg::VaultExtension(ExtensionExecuteMsg::Apollo(
+                ApolloExtensionExecuteMsg::WithdrawAllUnlocked { recipient, limit },
+            )),
+            &[],
+            signer,
+        ));
+        self
+    }
+
+    /// Calls `LockupExecuteMsg::EmergencyUnlock` to withdraw the base tokens of
+    /// `amount` vault tokens immediately, minus the emergency unlock penalty
+    pub fn emergency_unlock(
+        &self,
+        amount: Uint128,
+        unwrap_choice: Unwrap,
+        signer: &SigningAccount,
+    ) -> &Self {
+        unwrap_choice.unwrap(self.wasm().execute(
+            &self.vault_addr,
+            &ExecuteMsg::VaultExtension(ExtensionExecuteMsg::Lockup(
+                LockupExecuteMsg::EmergencyUnlock { amount },
+            )),
+            &coins(amount.u128(), self.vault_token()),
+            signer,
+        ));
+        self
+    }
+
+    /// Withdraws an unlocking position of `signer` before it has matured and
+    /// return a reference to the robot.
+    pub fn emergency_withdraw_unlocking(
+        &self,
+        lockup_id: u64,
+        recipient: Option<String>,
+        unwrap_choice: Unwrap,
+        signer: &SigningAccount,
+    ) -> &Self {
+        unwrap_choice.unwrap(self.wasm().execute(
+            &self.vault_addr,
+            &ExecuteMsg::VaultExtension(ExtensionExecuteMsg::Apollo(
+                ApolloExtensionExecuteMsg::EmergencyWithdrawUnlocking {
+                    lockup_id,
+                    recipient,
+                },
+            )),
+            &[],
+            signer,
+        ));
+        self
+    }
+
+    /// Cancels an unlocking position of `signer` and return a reference to the
+    /// robot.
+    pub fn cancel_unlock(
+        &self,
+        lockup_id: u64,
+        amount: Option<Uint128>,
+        unwrap_choice: Unwrap,
+        signer: &SigningAccount,
+    ) -> &Self {
+        unwrap_choice.unwrap(self.wasm().execute(
+            &self.vault_addr,
+            &ExecuteMsg::VaultExtension(ExtensionExecuteMsg::Apollo(
+                ApolloExtensionExecuteMsg::CancelUnlock { lockup_id, amount },
+            )),
+            &[],
+            signer,
+        ));
+        self
+    }
+
+    /// Transfers an unlocking position of `signer` to `new_owner` and return a
+    /// reference to the robot.
+    pub fn transfer_unlocking_position(
+        &self,
+        lockup_id: u64,
+        new_owner: impl Into<String>,
+        unwrap_choice: Unwrap,
+        signer: &SigningAccount,
+    ) -> &Self {
+        unwrap_choice.unwrap(self.wasm().execute(
+            &self.vault_addr,
+            &ExecuteMsg::VaultExtension(ExtensionExecuteMsg::Apollo(
+                ApolloExtensionExecuteMsg::TransferUnlockingPosition {
+                    lockup_id,
+                    new_owner: new_owner.into(),
+                },
+            )),
+            &[],
+            signer,
+        ));
+        self
+    }
+
+    /// Splits `amount` off an unlocking position of `signer` and return a
+    /// reference to the robot.
+    pub fn split_unlocking_position(
+        &self,
+        lockup_id: u64,
+        amount: Uint128,
+        new_owner: Option<String>,
+        unwrap_choice: Unwrap,
+        signer: &SigningAccount,
+    ) -> &Self {
+        unwrap_choice.unwrap(self.wasm().execute(
+            &self.vault_addr,
+            &ExecuteMsg::VaultExtension(ExtensionExecuteMsg::Apollo(
+                ApolloExtensionExecuteMsg::SplitUnlockingPosition {
+                    lockup_id,
+                    amount,
+                    new_owner,
+                },
+            )),
+            &[],
+            signer,
+        ));
+        self
+    }
+
+    /// Donates base tokens to the vault and return a reference to the robot.
+    /// Cw20 base tokens are donated with `ReceiveMsg::Donate`.
+    pub fn donate(&self, amount: Uint128, unwrap_choice: Unwrap, signer: &SigningAccount) -> &Self {
+        if self.base_token_is_cw20 {
+            unwrap_choice.unwrap(self.wasm().execute(
+                &self.base_token(),
+                &Cw20ExecuteMsg::Send {
+                    contract: self.vault_addr.clone(),
+                    amount,
+                    msg: to_json_binary(&ReceiveMsg::Donate {}).unwrap(),
+                },
+                &[],
+                signer,
+            ));
+        } else {
+            unwrap_choice.unwrap(self.wasm().execute(
+                &self.vault_addr,
+                &ExecuteMsg::VaultExtension(ExtensionExecuteMsg::Apollo(
+                    ApolloExtensionExecuteMsg::Donate {},
+                )),
+                &coins(amount.u128(), self.base_token()),
+                signer,
+            ));
+        }
+        self
+    }
+
+    /// Deposits `amount` native base tokens into the lock tier `lock_tier` and
+    /// return a reference to the robot.
+    pub fn deposit_to_lock_tier(
+        &self,
+        lock_tier: u32,
+        amount: Uint128,
+        recipient: Option<String>,
+        unwrap_choice: Unwrap,
+        signer: &SigningAccount,
+    ) -> &Self {
+        unwrap_choice.unwrap(self.wasm().execute(
+            &self.vault_addr,
+            &ExecuteMsg::VaultExtension(ExtensionExecuteMsg::Apollo(
+                ApolloExtensionExecuteMsg::DepositToLockTier {
+                    lock_tier,
+                    amount,
+                    recipient,
+                },
+            )),
+            &coins(amount.u128(), self.base_token()),
+            signer,
+        ));
+        self
+    }
+
+    /// Unlocks `amount` shares of the lock tier `lock_tier` owned by `signer`
+    /// and return a reference to the robot.
+    pub fn unlock_from_lock_tier(
+        &self,
+        lock_tier: u32,
+        amount: Uint128,
+        unwrap_choice: Unwrap,
+        signer: &SigningAccount,
+    ) -> &Self {
+        unwrap_choice.unwrap(self.wasm().execute(
+            &self.vault_addr,
+            &ExecuteMsg::VaultExtension(ExtensionExecuteMsg::Apollo(
+                ApolloExtensionExecuteMsg::UnlockFromLockTier { lock_tier, amount },
+            )),
+            &[],
+            signer,
+        ));
+        self
+    }
+
+    /// Shuts the vault down and return a reference to the robot.
+    pub fn shutdown(&self, unwrap_choice: Unwrap, signer: &SigningAccount) -> &Self {
+        unwrap_choice.unwrap(self.wasm().execute(
+            &self.vault_addr,
+            &ExecuteMsg::VaultExtension(ExtensionExecuteMsg::Apollo(
+                ApolloExtensionExecuteMsg::Shutdown {},
+            )),
+            &[],
+            signer,
+        ));
+        self
+    }
+
+    /// Returns the address of the unlocking position NFT contract. Panics if
+    /// unlocking positions are not NFTs.
+    pub fn unlocking_position_nft(&self) -> Addr {
+        self.query_state()
+            .unlocking_position_nft
+            .expect("Unlocking positions are not NFTs")
+    }
+
+    /// Sends the NFT of the unlocking position with id `lockup_id` to the vault
+    /// with `msg` and return a reference to the robot.
+    pub fn send_unlocking_position_nft(
+        &self,
+        lockup_id: u64,
+        msg: ReceiveNftMsg,
+        unwrap_choice: Unwrap,
+        signer: &SigningAccount,
+    ) -> &Self {
+        unwrap_choice.unwrap(self.wasm().execute(
+            self.unlocking_position_nft().as_str(),
+            &cw721_base::ExecuteMsg::<Option<Empty>, Empty>::SendNft {
+                contract: self.vault_addr.clone(),
+                token_id: lockup_id.to_string(),
+                msg: to_json_binary(&msg).unwrap(),
+            },
+            &[],
+            signer,
+        ));
+        self
+    }
+
+    /// Transfers the NFT of the unlocking position with id `lockup_id` to
+    /// `recipient` and return a reference to the robot.
+    pub fn transfer_unlocking_position_nft(
+        &self,
+        lockup_id: u64,
+        recipient: impl Into<String>,
+        signer: &SigningAccount,
+    ) -> &Self {
+        self.wasm()
+            .execute(
+                self.unlocking_position_nft().as_str(),
+                &cw721_base::ExecuteMsg::<Option<Empty>, Empty>::TransferNft {
+                    recipient: recipient.into(),
+                    token_id: lockup_id.to_string(),
+                },
+                &[],
+                signer,
+            )
+            .unwrap();
+        self
+    }
+
+    /// Queries the owner of the NFT of the unlocking position with id
+    /// `lockup_id`.
+    pub fn query_unlocking_position_nft_owner(&self, lockup_id: u64) -> StdResult<String> {
+        self.wasm()
+            .query::<_, cw721::OwnerOfResponse>(
+                self.unlocking_position_nft().as_str(),
+                &cw721_base::QueryMsg::<Empty>::OwnerOf {
+                    token_id: lockup_id.to_string(),
+                    include_expired: None,
+                },
+            )
+            .map(|res| res.owner)
+            .map_err(|e| StdError::generic_err(e.to_string()))
+    }
+
+    /// Sends cw20 vault tokens to the vault with `msg` and return a reference
+    /// to the robot. Panics if the vault token is not a cw20 token.
+    pub fn send_cw20_vault_tokens(
+        &self,
+        amount: impl Into<Uint128>,
+        msg: ReceiveMsg,
+        unwrap_choice: Unwrap,
+        signer: &SigningAccount,
+    ) -> &Self {
+        let VaultToken::Cw20 { addr } = self.query_state().vault_token else {
+            panic!("Vault token is not a cw20 token");
+        };
+        unwrap_choice.unwrap(self.wasm().execute(
+            addr.as_str(),
+            &Cw20ExecuteMsg::Send {
+                contract: self.vault_addr.clone(),
+                amount: amount.into(),
+                msg: to_json_binary(&msg).unwrap(),
+            },
+            &[],
+            signer,
+        ));
+        self
+    }
+
+    /// Queries the cw20 vault token balance of `address`. Panics if the vault
+    /// token is not a cw20 token.
+    pub fn query_cw20_vault_token_balance(&self, address: impl Into<String>) -> Uint128 {
+        let VaultToken::Cw20 { addr } = self.query_state().vault_token else {
+            panic!("Vault token is not a cw20 token");
+        };
+        self.wasm()
+            .query::<_, cw20::BalanceResponse>(
+                addr.as_str(),
+                &Cw20QueryMsg::Balance {
+                    address: address.into(),
+                },
+            )
+            .unwrap()
+            .balance
+    }
+
+    /// Sets the bank metadata of the vault token and return a reference to the
+    /// robot.
+    pub fn set_vault_token_metadata(
+        &self,
+        metadata: VaultTokenMetadata,
+        unwrap_choice: Unwrap,
+        signer: &SigningAccount,
+    ) -> &Self {
+        unwrap_choice.unwrap(self.wasm().execute(
+            &self.vault_addr,
+            &ExecuteMsg::VaultExtension(ExtensionExecuteMsg::Apollo(
+                ApolloExtensionExecuteMsg::SetVaultTokenMetadata { metadata },
+            )),
+            &[],
+            signer,
+        ));
+        self
+    }
+
+    /// Rescues tokens that were accidentally sent to the vault and return a
+    /// reference to the robot.
+    pub fn rescue_tokens(
+        &self,
+        assets: Vec<AssetUnchecked>,
+        recipient: impl Into<String>,
+        unwrap_choice: Unwrap,
+        signer: &SigningAccount,
+    ) -> &Self {
+        unwrap_choice.unwrap(self.wasm().execute(
+            &self.vault_addr,
+            &ExecuteMsg::VaultExtension(ExtensionExecuteMsg::Apollo(
+                ApolloExtensionExecuteMsg::RescueTokens {
+                    assets,
+                    recipient: recipient.into(),
+                },
+            )),
+            &[],
+            signer,
+        ));
+        self
+    }
+
+    /// Incentivizes stakers of the vault's base token in the Astroport
+    /// incentives contract with `reward` for `duration_periods` epochs.
+    pub fn incentivize(
+        &self,
+        reward: Coin,
+        duration_periods: u64,
+        signer: &SigningAccount,
+    ) -> &Self {
+        self.wasm()
+            .execute(
+                &self.astroport_contracts().incentives.address,
+                &IncentivesExecuteMsg::Incentivize {
+                    lp_token: self.base_token(),
+                    schedule: InputSchedule {
+                        reward: AstroAsset::native(&reward.denom, reward.amount),
+                        duration_periods,
+                    },
+                },
+                &[reward],
+                signer,
+            )
+            .unwrap();
+        self
+    }
+
+    /// Proposes a config update that appends newly incentivized reward tokens
+    /// to the vault's config
+    pub fn discover_reward_tokens(&self, unwrap_choice: Unwrap, signer: &SigningAccount) -> &Self {
+        unwrap_choice.unwrap(self.wasm().execute(
+            &self.vault_addr,
+            &ExecuteMsg::VaultExtension(ExtensionExecuteMsg::Apollo(
+                ApolloExtensionExecuteMsg::DiscoverRewardTokens {},
+            )),
+            &[],
+            signer,
+        ));
+        self
+    }
+
+    /// Updates the contract's ownership
+    pub fn update_ownership(
+        &self,
+        action: cw_ownable::Action,
+        unwrap_choice: Unwrap,
+        signer: &SigningAccount,
+    ) -> &Self {
+        let msg = ExecuteMsg::VaultExtension(ExtensionExecuteMsg::UpdateOwnership(action));
+        unwrap_choice.unwrap(self.wasm().execute(&self.vault_addr, &msg, &[], signer));
+        self
+    }
+
+    /// Increases the test runner's block time by the given number of seconds
+    pub fn increase_time(&self, seconds: u64) -> &Self {
+        self.runner.increase_time(seconds).unwrap();
+        self
+    }
+
+    pub fn increase_time_by_lockup_duration(&self) -> &Self {
+        match self.query_lockup_duration() {
+            Duration::Time(seconds) => self.increase_time(seconds),
+            Duration::Height(blocks) => self.increase_blocks(blocks),
+        }
+    }
+
+    /// Produces `blocks` new blocks, each `BLOCK_TIME_IN_SECS` seconds after
+    /// the previous one
+    pub fn increase_blocks(&self, blocks: u64) -> &Self {
+        // Every call to `increase_time` produces exactly one block
+        for _ in 0..blocks {
+            self.increase_time(BLOCK_TIME_IN_SECS);
+        }
+        self
+    }
+
+    // Queries //
+
+    /// Queries the ownership info of the vault
+    pub fn query_ownership(&self) -> Ownership<Addr> {
+        self.wasm()
+            .query::<_, cw_ownable::Ownership<Addr>>(
+                &self.vault_addr,
+                &QueryMsg::VaultExtension(ExtensionQueryMsg::Apollo(
+                    ApolloExtensionQueryMsg::Ownership {},
+                )),
+            )
+            .unwrap()
+    }
+
+    /// Queries the contract version info of the vault
+    pub fn query_contract_version(&self) -> cw2::ContractVersion {
+        self.wasm()
+            .query::<_, cw2::ContractVersion>(
+                &self.vault_addr,
+                &QueryMsg::VaultExtension(ExtensionQueryMsg::Apollo(
+                    ApolloExtensionQueryMsg::ContractVersion {},
+                )),
+            )
+            .unwrap()
+    }
+
+    /// Queries all the unlocking positions of the given owner
+    pub fn query_unlocking_positions(&self, owner: &str) -> Vec<UnlockingPosition> {
+        self.wasm()
+            .query::<_, Vec<UnlockingPosition>>(
+                &self.vault_addr,
+                &QueryMsg::VaultExtension(ExtensionQueryMsg::Lockup(
+                    LockupQueryMsg::UnlockingPositions {
+                        owner: owner.to_string(),
+                        start_after: None,
+                        limit: None,
+                    },
+                )),
+            )
+            .unwrap()
+    }
+
+    /// Queries the config of the vault
+    pub fn query_config(&self) -> ConfigBase<Addr> {
+        self.wasm()
+            .query::<_, Config>(
+                &self.vault_addr,
+                &QueryMsg::VaultExtension(ExtensionQueryMsg::Apollo(
+                    ApolloExtensionQueryMsg::Config {},
+                )),
+            )
+            .unwrap()
+    }
+
+    /// Queries the config that would result from applying `updates`
+    pub fn query_validate_config_update(
+        &self,
+        updates: ConfigUpdates<String>,
+    ) -> ValidateConfigUpdateResponse {
+        self.wasm()
+            .query::<_, ValidateConfigUpdateResponse>(
+                &self.vault_addr,
+                &QueryMsg::VaultExtension(ExtensionQueryMsg::Apollo(
+                    ApolloExtensionQueryMsg::ValidateConfigUpdate { updates },
+                )),
+            )
+            .unwrap()
+    }
+
+    /// Queries the balances that the vault holds outside of its staked LP
+    /// tokens
+    pub fn query_idle_balances(&self) -> Vec<Asset> {
+        self.wasm()
+            .query(
+                &self.vault_addr,
+                &QueryMsg::VaultExtension(ExtensionQueryMsg::Apollo(
+                    ApolloExtensionQueryMsg::IdleBalances {},
+                )),
+            )
+            .unwrap()
+    }
+
+    /// Queries the cumulative amounts of base tokens donated to the vault
+    pub fn query_donations(&self) -> Donations {
+        self.wasm()
+            .query(
+                &self.vault_addr,
+                &QueryMsg::VaultExtension(ExtensionQueryMsg::Apollo(
+                    ApolloExtensionQueryMsg::Donations {},
+                )),
+            )
+            .unwrap()
+    }
+
+    /// Queries all unlocking positions of the vault
+    pub fn query_all_unlocking_positions(
+        &self,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> Vec<UnlockingPosition> {
+        self.wasm()
+            .query(
+                &self.vault_addr,
+                &QueryMsg::VaultExtension(ExtensionQueryMsg::Apollo(
+                    ApolloExtensionQueryMsg::AllUnlockingPositions { start_after, limit },
+                )),
+            )
+            .unwrap()
+    }
+
+    /// Queries the unlocking positions that are released at or after `from`
+    /// and before `to`
+    pub fn query_unlocking_positions_released_between(
+        &self,
+        from: Expiration,
+        to: Expiration,
+        start_after: Option<(Expiration, u64)>,
+        limit: Option<u32>,
+    ) -> Vec<UnlockingPosition> {
+        self.wasm()
+            .query(
+                &self.vault_addr,
+                &QueryMsg::VaultExtension(ExtensionQueryMsg::Apollo(
+                    ApolloExtensionQueryMsg::UnlockingPositionsReleasedBetween {
+                        from,
+                        to,
+                        start_after,
+                        limit,
+                    },
+                )),
+            )
+            .unwrap()
+    }
+
+    /// Queries the total amount of base tokens in all unlocking positions
+    pub fn query_total_unlocking(&self) -> Uint128 {
+        self.wasm()
+            .query(
+                &self.vault_addr,
+                &QueryMsg::VaultExtension(ExtensionQueryMsg::Apollo(
+                    ApolloExtensionQueryMsg::TotalUnlocking {},
+                )),
+            )
+            .unwrap()
+    }
+
+    /// Queries all lock tiers of the vault
+    pub fn query_lock_tiers(&self) -> Vec<LockTierResponse> {
+        self.wasm()
+            .query(
+                &self.vault_addr,
+                &QueryMsg::VaultExtension(ExtensionQueryMsg::Apollo(
+                    ApolloExtensionQueryMsg::LockTiers {},
+                )),
+            )
+            .unwrap()
+    }
+
+    /// Queries the shares of the lock tiers that `address` owns
+    pub fn query_lock_tier_positions(
+        &self,
+        address: impl Into<String>,
+    ) -> Vec<LockTierPositionResponse> {
+        self.wasm()
+            .query(
+                &self.vault_addr,
+                &QueryMsg::VaultExtension(ExtensionQueryMsg::Apollo(
+                    ApolloExtensionQueryMsg::LockTierPositions {
+                        address: address.into(),
+                    },
+                )),
+            )
+            .unwrap()
+    }
+
+    /// Queries the position of `address` in the vault
+    pub fn query_user_position(&self, address: impl Into<String>) -> UserPositionResponse {
+        self.wasm()
+            .query(
+                &self.vault_addr,
+                &QueryMsg::VaultExtension(ExtensionQueryMsg::Apollo(
+                    ApolloExtensionQueryMsg::UserPosition {
+                        address: address.into(),
+                    },
+                )),
+            )
+            .unwrap()
+    }
+
+    /// Queries the rewards that are pending to be compounded by the vault
+    pub fn query_pending_rewards(&self) -> PendingRewardsResponse {
+        self.wasm()
+            .query::<_, PendingRewardsResponse>(
+                &self.vault_addr,
+                &QueryMsg::VaultExtension(ExtensionQueryMsg::Apollo(
+                    ApolloExtensionQueryMsg::PendingRewards {},
+                )),
+            )
+            .unwrap()
+    }
+
+    /// Queries the estimated result of compounding the vault
+    pub fn query_simulate_compound(&self) -> SimulateCompoundResponse {
+        self.wasm()
+            .query::<_, SimulateCompoundResponse>(
+                &self.vault_addr,
+                &QueryMsg::VaultExtension(ExtensionQueryMsg::Apollo(
+                    ApolloExtensionQueryMsg::SimulateCompound {},
+                )),
+            )
+            .unwrap()
+    }
+
+    /// Queries the pending config update proposals of the vault
+    pub fn query_config_update_proposals(&self) -> Vec<ConfigUpdateProposal> {
+        self.wasm()
+            .query::<_, Vec<ConfigUpdateProposal>>(
+                &self.vault_addr,
+                &QueryMsg::VaultExtension(ExtensionQueryMsg::Apollo(
+                    ApolloExtensionQueryMsg::ConfigUpdateProposals {
+                        start_after: None,
+                        limit: Some(u32::MAX),
+                    },
                 )),
             )
             .unwrap()
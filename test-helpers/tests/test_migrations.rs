@@ -0,0 +1,203 @@
+use apollo_cw_asset::AssetInfo;
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::testing::{mock_dependencies, mock_env};
+use cosmwasm_std::{to_json_vec, Addr, Decimal, Order, Storage, Uint128};
+use cw20::Expiration;
+use cw_dex_astroport::AstroportStaking;
+use cw_utils::Duration;
+use locked_astroport_vault::contract::{migrate, CONTRACT_NAME, CONTRACT_VERSION};
+use locked_astroport_vault::error::ContractError;
+use locked_astroport_vault::msg::MigrateMsg;
+use locked_astroport_vault::state::{
+    self, EmergencyUnlockPenalty, FeeConfig, StakingMode, TokenFactoryFlavor, VaultToken, CONFIG,
+    LEGACY_STAKING, LEGACY_VAULT_TOKEN_DENOM, STAKING, VAULT_TOKEN,
+};
+
+/// The config as stored by version 0.5.0 of the contract.
+#[cw_serde]
+struct ConfigV0_5_0 {
+    lock_duration: Duration,
+    reward_tokens: Vec<AssetInfo>,
+    deposits_enabled: bool,
+    router: Addr,
+    reward_liquidation_target: AssetInfo,
+    liquidity_helper: Addr,
+    performance_fee: FeeConfig<Addr>,
+    deposit_fee: FeeConfig<Addr>,
+    withdrawal_fee: FeeConfig<Addr>,
+}
+
+const CLAIM_AMOUNTS: [u128; 2] = [100, 250];
+
+const VAULT_TOKEN_DENOM: &str = "factory/vault/vault_token";
+
+fn legacy_staking() -> AstroportStaking {
+    AstroportStaking {
+        lp_token: AssetInfo::native("factory/pair/astroport/share"),
+        incentives: Addr::unchecked("incentives"),
+    }
+}
+
+/// Writes the storage layout of version 0.5.0 of the contract.
+fn store_v0_5_0_layout(storage: &mut dyn Storage) {
+    cw2::set_contract_version(storage, CONTRACT_NAME, "0.5.0").unwrap();
+
+    let fee = FeeConfig {
+        fee_rate: Decimal::percent(5),
+        fee_recipients: vec![(Addr::unchecked("treasury"), Decimal::one())],
+    };
+    let config = ConfigV0_5_0 {
+        lock_duration: Duration::Time(86_400),
+        reward_tokens: vec![AssetInfo::native("uastro")],
+        deposits_enabled: true,
+        router: Addr::unchecked("router"),
+        reward_liquidation_target: AssetInfo::native("ueth"),
+        liquidity_helper: Addr::unchecked("liquidity_helper"),
+        performance_fee: fee.clone(),
+        deposit_fee: fee.clone(),
+        withdrawal_fee: fee,
+    };
+    storage.set(b"config", &to_json_vec(&config).unwrap());
+
+    LEGACY_STAKING.save(storage, &legacy_staking()).unwrap();
+    LEGACY_VAULT_TOKEN_DENOM
+        .save(storage, &VAULT_TOKEN_DENOM.to_string())
+        .unwrap();
+
+    // Version 0.5.0 did not store the total amount of base tokens in claims
+    for amount in CLAIM_AMOUNTS {
+        state::claims()
+            .create_claim(
+                storage,
+                &Addr::unchecked("user"),
+                Uint128::new(amount),
+                Expiration::AtHeight(100),
+                &mock_env().block,
+            )
+            .unwrap();
+    }
+    storage.remove(b"claims_total");
+
+    // Version 0.5.0 did not index claims by their release
+    let namespace = b"claims_release_at_index";
+    let mut prefix = (namespace.len() as u16).to_be_bytes().to_vec();
+    prefix.extend_from_slice(namespace);
+    let index_keys: Vec<Vec<u8>> = storage
+        .range(None, None, Order::Ascending)
+        .map(|(k, _)| k)
+        .filter(|k| k.starts_with(&prefix))
+        .collect();
+    assert!(!index_keys.is_empty());
+    for key in index_keys {
+        storage.remove(&key);
+    }
+}
+
+#[test]
+fn migrate_from_v0_5_0_layout() {
+    let mut deps = mock_dependencies();
+    store_v0_5_0_layout(&mut deps.storage);
+
+    let res = migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg {
+            config_update_delay: Some(86_400),
+        },
+    )
+    .unwrap();
+    assert!(res.events[0]
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "applied_migrations" && attr.value == "0.6.0"));
+
+    assert_eq!(
+        STAKING.load(&deps.storage).unwrap(),
+        StakingMode::Astroport(legacy_staking())
+    );
+    assert!(LEGACY_STAKING.may_load(&deps.storage).unwrap().is_none());
+    assert_eq!(
+        VAULT_TOKEN.load(&deps.storage).unwrap(),
+        VaultToken::TokenFactory {
+            denom: VAULT_TOKEN_DENOM.to_string(),
+            flavor: TokenFactoryFlavor::Osmosis,
+        }
+    );
+    assert!(LEGACY_VAULT_TOKEN_DENOM
+        .may_load(&deps.storage)
+        .unwrap()
+        .is_none());
+    assert_eq!(
+        state::claims().query_total_amount(&deps.storage).unwrap(),
+        Uint128::new(CLAIM_AMOUNTS.iter().sum())
+    );
+    let released = state::claims()
+        .query_claims_by_release(
+            deps.as_ref(),
+            Expiration::AtHeight(0),
+            Expiration::AtHeight(101),
+            None,
+            None,
+        )
+        .unwrap();
+    assert_eq!(released.len(), CLAIM_AMOUNTS.len());
+    let config = CONFIG.load(&deps.storage).unwrap();
+    assert_eq!(config.config_update_delay, 86_400);
+    assert!(!config.reject_implicit_donations);
+    assert_eq!(
+        config.emergency_unlock_penalty,
+        EmergencyUnlockPenalty::Disabled
+    );
+    assert_eq!(
+        cw2::get_contract_version(&deps.storage).unwrap().version,
+        CONTRACT_VERSION
+    );
+}
+
+#[test]
+fn migrations_are_idempotent() {
+    let mut deps = mock_dependencies();
+    store_v0_5_0_layout(&mut deps.storage);
+
+    let msg = MigrateMsg {
+        config_update_delay: Some(86_400),
+    };
+    migrate(deps.as_mut(), mock_env(), msg.clone()).unwrap();
+    let config = CONFIG.load(&deps.storage).unwrap();
+    let staking = STAKING.load(&deps.storage).unwrap();
+    let vault_token = VAULT_TOKEN.load(&deps.storage).unwrap();
+    let claims_total = state::claims().query_total_amount(&deps.storage).unwrap();
+
+    // Migrating again without parameters should not apply any migrations or
+    // change the storage
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg::default()).unwrap();
+    assert!(!res.events[0]
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "applied_migrations"));
+    assert_eq!(CONFIG.load(&deps.storage).unwrap(), config);
+    assert_eq!(STAKING.load(&deps.storage).unwrap(), staking);
+    assert_eq!(VAULT_TOKEN.load(&deps.storage).unwrap(), vault_token);
+    assert_eq!(
+        state::claims().query_total_amount(&deps.storage).unwrap(),
+        claims_total
+    );
+}
+
+#[test]
+fn migrate_from_v0_5_0_requires_config_update_delay() {
+    let mut deps = mock_dependencies();
+    store_v0_5_0_layout(&mut deps.storage);
+
+    let err = migrate(deps.as_mut(), mock_env(), MigrateMsg::default()).unwrap_err();
+    assert!(matches!(err, ContractError::ConfigUpdateDelayRequired {}));
+}
+
+#[test]
+fn can_not_migrate_from_newer_version() {
+    let mut deps = mock_dependencies();
+    store_v0_5_0_layout(&mut deps.storage);
+    cw2::set_contract_version(&mut deps.storage, CONTRACT_NAME, "99.0.0").unwrap();
+
+    migrate(deps.as_mut(), mock_env(), MigrateMsg::default()).unwrap_err();
+}